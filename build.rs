@@ -0,0 +1,4 @@
+fn main() {
+    // Migrations are embedded in the binary
+    println!("cargo:rerun-if-changed=migrations");
+}
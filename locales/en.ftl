@@ -0,0 +1,240 @@
+# Messages of the bot in English, falling back to the French catalog when missing
+
+## Errors
+
+error-internal = Internal error
+error-no-participant = ⁉️ But nobody has the hand...
+error-not-your-turn = ❌ Tut tut tut, it's not your turn...
+error-you-posted-no-pic = 🤦 Hrmpf, you didn't post any picture...
+error-stfu-bot = 🤖 Shut up, bot!
+error-pic-already-posted = 🦜 You already posted a picture, buddy.
+error-invalid-page = Invalid page
+error-invalid-reset-id = Invalid reset ID
+error-unknown-arguments = Unknown arguments
+error-unknown-setting = Unknown setting
+error-invalid-setting-value = Invalid value for this setting
+error-unknown-season = Unknown season
+error-nothing-to-undo = 🤷 Nothing to undo
+error-undo-not-allowed = ❌ Only the author can undo, and not too long after. Ask a mod!
+error-next-picture-posted = ⏩ Too late, the next picture is already posted.
+error-missing-hint = 💡 Where's the hint?
+error-duplicate-picture = ♻️ Picture refused, it looks like a picture { $duplicate }. Find another one!
+error-unknown-locale = Unknown language, pick one of { $locales }
+error-missing-permissions = 🔒 You are not allowed to run this command.
+
+duplicate-found = already played on { $date }, found by { $finder }
+duplicate-never-found = already played on { $date }, never found
+
+## Game
+
+participation-new = 🔎 To your keyboards, a new picture is waiting to be found
+participation-duplicate = ♻️ Careful, it looks like a picture { $duplicate }
+
+skip = To your pictures, { $player } passes the hand!
+win-no-winner = { $player }, who's the winner?
+win-many-winners = Hey { $player }, have you lost your mind? One winner, only one!
+win-self = { $player } be like https://i.imgflip.com/12w3f0.jpg
+win-default = Well done { $winner }, your turn.
+win-speed = ⏱️ Found in { $duration }: { $points } { $count ->
+        [one] point
+       *[other] points
+    }
+win-poster = 📷 { $points } { $count ->
+        [one] point
+       *[other] points
+    } for { $poster } who posted the picture
+win-location = 📍 It was here: { $location }
+
+hint = 💡 Hint from { $player }: { $hint }
+hint-points = The picture is now only worth { $points } { $count ->
+        [one] point
+       *[other] points
+    }.
+
+undo-win = ↩️ Win of { $winner } undone, { $back }
+undo-skip = ↩️ Skip undone, { $back }
+undo-back = { $player } gets the hand back.
+undo-back-picture = { $player } gets the hand back with their picture.
+
+pic-not-posted = It's the turn of { $player }, who hasn't posted a picture yet.
+pic-hint = 💡 Hint { $number }
+change = Ok, ok, since you insist...
+
+autoskip-warning = ⏰ { $player } autoskip incoming!
+autoskip = Sorry { $player }, people suck, take your point off ¯\_(ツ)_/¯.
+
+## Scores
+
+scoreboard-title = 👑 👑 👑 Scores ({ $page }/{ $page_count }) 👑 👑 👑
+scoreboard-week-delta = { $delta } in 7 days
+
+season-none = No season yet in this channel
+season-list = Seasons:
+season-ended = - `{ $name }` from { $start } to { $end }
+season-current = - `{ $name }` since { $start }
+season-title = 🏆 Season { $name } ({ $page }/{ $page_count }) 🏆
+season-start = 🚀 Here we go for season { $name }!
+season-end = 🏁 End of season { $name }, congrats to the podium! 🏁
+season-end-empty = 🏁 End of season { $name }, without a single point scored...
+
+history-title = 📜 History ({ $page }/{ $page_count })
+history-empty = No picture played yet
+history-player = Pictures posted or found by { $player }
+history-picture = picture
+history-found = 🔎 Found by { $finder } in { $duration }
+history-auto-skipped = ⏰ Skipped automatically
+history-skipped = ⏭️ Skipped
+
+chart-title = 📈 Score evolution
+chart-title-reset = 📈 Score evolution until the reset of { $date }
+chart-empty = No points scored during this period
+
+stats-title = 📊 Stats of { $player }
+stats-finder = { $player } ({ $count ->
+        [one] once
+       *[other] { $count } times
+    })
+stats-nobody = Nobody
+stats-points = Points
+stats-finder-points = Points finding
+stats-poster-points = Points posting
+stats-total-points = Points since the beginning
+stats-pictures = Pictures posted
+stats-finds = Pictures found
+stats-skips = Skips
+stats-auto-skips = Auto-skips
+stats-average-find-time = Average time to find
+stats-longest-streak = Longest streak
+stats-favourite-finder = Finds their pictures the most
+
+duration-seconds = { $seconds }s
+duration-minutes = { $minutes }min { $seconds }s
+duration-hours = { $hours }h { $minutes }min
+duration-days = { $days }d { $hours }h
+
+## Administration
+
+reset-confirm = To confirm the reset, send `!reset do`.
+reset-done = Scores reset with ID { $id }
+reset-cancelled = Reset { $id } cancelled
+resets-title = 🔄 Resets ({ $page }/{ $page_count })
+resets-empty = No reset yet
+resets-season = { $number }. end of season { $name }
+
+force-skip = To your pictures, { $player } lost the hand, we cut it short!
+start-already = There is already a game running in this channel
+start = Game started!
+stop = 🏁 Game over, the scores are frozen. Thanks for playing!
+pause-already = The game is already paused
+pause = ⏸️ Game paused, pictures are ignored until `resume`.
+resume-not-paused = The game is not paused
+resume = ▶️ Here we go again!
+
+config-title = Game settings (`*`: specific to this channel)
+settings-disabled = disabled
+
+locale-current = Server language: `{ $locale }`
+locale-set = Server language set to `{ $locale }`
+
+audit-title = 📝 Latest events ({ $page }/{ $page_count })
+audit-empty = Nothing in the log yet
+audit-log-title = 📝 Moderation log
+audit-channel = Channel
+audit-by = By { $actor }
+audit-by-bot = By the bot
+audit-before = Before
+audit-after = After
+
+audit-action-start = Game start
+audit-action-stop = Game end
+audit-action-pause = Pause
+audit-action-resume = Resume
+audit-action-win = Win
+audit-action-force-win = Forced win
+audit-action-skip = Skip
+audit-action-force-skip = Forced skip
+audit-action-auto-skip = Auto-skip
+audit-action-change = Picture change
+audit-action-hint = Hint
+audit-action-undo = Undo
+audit-action-reset = Score reset
+audit-action-cancel-reset = Reset cancellation
+audit-action-config = Setting change
+audit-action-season-end = Season end
+
+## Help of the commands, also used by the slash commands
+
+help-no-help-available = No such command
+help-usage-sample = Example
+help-guild-only = No DMs, you rascal 😏
+help-command-not-found = Look at that, a command that doesn't exist. Some people really aren't the sharpest tools in the shed...
+help-strikethrough-in-guild = ~~`Strikethrough commands`~~ are unavailable because we didn't feel like it.
+
+help-skip = Skip your turn.
+help-win = Mark a player as the winner
+help-win-usage = <player>
+help-win-example = @Tuetuopay#2939
+help-show = Show the scoreboard
+help-show-usage = [page] | season [name [page]]
+help-show-example = 1
+help-reset = Manage the score resets
+help-reset-usage = [do|list [page]|cancel <id>]
+help-pic = Show the picture to guess
+help-change = Change your picture, for the undecided
+help-force-skip = Force the hand to pass
+help-start = Start a new game
+help-force-win = Force the win of a player
+help-stop = End the current game and freeze its scores
+help-pause = Pause the game: pictures ignored and no autoskip
+help-resume = Resume the paused game
+help-config = Show or change the settings of the game
+help-config-usage = [get [setting]|set <setting> <value>|unset <setting>]
+help-config-example = set autoskip-delay 3600
+help-season = List the seasons, or show the scoreboard of one of them
+help-season-usage = [name [page]]
+help-season-example = 2026-10
+help-stats = Show the statistics of a player
+help-stats-usage = [player]
+help-stats-example = @Tuetuopay#2939
+help-undo = Undo the last win or skip
+help-audit = Show the latest events of the game
+help-audit-usage = [page]
+help-audit-example = 2
+help-hint = Give a hint about your picture, which will then be worth fewer points
+help-hint-usage = <hint>
+help-hint-example = It's in Brittany
+help-history = Show the played pictures, or the ones a player posted or found
+help-history-usage = [player] [page]
+help-history-example = @Tuetuopay#2939 2
+help-chart = Plot the scores since the last reset, or until a given reset
+help-chart-usage = [players...] [reset id]
+help-chart-example = @Tuetuopay#2939 @Zoé#1234
+help-locale = Show or change the language of the bot on this server
+help-locale-usage = [fr|en]
+help-locale-example = en
+
+## Slash commands
+
+slash-no-game = No game running in this channel
+slash-win-player = The winner
+slash-show-page = Page of the scoreboard
+slash-reset-do = Reset the scores
+slash-reset-list = List the resets
+slash-reset-list-page = Page of the list
+slash-reset-cancel = Cancel a reset
+slash-reset-cancel-id = ID of the reset
+slash-force-win-player = The winner
+slash-config-get = Show the settings
+slash-config-set = Change a setting for this game
+slash-config-set-value = New value
+slash-config-unset = Reset a setting to its default value
+slash-config-setting = Name of the setting
+slash-season-name = Name of the season
+slash-stats-player = The player, you by default
+slash-audit-page = Page of the log
+slash-hint-hint = The hint
+slash-history-player = The player
+slash-history-page = Page of the history
+slash-chart-player = A player to follow, the best ones by default
+slash-chart-reset = ID of the reset to plot the scores until
+slash-locale-language = The new language
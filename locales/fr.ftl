@@ -0,0 +1,248 @@
+# Messages du bot en français, la langue de repli des autres catalogues
+
+## Erreurs
+
+error-internal = Erreur interne
+error-no-participant = ⁉️ Mais personne n'a la main ...
+error-not-your-turn = ❌ Tut tut tut, c'est pas toi qui a la main...
+error-you-posted-no-pic = 🤦 Hrmpf t'as pas mis de photo toi ...
+error-stfu-bot = 🤖 Tg le bot !
+error-pic-already-posted = 🦜 T'as déjà mis une photo coco.
+error-invalid-page = Page invalide
+error-invalid-reset-id = ID de reset invalide
+error-unknown-arguments = Arguments inconnus
+error-unknown-setting = Paramètre inconnu
+error-invalid-setting-value = Valeur invalide pour ce paramètre
+error-unknown-season = Saison inconnue
+error-nothing-to-undo = 🤷 Rien à annuler
+error-undo-not-allowed = ❌ Seul l'auteur peut annuler, et pas trop longtemps après. Demande à un modo !
+error-next-picture-posted = ⏩ Trop tard, la photo suivante est déjà postée.
+error-missing-hint = 💡 Et l'indice, il est où ?
+error-duplicate-picture = ♻️ Photo refusée, elle ressemble à une photo { $duplicate }. Trouves-en une autre !
+error-unknown-locale = Langue inconnue, au choix : { $locales }
+error-missing-permissions = 🔒 Tu n'as pas les droits pour cette commande.
+
+duplicate-found = déjà jouée le { $date }, trouvée par { $finder }
+duplicate-never-found = déjà jouée le { $date }, jamais trouvée
+
+## Partie
+
+participation-new = 🔎 À vos claviers, une nouvelle photo est à trouver
+participation-duplicate = ♻️ Attention, elle ressemble à une photo { $duplicate }
+
+skip = A vos photos, { $player } passe la main !
+win-no-winner = { $player }, cékiki le gagnant ?
+win-many-winners = Hé { $player }, tu serai pas un peu fada ? Un seul gagnant, un seul !
+win-self = { $player } be like https://i.imgflip.com/12w3f0.jpg
+win-default = Bravo { $winner }, à vous la main.
+win-speed = ⏱️ Trouvé en { $duration } : { $points } { $count ->
+        [one] point
+       *[other] points
+    }
+win-poster = 📷 { $points } { $count ->
+        [one] point
+       *[other] points
+    } pour { $poster } qui a posté la photo
+win-location = 📍 C'était ici : { $location }
+
+hint = 💡 Indice de { $player } : { $hint }
+hint-points = La photo ne rapporte plus que { $points } { $count ->
+        [one] point
+       *[other] points
+    }.
+
+undo-win = ↩️ Victoire de { $winner } annulée, { $back }
+undo-skip = ↩️ Skip annulé, { $back }
+undo-back = { $player } reprend la main.
+undo-back-picture = { $player } reprend la main avec sa photo.
+
+pic-not-posted = C'est au tour de { $player } qui n'a pas encore posté de photo.
+pic-hint = 💡 Indice { $number }
+change = Ok, ok, puisque t'insistes ...
+
+autoskip-warning = ⏰ { $player } ça va autoskip !
+autoskip = Sorry { $player }, les gens sont nuls, prends ton point en moins ¯\_(ツ)_/¯.
+
+## Scores
+
+scoreboard-title = 👑 👑 👑 Scores ({ $page }/{ $page_count }) 👑 👑 👑
+scoreboard-week-delta = { $delta } en 7 jours
+
+season-none = Pas encore de saison dans ce chan
+season-list = Saisons:
+season-ended = - `{ $name }` du { $start } au { $end }
+season-current = - `{ $name }` depuis le { $start }
+season-title = 🏆 Saison { $name } ({ $page }/{ $page_count }) 🏆
+season-start = 🚀 C'est parti pour la saison { $name } !
+season-end = 🏁 Fin de la saison { $name }, bravo au podium ! 🏁
+season-end-empty = 🏁 Fin de la saison { $name }, sans un seul point marqué...
+
+history-title = 📜 Historique ({ $page }/{ $page_count })
+history-empty = Aucune photo jouée pour l'instant
+history-player = Photos postées ou trouvées par { $player }
+history-picture = photo
+history-found = 🔎 Trouvée par { $finder } en { $duration }
+history-auto-skipped = ⏰ Passée automatiquement
+history-skipped = ⏭️ Passée
+
+chart-title = 📈 Évolution des scores
+chart-title-reset = 📈 Évolution des scores jusqu'au reset du { $date }
+chart-empty = Aucun point marqué sur cette période
+
+stats-title = 📊 Stats de { $player }
+stats-finder = { $player } ({ $count } fois)
+stats-nobody = Personne
+stats-points = Points
+stats-finder-points = Points en trouvant
+stats-poster-points = Points en postant
+stats-total-points = Points depuis le début
+stats-pictures = Photos postées
+stats-finds = Photos trouvées
+stats-skips = Skips
+stats-auto-skips = Auto-skips
+stats-average-find-time = Temps moyen pour trouver
+stats-longest-streak = Plus longue série
+stats-favourite-finder = Trouve le plus ses photos
+
+duration-seconds = { $seconds }s
+duration-minutes = { $minutes }min { $seconds }s
+duration-hours = { $hours }h { $minutes }min
+duration-days = { $days }j { $hours }h
+
+## Administration
+
+reset-confirm = Pour confirmer le reset, envoie `!reset do`.
+reset-done = Scores reset avec ID { $id }
+reset-cancelled = Reset { $id } annulé
+resets-title = 🔄 Resets ({ $page }/{ $page_count })
+resets-empty = Aucun reset pour l'instant
+resets-season = { $number }. fin de la saison { $name }
+
+force-skip = A vos photos, { $player } n'a plus la main, on y a coupé court !
+start-already = Il y a déjà une partie en cours dans ce chan
+start = Partie démarrée !
+stop = 🏁 Partie terminée, les scores sont gelés. Merci d'avoir joué !
+pause-already = La partie est déjà en pause
+pause = ⏸️ Partie en pause, les photos sont ignorées jusqu'au `resume`.
+resume-not-paused = La partie n'est pas en pause
+resume = ▶️ C'est reparti !
+
+config-title = Paramètres de la partie (`*`: propres à ce chan)
+settings-disabled = désactivé
+
+locale-current = Langue du serveur : `{ $locale }`
+locale-set = Langue du serveur changée pour `{ $locale }`
+
+audit-title = 📝 Derniers événements ({ $page }/{ $page_count })
+audit-empty = Rien dans le journal pour l'instant
+audit-log-title = 📝 Journal de modération
+audit-channel = Chan
+audit-by = Par { $actor }
+audit-by-bot = Par le bot
+audit-before = Avant
+audit-after = Après
+
+audit-action-start = Début de partie
+audit-action-stop = Fin de partie
+audit-action-pause = Pause
+audit-action-resume = Reprise
+audit-action-win = Victoire
+audit-action-force-win = Victoire forcée
+audit-action-skip = Skip
+audit-action-force-skip = Skip forcé
+audit-action-auto-skip = Auto-skip
+audit-action-change = Changement de photo
+audit-action-hint = Indice
+audit-action-undo = Annulation
+audit-action-reset = Reset des scores
+audit-action-cancel-reset = Annulation de reset
+audit-action-config = Changement de paramètre
+audit-action-season-end = Fin de saison
+
+## Aide des commandes, aussi utilisée pour les commandes slash
+
+help-no-help-available = Commande inexistante
+help-usage-sample = Exemple
+help-guild-only = Pas de DM p'tit coquin 😏
+help-command-not-found = V'là qu'il utilise une commande inexistante. Y'en a vraiment qui ont pas la lumière à tous les étages ...
+help-strikethrough-in-guild = ~~`Les commandes barrées`~~ sont indispo parce qu'on avait pas envie.
+
+help-skip = Passer son tour.
+help-win = Marquer un joueur comme gagnant
+help-win-usage = <joueur>
+help-win-example = @Tuetuopay#2939
+help-show = Afficher le scoreboard
+help-show-usage = [page] | season [nom [page]]
+help-show-example = 1
+help-reset = Gère le reset des scores
+help-reset-usage = [do|list [page]|cancel <id>]
+help-pic = Affiche l'image à deviner
+help-change = Changer de photo, pour les indécis
+help-force-skip = Force la main à passer
+help-start = Démarre une nouvelle partie
+help-force-win = Force une victoire d'un joueur
+help-stop = Termine la partie en cours et gèle ses scores
+help-pause = Met la partie en pause: photos ignorées et pas d'autoskip
+help-resume = Reprend la partie en pause
+help-config = Affiche ou modifie les paramètres de la partie
+help-config-usage = [get [paramètre]|set <paramètre> <valeur>|unset <paramètre>]
+help-config-example = set autoskip-delay 3600
+help-season = Liste les saisons, ou affiche le scoreboard de l'une d'elles
+help-season-usage = [nom [page]]
+help-season-example = 2026-10
+help-stats = Affiche les statistiques d'un joueur
+help-stats-usage = [joueur]
+help-stats-example = @Tuetuopay#2939
+help-undo = Annule la dernière victoire ou le dernier skip
+help-audit = Affiche les derniers événements de la partie
+help-audit-usage = [page]
+help-audit-example = 2
+help-hint = Donne un indice sur sa photo, qui rapportera moins de points
+help-hint-usage = <indice>
+help-hint-example = C'est en Bretagne
+help-history = Affiche les photos jouées, ou celles postées ou trouvées par un joueur
+help-history-usage = [joueur] [page]
+help-history-example = @Tuetuopay#2939 2
+help-chart = Trace l'évolution des scores depuis le dernier reset, ou jusqu'à un reset donné
+help-chart-usage = [joueurs...] [id de reset]
+help-chart-example = @Tuetuopay#2939 @Zoé#1234
+help-locale = Affiche ou change la langue du bot sur ce serveur
+help-locale-usage = [fr|en]
+help-locale-example = en
+
+## Commandes slash
+
+slash-no-game = Pas de partie en cours dans ce chan
+slash-win-player = Le gagnant
+slash-show-page = Page du scoreboard
+slash-reset-do = Reset les scores
+slash-reset-list = Liste les resets
+slash-reset-list-page = Page de la liste
+slash-reset-cancel = Annule un reset
+slash-reset-cancel-id = ID du reset
+slash-force-win-player = Le gagnant
+slash-config-get = Affiche les paramètres
+slash-config-set = Modifie un paramètre pour cette partie
+slash-config-set-value = Nouvelle valeur
+slash-config-unset = Remet un paramètre à sa valeur par défaut
+slash-config-setting = Nom du paramètre
+slash-season-name = Nom de la saison
+slash-stats-player = Le joueur, toi par défaut
+slash-audit-page = Page du journal
+slash-hint-hint = L'indice
+slash-history-player = Le joueur
+slash-history-page = Page de l'historique
+slash-chart-player = Un joueur à suivre, les meilleurs par défaut
+slash-chart-reset = ID du reset jusqu'auquel tracer les scores
+slash-locale-language = La nouvelle langue
+
+# Noms des options, les anglais servant pour les autres langues
+slash-option-player = joueur
+slash-option-player1 = joueur1
+slash-option-player2 = joueur2
+slash-option-player3 = joueur3
+slash-option-value = valeur
+slash-option-setting = paramètre
+slash-option-name = nom
+slash-option-hint = indice
+slash-option-language = langue
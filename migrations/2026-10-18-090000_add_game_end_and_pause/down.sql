@@ -0,0 +1,2 @@
+alter table game drop column paused_at;
+alter table game drop column ended_at;
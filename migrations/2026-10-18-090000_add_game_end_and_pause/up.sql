@@ -0,0 +1,2 @@
+alter table game add column ended_at timestamptz;
+alter table game add column paused_at timestamptz;
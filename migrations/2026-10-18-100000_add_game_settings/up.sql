@@ -0,0 +1,11 @@
+create table game_settings (
+	game_id        uuid not null,
+	autoskip_delay integer,
+	warn_delay     integer,
+	skip_penalty   integer,
+	win_sentences  text[],
+	page_size      integer,
+
+	constraint game_settings_pkey primary key (game_id),
+	constraint game_settings_game_id_fkey foreign key (game_id) references game(id)
+);
@@ -0,0 +1,2 @@
+alter table game_settings drop column season_rollover;
+drop table season;
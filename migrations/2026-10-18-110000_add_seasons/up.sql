@@ -0,0 +1,13 @@
+create table season (
+	id         uuid default uuid_generate_v4() not null,
+	game_id    uuid not null,
+	name       text not null,
+	started_at timestamptz default now() not null,
+	ended_at   timestamptz,
+
+	constraint season_pkey primary key (id),
+	constraint season_game_id_fkey foreign key (game_id) references game(id),
+	constraint season_game_id_name_key unique (game_id, name)
+);
+
+alter table game_settings add column season_rollover text;
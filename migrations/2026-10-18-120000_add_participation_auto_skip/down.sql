@@ -0,0 +1 @@
+alter table participation drop column is_auto_skip;
@@ -0,0 +1 @@
+alter table participation add column is_auto_skip boolean default false not null;
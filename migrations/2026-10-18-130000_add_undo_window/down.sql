@@ -0,0 +1 @@
+alter table game_settings drop column undo_window;
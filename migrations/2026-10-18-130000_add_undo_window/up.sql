@@ -0,0 +1 @@
+alter table game_settings add column undo_window integer;
@@ -0,0 +1,2 @@
+alter table game_settings drop column audit_channel;
+drop table audit_event;
@@ -0,0 +1,18 @@
+create table audit_event (
+	id         uuid default uuid_generate_v4() not null,
+	created_at timestamptz default now() not null,
+	game_id    uuid not null,
+	actor_id   text,
+	action     text not null,
+	target_id  text,
+	before_id  uuid,
+	after_id   uuid,
+	details    text,
+
+	constraint audit_event_pkey primary key (id),
+	constraint audit_event_game_id_fkey foreign key (game_id) references game(id)
+);
+
+create index audit_event_game_id_created_at_idx on audit_event (game_id, created_at);
+
+alter table game_settings add column audit_channel text;
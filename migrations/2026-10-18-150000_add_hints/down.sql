@@ -0,0 +1,5 @@
+alter table game_settings drop column hint_penalty;
+alter table participation drop column hints;
+
+update game_settings set skip_penalty = skip_penalty / 100;
+update win set score = score / 100;
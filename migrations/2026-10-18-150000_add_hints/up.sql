@@ -0,0 +1,6 @@
+-- Scores are now stored in hundredths of a point
+update win set score = score * 100;
+update game_settings set skip_penalty = skip_penalty * 100;
+
+alter table participation add column hints text[] default '{}' not null;
+alter table game_settings add column hint_penalty integer;
@@ -0,0 +1,5 @@
+alter table game_settings drop column poster_brackets;
+alter table game_settings drop column speed_brackets;
+alter table game_settings drop column scoring;
+
+alter table win drop column poster_score;
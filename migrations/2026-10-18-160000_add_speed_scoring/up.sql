@@ -0,0 +1,6 @@
+-- Points of the poster of the picture, in hundredths of a point
+alter table win add column poster_score integer default 0 not null;
+
+alter table game_settings add column scoring text;
+alter table game_settings add column speed_brackets text;
+alter table game_settings add column poster_brackets text;
@@ -0,0 +1 @@
+alter table game_settings drop column poster_points;
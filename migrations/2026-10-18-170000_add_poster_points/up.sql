@@ -0,0 +1 @@
+alter table game_settings add column poster_points integer;
@@ -0,0 +1,3 @@
+alter table participation drop column picture_id;
+
+drop table picture;
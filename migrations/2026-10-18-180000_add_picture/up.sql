@@ -0,0 +1,19 @@
+create table picture (
+	id          uuid default uuid_generate_v4() not null,
+	created_at  timestamptz default now() not null,
+	url         text not null,
+	storage_key text not null,
+	hash        text not null,
+	size        integer not null,
+	width       integer not null,
+	height      integer not null,
+	mime_type   text not null,
+
+	constraint picture_pkey primary key (id)
+);
+
+create index picture_hash_idx on picture (hash);
+
+alter table participation add column picture_id uuid;
+alter table participation add constraint participation_picture_id_fkey
+	foreign key (picture_id) references picture(id);
@@ -0,0 +1,3 @@
+alter table game_settings drop column duplicate_pictures;
+
+alter table picture drop column phash;
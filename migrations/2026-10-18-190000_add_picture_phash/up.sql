@@ -0,0 +1,4 @@
+-- Perceptual hash, to spot pictures played again
+alter table picture add column phash bigint;
+
+alter table game_settings add column duplicate_pictures text;
@@ -0,0 +1,4 @@
+alter table game_settings drop column reveal_location;
+
+alter table participation drop column longitude;
+alter table participation drop column latitude;
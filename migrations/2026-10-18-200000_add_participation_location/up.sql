@@ -0,0 +1,5 @@
+-- Where the picture was taken, kept secret until it is found
+alter table participation add column latitude double precision;
+alter table participation add column longitude double precision;
+
+alter table game_settings add column reveal_location boolean;
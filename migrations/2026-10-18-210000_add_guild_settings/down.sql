@@ -0,0 +1 @@
+drop table guild_settings;
@@ -0,0 +1,7 @@
+-- Settings shared by all the games of a guild
+create table guild_settings (
+	guild_id text not null,
+	locale   text,
+
+	constraint guild_settings_pkey primary key (guild_id)
+);
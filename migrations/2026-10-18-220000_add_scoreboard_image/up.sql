@@ -0,0 +1 @@
+alter table game_settings add column scoreboard_image boolean;
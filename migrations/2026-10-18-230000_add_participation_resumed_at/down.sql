@@ -0,0 +1 @@
+alter table participation drop column resumed_at;
@@ -0,0 +1,2 @@
+-- Restarts the auto-skip timer on resume, without touching the posting time
+alter table participation add column resumed_at timestamptz;
@@ -0,0 +1,236 @@
+//! Audit log of everything changing the state of a game, optionally mirrored to a moderation
+//! channel
+
+use std::sync::Mutex;
+
+use serenity::{
+    model::id::{ChannelId, UserId},
+    utils::{Colour, MessageBuilder},
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    engine::Env,
+    error::Result,
+    i18n::{FALLBACK, Locale, tr},
+    models::*,
+    store::GameStore,
+    transport::{Embed, Reply, Transport},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Start,
+    Stop,
+    Pause,
+    Resume,
+    Win,
+    ForceWin,
+    Skip,
+    ForceSkip,
+    AutoSkip,
+    Change,
+    Hint,
+    Undo,
+    Reset,
+    CancelReset,
+    Config,
+    SeasonEnd,
+}
+
+const ACTIONS: &[(Action, &str)] = &[
+    (Action::Start, "start"),
+    (Action::Stop, "stop"),
+    (Action::Pause, "pause"),
+    (Action::Resume, "resume"),
+    (Action::Win, "win"),
+    (Action::ForceWin, "force_win"),
+    (Action::Skip, "skip"),
+    (Action::ForceSkip, "force_skip"),
+    (Action::AutoSkip, "auto_skip"),
+    (Action::Change, "change"),
+    (Action::Hint, "hint"),
+    (Action::Undo, "undo"),
+    (Action::Reset, "reset"),
+    (Action::CancelReset, "cancel_reset"),
+    (Action::Config, "config"),
+    (Action::SeasonEnd, "season_end"),
+];
+
+impl Action {
+    /// Name stored in the database.
+    pub fn as_str(self) -> &'static str {
+        ACTIONS.iter().find(|(a, _)| *a == self).map(|(_, name)| *name).unwrap()
+    }
+
+    /// Human readable label of a stored action.
+    pub fn label(name: &str, locale: Locale) -> String {
+        let id = format!("audit-action-{}", name.replace('_', "-"));
+        locale.message(&id, &[]).or_else(|| FALLBACK.message(&id, &[])).unwrap_or(name.to_owned())
+    }
+}
+
+/// Event to record, `actor` being `None` for the bot itself.
+#[derive(Clone, Debug)]
+pub struct Event {
+    action: Action,
+    actor: Option<UserId>,
+    target: Option<String>,
+    before: Option<Uuid>,
+    after: Option<Uuid>,
+    details: Option<String>,
+}
+
+impl Event {
+    pub fn new(action: Action) -> Self {
+        Self { action, actor: None, target: None, before: None, after: None, details: None }
+    }
+
+    pub fn actor(mut self, actor: UserId) -> Self {
+        self.actor = Some(actor);
+        self
+    }
+
+    /// Player affected by the action.
+    pub fn target(mut self, target: impl ToString) -> Self {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    /// Identifier of what the action replaced or undid.
+    pub fn before(mut self, id: impl Into<Option<Uuid>>) -> Self {
+        self.before = id.into();
+        self
+    }
+
+    /// Identifier of what the action created.
+    pub fn after(mut self, id: impl Into<Option<Uuid>>) -> Self {
+        self.after = id.into();
+        self
+    }
+
+    pub fn details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+}
+
+/// Save an event, and queue its mirror to the moderation channel of the game if there is one.
+///
+/// The mirror is only sent by [`Mirrors::flush`], once the transaction saving the event is over,
+/// so that moderators never see an event that was rolled back.
+pub async fn record(
+    env: &Env,
+    store: &mut dyn GameStore,
+    game: &Game,
+    event: Event,
+) -> Result<AuditEvent> {
+    let event = save(store, game, event).await?;
+
+    if let Some(channel) = env.settings(store, game).await?.audit_channel {
+        let embed = Embed {
+            title: Some(env.locale.tr("audit-log-title", &[])),
+            colour: Some(Colour::DARK_GREY),
+            fields: vec![
+                (env.locale.tr("audit-channel", &[]), format!("<#{}>", game.channel_id), false),
+                field(&event, env.locale),
+            ],
+            ..Default::default()
+        };
+        env.audit.0.lock().unwrap().push((channel, embed));
+    }
+
+    Ok(event)
+}
+
+/// Mirrors of the events recorded during a transaction, waiting for it to be over.
+#[derive(Debug, Default)]
+pub struct Mirrors(Mutex<Vec<(ChannelId, Embed)>>);
+
+impl Mirrors {
+    /// Send the pending mirrors if the transaction was committed, drop them otherwise.
+    pub async fn flush(&self, transport: &dyn Transport, committed: bool) {
+        let pending = std::mem::take(&mut *self.0.lock().unwrap());
+        if !committed {
+            return;
+        }
+        for (channel, embed) in pending {
+            // The action itself went fine, no need to fail it
+            if let Err(e) = transport.send(channel, Reply::Embed(embed)).await {
+                warn!("Failed to mirror audit event to {channel}: {e:?}");
+            }
+        }
+    }
+}
+
+/// Save an event without mirroring it, for callers without a transport.
+pub async fn save(store: &mut dyn GameStore, game: &Game, event: Event) -> Result<AuditEvent> {
+    let event = NewAuditEvent {
+        game_id: game.id,
+        actor_id: event.actor.map(|id| id.to_string()),
+        action: event.action.as_str().to_owned(),
+        target_id: event.target,
+        before_id: event.before,
+        after_id: event.after,
+        details: event.details,
+    };
+    let event = store.record_event(&event).await?;
+    info!("Audit: {event:?}");
+    Ok(event)
+}
+
+/// Render an event as an embed field.
+pub fn field(event: &AuditEvent, locale: Locale) -> (String, String, bool) {
+    let at = locale.format_datetime(event.created_at);
+    let name = format!("{at} · {}", Action::label(&event.action, locale));
+
+    let mut value = MessageBuilder::new();
+    match &event.actor_id {
+        Some(actor) => value.push(tr!(locale, "audit-by", actor = format!("<@{actor}>"))),
+        None => value.push(locale.tr("audit-by-bot", &[])),
+    };
+    if let Some(target) = &event.target_id {
+        value.push(" → <@").push(target).push(">");
+    }
+    if let Some(details) = &event.details {
+        value.push("\n").push_safe(details);
+    }
+    if let Some(before) = event.before_id {
+        value.push(format!("\n{}: ", locale.tr("audit-before", &[]))).push_mono(before);
+    }
+    if let Some(after) = event.after_id {
+        value.push(format!("\n{}: ", locale.tr("audit-after", &[]))).push_mono(after);
+    }
+
+    (name, value.build(), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fake::FakeTransport;
+
+    #[tokio::test]
+    async fn mirrors_wait_for_commit() {
+        let transport = FakeTransport::new();
+        let mirrors = Mirrors::default();
+        let queue = |title: &str| {
+            let embed = Embed { title: Some(title.to_owned()), ..Default::default() };
+            mirrors.0.lock().unwrap().push((ChannelId(7), embed));
+        };
+
+        queue("rolled back");
+        mirrors.flush(&transport, false).await;
+        assert_eq!(transport.sent(), []);
+
+        queue("committed");
+        mirrors.flush(&transport, true).await;
+        mirrors.flush(&transport, true).await;
+        let sent = transport.sent();
+        let [sent] = &sent[..] else { panic!("Expected a single mirror, got {sent:?}") };
+        assert_eq!(sent.channel, ChannelId(7));
+        let Reply::Embed(embed) = &sent.reply else { panic!("Expected an embed") };
+        assert_eq!(embed.title.as_deref(), Some("committed"));
+    }
+}
@@ -1,28 +1,33 @@
 //! Actual discord client
+//!
+//! The help of the prefixed commands comes from the `help-<name>` messages of the catalogs.
 
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{LazyLock, Mutex},
+};
 
-use diesel::{dsl::now, prelude::ExpressionMethods};
-use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use serenity::{
     client::{Context, EventHandler},
     framework::standard::{
-        Args, CommandGroup, CommandResult, HelpOptions, help_commands,
+        Args, Command, CommandGroup, CommandOptions, CommandResult, GroupOptions, HelpOptions,
+        help_commands,
         macros::{command, group, help, hook},
     },
-    model::prelude::{
-        Attachment, Guild, GuildMembersChunkEvent, Member, Message, Reaction, ReactionType, UserId,
+    model::{
+        application::interaction::Interaction,
+        prelude::{Guild, GuildMembersChunkEvent, Member, Message, Reaction, ReactionType, UserId},
     },
-    utils::Colour,
 };
 use tracing::{Instrument, instrument};
 
 use crate::{
     BotUserId,
-    cmd::{StringResult, player::scoreboard_message},
+    engine::{self, Env},
     error::{Error, ErrorResultExt},
     extensions::*,
-    models::*,
+    i18n::{self, Locale},
+    report, slash, transport,
 };
 
 pub struct Bot;
@@ -31,6 +36,16 @@ pub struct Bot;
 impl EventHandler for Bot {
     async fn ready(&self, ctx: Context, data: serenity::model::gateway::Ready) {
         ctx.data.write().await.insert::<BotUserId>(data.user.id);
+
+        if let Err(e) = slash::register(&ctx).await {
+            tracing::error!("Failed to register slash commands: {e:?}");
+            report::capture(&e, report::Context::task("register_slash_commands"));
+        }
+    }
+
+    #[instrument(skip(self, ctx, interaction))]
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        slash::on_interaction(&ctx, interaction).await;
     }
 
     #[instrument(skip(self, ctx, guild))]
@@ -59,15 +74,23 @@ impl EventHandler for Bot {
     #[instrument(skip(self, ctx, chunk))]
     async fn guild_members_chunk(&self, ctx: Context, chunk: GuildMembersChunkEvent) {
         tracing::debug!("recieved guild member chunk with {} members", chunk.members.len());
-        let members = chunk.members.into_iter().map(|(_, v)| v).collect();
+        let members = chunk.members.into_values().collect();
         ctx.cache().await.batch_update(members).await;
     }
 
     #[instrument(skip(self, ctx, react))]
     async fn reaction_add(&self, ctx: Context, react: Reaction) {
-        let res = on_reaction(&ctx, &react).await;
-        if let Err(e) = res.handle_err(&react.channel_id, &ctx.http).await {
+        let env = ctx.env(react.guild_id).await;
+        let res = on_reaction(&ctx, &env, &react).await;
+        if let Err(e) = res.handle_err(&react.channel_id, &env).await {
             tracing::error!("{e:?}");
+            let context = report::Context {
+                command: None,
+                guild_id: react.guild_id,
+                channel_id: Some(react.channel_id),
+                user_id: react.user_id,
+            };
+            report::capture(&e, context);
         }
     }
 }
@@ -78,243 +101,210 @@ pub async fn filter_command(_: &Context, msg: &Message, _: &str) -> bool {
 }
 
 #[command("skip")]
-#[description("Passer son tour.")]
 #[num_args(0)]
 #[help_available]
 #[only_in(guild)]
 async fn cmd_skip(ctx: &Context, msg: &Message) -> CommandResult {
-    cmd_skip_(ctx, msg).await
-}
-
-#[instrument(skip(ctx, msg), name = "cmd_skip")]
-async fn cmd_skip_(ctx: &Context, msg: &Message) -> CommandResult {
-    let mut conn = ctx.conn().await?;
-
-    let res = conn
-        .build_transaction()
-        .serializable()
-        .run(|conn| Box::pin(crate::cmd::player::skip(ctx.clone(), msg.clone(), conn)))
-        .await;
-    if let Some(reply) = res.handle_err(&msg.channel_id, &ctx.http).await? {
-        msg.channel_id.say(&ctx.http, reply).await?;
-    }
-
-    Ok(())
+    run_command(ctx, msg, "skip").await
 }
 
 #[command("win")]
-#[description("Marquer un joueur comme gagnant")]
-#[usage("<joueur>")]
-#[example("@Tuetuopay#2939")]
 #[num_args(1)]
 #[help_available]
 #[only_in(guild)]
 async fn cmd_win(ctx: &Context, msg: &Message) -> CommandResult {
-    cmd_win_(ctx, msg).await
-}
-
-#[instrument(skip(ctx, msg), name = "cmd_win")]
-async fn cmd_win_(ctx: &Context, msg: &Message) -> CommandResult {
-    let mut conn = ctx.conn().await?;
-
-    let res = conn
-        .build_transaction()
-        .serializable()
-        .run(|conn| Box::pin(crate::cmd::player::win(ctx.clone(), msg.clone(), conn, false)))
-        .await;
-    if let Some(reply) = res.handle_err(&msg.channel_id, &ctx.http).await? {
-        msg.channel_id.say(&ctx.http, reply).await?;
-    }
-
-    Ok(())
+    run_command(ctx, msg, "win").await
 }
 
 #[command("show")]
-#[description("Afficher le scoreboard")]
 #[min_args(0)]
-#[max_args(1)]
-#[usage("[page]")]
-#[example("1")]
+#[max_args(3)]
 #[help_available]
 #[only_in(guild)]
 #[bucket(show_limiter)]
 async fn cmd_show(ctx: &Context, msg: &Message) -> CommandResult {
-    cmd_show_(ctx, msg).await
-}
-
-#[instrument(skip(ctx, msg), name = "cmd_show")]
-async fn cmd_show_(ctx: &Context, msg: &Message) -> CommandResult {
-    let mut conn = ctx.conn().await?;
-
-    let res = crate::cmd::player::show(ctx.clone(), msg.clone(), &mut conn)
-        .instrument(tracing::info_span!("show"))
-        .await;
-    if let Some(reply) = res.handle_err(&msg.channel_id, &ctx.http).await? {
-        let msg = msg.channel_id.send_message(&ctx.http, reply).await?;
-        msg.react(&ctx, ReactionType::Unicode("⬅️".to_owned())).await?;
-        msg.react(&ctx, ReactionType::Unicode("➡️".to_owned())).await?;
-    }
-
-    Ok(())
+    run_command(ctx, msg, "show").await
 }
 
 #[command("reset")]
-#[description("Gère le reset des scores")]
-#[usage("[do|list|cancel <id>]")]
 #[min_args(1)]
 #[max_args(2)]
 #[only_in(guild)]
 #[required_permissions(ADMINISTRATOR)]
 async fn cmd_reset(ctx: &Context, msg: &Message) -> CommandResult {
-    cmd_reset_(ctx, msg).await
-}
-
-#[instrument(skip(ctx, msg), name = "cmd_reset")]
-async fn cmd_reset_(ctx: &Context, msg: &Message) -> CommandResult {
-    let mut conn = ctx.conn().await?;
-
-    let res = conn
-        .build_transaction()
-        .serializable()
-        .run(|conn| Box::pin(crate::cmd::admin::reset(ctx.clone(), msg.clone(), conn)))
-        .await;
-    if let Some(reply) = res.handle_err(&msg.channel_id, &ctx.http).await? {
-        msg.channel_id.say(&ctx.http, reply).await?;
-    }
-
-    Ok(())
+    run_command(ctx, msg, "reset").await
 }
 
 #[command("pic")]
-#[description("Affiche l'image à deviner")]
 #[num_args(0)]
 #[help_available]
 #[only_in(guild)]
 #[bucket(pic_limiter)]
 async fn cmd_pic(ctx: &Context, msg: &Message) -> CommandResult {
-    cmd_pic_(ctx, msg).await
-}
-
-#[instrument(skip(ctx, msg), name = "cmd_pic")]
-async fn cmd_pic_(ctx: &Context, msg: &Message) -> CommandResult {
-    let mut conn = ctx.conn().await?;
-
-    let res = crate::cmd::player::pic(ctx.clone(), msg.clone(), &mut conn)
-        .instrument(tracing::info_span!("pic"))
-        .await;
-    if let Some(reply) = res.handle_err(&msg.channel_id, &ctx.http).await? {
-        msg.channel_id.send_message(&ctx.http, reply).await?;
-    }
-
-    Ok(())
+    run_command(ctx, msg, "pic").await
 }
 
 #[command("change")]
-#[description("Changer de photo, pour les indécis")]
 #[num_args(0)]
 #[only_in(guild)]
 async fn cmd_change(ctx: &Context, msg: &Message) -> CommandResult {
-    cmd_change_(ctx, msg).await
-}
-
-#[instrument(skip(ctx, msg), name = "cmd_change")]
-async fn cmd_change_(ctx: &Context, msg: &Message) -> CommandResult {
-    let mut conn = ctx.conn().await?;
-
-    let res = crate::cmd::player::change(ctx.clone(), msg.clone(), &mut conn).await;
-    if let Some(reply) = res.handle_err(&msg.channel_id, &ctx.http).await? {
-        msg.channel_id.say(&ctx.http, reply).await?;
-    }
-
-    Ok(())
+    run_command(ctx, msg, "change").await
 }
 
 #[command("force_skip")]
-#[description("Force la main à passer")]
 #[num_args(0)]
 #[only_in(guild)]
 #[required_permissions(KICK_MEMBERS)]
 async fn cmd_force_skip(ctx: &Context, msg: &Message) -> CommandResult {
-    cmd_force_skip_(ctx, msg).await
+    run_command(ctx, msg, "force_skip").await
 }
 
-#[instrument(skip(ctx, msg), name = "cmd_force_skip")]
-async fn cmd_force_skip_(ctx: &Context, msg: &Message) -> CommandResult {
-    let mut conn = ctx.conn().await?;
+#[command("start")]
+#[num_args(0)]
+#[only_in(guild)]
+#[required_permissions(ADMINISTRATOR)]
+async fn cmd_start(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "start").await
+}
 
-    let res = conn
-        .build_transaction()
-        .serializable()
-        .run(|conn| Box::pin(crate::cmd::admin::force_skip(ctx.clone(), msg.clone(), conn)))
-        .await;
-    if let Some(reply) = res.handle_err(&msg.channel_id, &ctx.http).await? {
-        msg.channel_id.say(&ctx.http, reply).await?;
+#[command("force_win")]
+#[num_args(1)]
+#[only_in(guild)]
+#[required_permissions(KICK_MEMBERS)]
+async fn cmd_force_win(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "force_win").await
+}
+
+#[instrument(skip(ctx, msg))]
+async fn run_command(ctx: &Context, msg: &Message, name: &str) -> CommandResult {
+    let env = ctx.env(msg.guild_id).await;
+    let msg = transport::Message::from(msg);
+
+    let res = match ctx.conn().await {
+        Ok(mut conn) => {
+            conn.build_transaction()
+                .serializable()
+                .run(|conn| Box::pin(engine::command(&env, conn, name, &msg)))
+                .await
+        }
+        Err(e) => Err(Error::from(e)),
+    };
+    env.audit.flush(&*env.transport, res.is_ok()).await;
+    if let Err(ref e) = res {
+        report::capture(e, report::Context::message(Some(name), &msg));
+    }
+    if let Some(reply) = res.handle_err(&msg.channel_id, &env).await? {
+        engine::send(&env, &msg, reply).await?;
     }
 
     Ok(())
 }
 
-#[command("start")]
-#[description("Démarre une nouvelle partie")]
+#[command("stop")]
 #[num_args(0)]
 #[only_in(guild)]
 #[required_permissions(ADMINISTRATOR)]
-async fn cmd_start(ctx: &Context, msg: &Message) -> CommandResult {
-    cmd_start_(ctx, msg).await
+async fn cmd_stop(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "stop").await
 }
 
-#[instrument(skip(ctx, msg), name = "cmd_start")]
-async fn cmd_start_(ctx: &Context, msg: &Message) -> CommandResult {
-    let mut conn = ctx.conn().await?;
+#[command("pause")]
+#[num_args(0)]
+#[only_in(guild)]
+#[required_permissions(KICK_MEMBERS)]
+async fn cmd_pause(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "pause").await
+}
 
-    let res = conn
-        .build_transaction()
-        .serializable()
-        .run(|conn| Box::pin(crate::cmd::admin::start(ctx.clone(), msg.clone(), conn)))
-        .await;
-    if let Some(reply) = res.handle_err(&msg.channel_id, &ctx.http).await? {
-        msg.channel_id.say(&ctx.http, reply).await?;
-    }
+#[command("resume")]
+#[num_args(0)]
+#[only_in(guild)]
+#[required_permissions(KICK_MEMBERS)]
+async fn cmd_resume(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "resume").await
+}
 
-    Ok(())
+#[command("config")]
+#[min_args(0)]
+#[only_in(guild)]
+#[required_permissions(ADMINISTRATOR)]
+async fn cmd_config(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "config").await
 }
 
-#[command("force_win")]
-#[description("Force une victoire d'un joueur")]
-#[num_args(1)]
+#[command("season")]
+#[min_args(0)]
+#[max_args(2)]
+#[help_available]
 #[only_in(guild)]
-#[required_permissions(KICK_MEMBERS)]
-async fn cmd_force_win(ctx: &Context, msg: &Message) -> CommandResult {
-    cmd_force_win_(ctx, msg).await
+#[bucket(show_limiter)]
+async fn cmd_season(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "season").await
 }
 
-#[instrument(skip(ctx, msg), name = "cmd_force_win")]
-async fn cmd_force_win_(ctx: &Context, msg: &Message) -> CommandResult {
-    let mut conn = ctx.conn().await?;
+#[command("stats")]
+#[min_args(0)]
+#[max_args(1)]
+#[help_available]
+#[only_in(guild)]
+#[bucket(show_limiter)]
+async fn cmd_stats(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "stats").await
+}
 
-    let res = conn
-        .build_transaction()
-        .serializable()
-        .run(|conn| Box::pin(crate::cmd::player::win(ctx.clone(), msg.clone(), conn, true)))
-        .await;
-    if let Some(reply) = res.handle_err(&msg.channel_id, &ctx.http).await? {
-        msg.channel_id.say(&ctx.http, reply).await?;
-    }
+#[command("undo")]
+#[num_args(0)]
+#[help_available]
+#[only_in(guild)]
+async fn cmd_undo(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "undo").await
+}
 
-    Ok(())
+#[command("audit")]
+#[min_args(0)]
+#[max_args(1)]
+#[only_in(guild)]
+#[required_permissions(ADMINISTRATOR)]
+async fn cmd_audit(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "audit").await
+}
+
+#[command("hint")]
+#[min_args(1)]
+#[help_available]
+#[only_in(guild)]
+async fn cmd_hint(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "hint").await
+}
+
+#[command("history")]
+#[min_args(0)]
+#[max_args(2)]
+#[help_available]
+#[only_in(guild)]
+#[bucket(show_limiter)]
+async fn cmd_history(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "history").await
+}
+
+#[command("locale")]
+#[min_args(0)]
+#[max_args(1)]
+#[only_in(guild)]
+#[required_permissions(ADMINISTRATOR)]
+async fn cmd_locale(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "locale").await
+}
+
+#[command("chart")]
+#[help_available]
+#[only_in(guild)]
+#[bucket(show_limiter)]
+async fn cmd_chart(ctx: &Context, msg: &Message) -> CommandResult {
+    run_command(ctx, msg, "chart").await
 }
 
 #[help]
-#[no_help_available_text("Commande inexistante")]
-#[usage_sample_label("Exemple")]
-#[guild_only_text("Pas de DM p'tit coquin 😏")]
-#[command_not_found_text(
-    "V'là qu'il utilise une commande inexistante. Y'en a vraiment qui ont pas \
-    la lumière à tous les étages ..."
-)]
-#[strikethrough_commands_tip_in_guild(
-    "~~`Les commandes barrées`~~ sont indispo parce qu'on avait pas envie."
-)]
 async fn cmd_help(
     ctx: &Context,
     msg: &Message,
@@ -324,12 +314,84 @@ async fn cmd_help(
     owners: HashSet<UserId>,
 ) -> CommandResult {
     let span = tracing::info_span!("cmd_help", ?args, ?help_options, ?groups, ?owners);
-    help_commands::with_embeds(ctx, msg, args, help_options, groups, owners)
+    let locale = ctx.env(msg.guild_id).await.locale;
+    let (help_options, groups) = localized_help(locale, help_options, groups);
+    help_commands::with_embeds(ctx, msg, args, help_options, &groups, owners)
         .instrument(span)
         .await?;
     Ok(())
 }
 
+type LocalizedHelp = (&'static HelpOptions, Vec<&'static CommandGroup>);
+
+/// Help options and command groups with the texts of a locale.
+///
+/// The framework wants them `'static`, so they are built and leaked once per locale.
+fn localized_help(
+    locale: Locale,
+    help_options: &HelpOptions,
+    groups: &[&'static CommandGroup],
+) -> (&'static HelpOptions, Vec<&'static CommandGroup>) {
+    static HELP: LazyLock<Mutex<HashMap<Locale, LocalizedHelp>>> = LazyLock::new(Mutex::default);
+
+    HELP.lock()
+        .unwrap()
+        .entry(locale)
+        .or_insert_with(|| {
+            let text = |id, default| help_text(locale, id).unwrap_or(default);
+            let help_options = HelpOptions {
+                no_help_available_text: text(
+                    "help-no-help-available",
+                    help_options.no_help_available_text,
+                ),
+                usage_sample_label: text("help-usage-sample", help_options.usage_sample_label),
+                guild_only_text: text("help-guild-only", help_options.guild_only_text),
+                command_not_found_text: text(
+                    "help-command-not-found",
+                    help_options.command_not_found_text,
+                ),
+                strikethrough_commands_tip_in_guild: help_text(
+                    locale,
+                    "help-strikethrough-in-guild",
+                ),
+                ..help_options.clone()
+            };
+            let groups = groups.iter().map(|group| localized_group(locale, group)).collect();
+            (Box::leak(Box::new(help_options)), groups)
+        })
+        .clone()
+}
+
+fn localized_group(locale: Locale, group: &CommandGroup) -> &'static CommandGroup {
+    let commands = group.options.commands.iter().map(|c| localized_command(locale, c)).collect();
+    let sub_groups = group.options.sub_groups.iter().map(|g| localized_group(locale, g)).collect();
+    let options = GroupOptions {
+        commands: Vec::leak(commands),
+        sub_groups: Vec::leak(sub_groups),
+        ..*group.options
+    };
+    Box::leak(Box::new(CommandGroup { name: group.name, options: Box::leak(Box::new(options)) }))
+}
+
+/// Command with its description, usage and example from the `help-<name>` messages.
+fn localized_command(locale: Locale, command: &Command) -> &'static Command {
+    let id = format!("help-{}", command.options.names[0].replace('_', "-"));
+    let examples = help_text(locale, &format!("{id}-example")).map(|example| vec![example]);
+    let options = CommandOptions {
+        desc: help_text(locale, &id),
+        usage: help_text(locale, &format!("{id}-usage")),
+        examples: Vec::leak(examples.unwrap_or_default()),
+        ..*command.options
+    };
+    Box::leak(Box::new(Command { fun: command.fun, options: Box::leak(Box::new(options)) }))
+}
+
+/// Help message of a locale, or of the fallback one, if there.
+fn help_text(locale: Locale, id: &str) -> Option<&'static str> {
+    let text = locale.message(id, &[]).or_else(|| i18n::FALLBACK.message(id, &[]))?;
+    Some(String::leak(text))
+}
+
 #[group]
 #[commands(
     cmd_win,
@@ -340,7 +402,19 @@ async fn cmd_help(
     cmd_force_skip,
     cmd_start,
     cmd_force_win,
-    cmd_change
+    cmd_change,
+    cmd_stop,
+    cmd_pause,
+    cmd_resume,
+    cmd_config,
+    cmd_season,
+    cmd_stats,
+    cmd_undo,
+    cmd_audit,
+    cmd_hint,
+    cmd_history,
+    cmd_locale,
+    cmd_chart
 )]
 pub struct General;
 
@@ -353,73 +427,31 @@ pub async fn on_message(ctx: &Context, msg: &Message) {
 pub async fn on_message_(ctx: Context, msg: Message) {
     tokio::spawn(log_message(ctx.clone(), msg.clone()));
 
-    let Ok(mut conn) = ctx.conn().await else {
-        // TODO raise to sentry
-        msg.channel_id.say(&ctx.http, "Erreur interne".to_owned()).await.unwrap();
-        return;
+    let env = ctx.env(msg.guild_id).await;
+    let msg = transport::Message::from(&msg);
+
+    let res = match ctx.conn().await {
+        // Downloading the picture would hold the transaction open for long
+        Ok(mut conn) => match engine::save_picture(&env, &mut *conn, &msg).await {
+            Ok(saved) => {
+                conn.build_transaction()
+                    .serializable()
+                    .run(|conn| Box::pin(engine::on_message(&env, conn, &msg, saved.as_ref())))
+                    .await
+            }
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(Error::from(e)),
     };
+    if let Err(ref e) = res {
+        report::capture(e, report::Context::message(None, &msg));
+    }
 
-    let res = conn
-        .build_transaction()
-        .serializable()
-        .run(|conn| {
-            Box::pin(async {
-                // Find picture attachment
-                let Some(attachment) = msg.attachments.iter().find(|a| a.height.is_some()) else {
-                    return Ok(None);
-                };
-                on_participation(&msg, conn, attachment).await
-            })
-        })
-        .await;
-
-    if let Ok(Some(reply)) = res.handle_err(&msg.channel_id, &ctx.http).await {
+    if let Ok(Some(reply)) = res.handle_err(&msg.channel_id, &env).await {
         msg.channel_id.say(&ctx.http, reply).await.expect("Failed to send message");
     }
 }
 
-#[instrument(skip(msg, conn, attachment))]
-async fn on_participation(
-    msg: &Message,
-    conn: &mut AsyncPgConnection,
-    attachment: &Attachment,
-) -> StringResult {
-    // Find game itself
-    let Some((game, part)) = msg.game(conn).await? else { return Ok(None) };
-
-    let part: Participation = if let Some(part) = part {
-        // Check the participant
-        if part.player_id != msg.author.id.to_string() {
-            // Don't send any error message as this is annoying when people post guess pics etc
-            return Ok(None);
-        }
-
-        if part.picture_url.is_none() {
-            diesel::update(&part)
-                .set((
-                    participation::picture_url.eq(&attachment.proxy_url),
-                    participation::updated_at.eq(now),
-                ))
-                .get_result(conn)
-                .await?
-        } else {
-            return Err(Error::PicAlreadyPosted);
-        }
-    } else {
-        // Create the participation itself as nobody has a hand
-        let part = NewParticipation {
-            player_id: &msg.author.id.to_string(),
-            picture_url: Some(&attachment.proxy_url),
-            game_id: &game.id,
-        };
-        diesel::insert_into(participation::table).values(part).get_result(conn).await?
-    };
-
-    println!("Saved participation {part:?}");
-
-    Ok(Some("🔎 À vos claviers, une nouvelle photo est à trouver".to_owned()))
-}
-
 async fn log_message(ctx: Context, msg: Message) {
     let guild = match msg.guild_id {
         Some(guild) => match guild.name(&ctx.cache) {
@@ -435,58 +467,18 @@ async fn log_message(ctx: Context, msg: Message) {
     println!("({}) {guild} {chan} @{}: {}", msg.id, msg.author.tag(), msg.content_safe(&ctx.cache));
 }
 
-async fn on_reaction(ctx: &Context, react: &Reaction) -> Result<(), Error> {
-    let Some(bot_id) = ctx.data.read().await.get::<BotUserId>().copied() else {
-        tracing::warn!("Got react on message but bot is not cached");
-        return Ok(());
-    };
-    if react.user_id == Some(bot_id) {
-        return Ok(());
-    }
-    let guild_id = react.guild_id.unwrap();
-
-    let Ok(mut conn) = ctx.conn().await else {
-        // TODO raise to sentry
-        react.channel_id.say(&ctx.http, "Erreur interne".to_owned()).await.unwrap();
-        return Ok(());
-    };
-    let Some(game) = Game::get(&mut conn, guild_id.0, react.channel_id.0).await? else {
-        return Ok(());
-    };
-
-    let mut msg = match ctx.cache.message(react.channel_id, react.message_id) {
-        Some(msg) => msg,
-        None => ctx.http.get_message(react.channel_id.0, react.message_id.0).await?,
-    };
-    // bug in serenity / discord: the fetched message has guild_id set to none. override it.
-    msg.guild_id = Some(guild_id);
-
-    if msg.author.id != bot_id {
-        return Ok(());
-    }
-    let page = msg
-        .embeds
-        .get(0)
-        .and_then(|embed| embed.title.as_ref())
-        .filter(|title| title.contains("Scores"))
-        .and_then(|title| title.split(|c| c == '(' || c == '/').nth(1))
-        .and_then(|page| page.parse::<usize>().ok());
-    let Some(page) = page else { return Ok(()) };
-
-    let page = if react.emoji == ReactionType::Unicode("➡️".to_owned()) {
-        page + 1
-    } else if react.emoji == ReactionType::Unicode("⬅️".to_owned()) && page > 1 {
-        page - 1
-    } else {
-        return Ok(());
-    };
+async fn on_reaction(ctx: &Context, env: &Env, react: &Reaction) -> Result<(), Error> {
+    let mut conn = ctx.conn().await?;
 
-    let (title, board) = match scoreboard_message(&ctx, &mut conn, game, guild_id, page).await {
-        Ok(res) => res,
-        Err(Error::InvalidPage) => return Ok(()),
-        Err(e) => return Err(e),
+    let react = transport::Reaction {
+        guild_id: react.guild_id,
+        channel_id: react.channel_id,
+        message_id: react.message_id,
+        user_id: react.user_id,
+        emoji: match &react.emoji {
+            ReactionType::Unicode(emoji) => emoji.clone(),
+            _ => return Ok(()),
+        },
     };
-    msg.edit(&ctx, |m| m.embed(|e| e.title(title).colour(Colour::GOLD).fields(board))).await?;
-
-    Ok(())
+    engine::on_reaction(env, &mut *conn, &react).await
 }
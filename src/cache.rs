@@ -12,6 +12,8 @@ use serenity::{
 use tokio::sync::RwLock;
 use tracing::{instrument, trace};
 
+use crate::metrics::CACHE_REQUESTS;
+
 #[derive(Clone, Debug, Default)]
 pub struct Cache {
     users: Arc<RwLock<HashMap<UserId, User>>>,
@@ -34,11 +36,13 @@ impl Cache {
             // Check the cache
             if let Some(member) = self.members.read().await.get(&(guild_id, user_id)) {
                 trace!("Cache hit");
+                CACHE_REQUESTS.with_label_values(&["member", "hit"]).inc();
                 return Ok(member.clone());
             }
         }
 
         trace!("Cache miss, fetching member from Discord");
+        CACHE_REQUESTS.with_label_values(&["member", "miss"]).inc();
 
         // Fetch the member from Discord
         let member = guild_id.member(http, user_id).await?;
@@ -59,11 +63,13 @@ impl Cache {
             // Check the cache
             if let Some(user) = self.users.read().await.get(&user_id) {
                 trace!("Cache hit");
+                CACHE_REQUESTS.with_label_values(&["user", "hit"]).inc();
                 return Ok(user.clone());
             }
         }
 
         trace!("Cache miss, fetching user from Discord");
+        CACHE_REQUESTS.with_label_values(&["user", "miss"]).inc();
 
         // Fetch user from Discord
         let user = user_id.to_user(http).await?;
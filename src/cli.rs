@@ -0,0 +1,238 @@
+//! Operator subcommands, working with the database alone
+
+use std::{error::Error as StdError, io::Write, path::PathBuf};
+
+use clap::{Subcommand, ValueEnum};
+use diesel_async::{AsyncConnection, AsyncPgConnection};
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, Action, Event},
+    config::{self, Config},
+    export::{self, GameExport},
+    migrate,
+    models::Game,
+    score,
+    store::GameStore,
+};
+
+type CliResult<T = ()> = Result<T, Box<dyn StdError + Send + Sync>>;
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Apply the pending database migrations.
+    Migrate,
+    /// Inspect games.
+    #[clap(subcommand)]
+    Games(GamesCommand),
+    /// Print the scoreboard of a game since its last reset.
+    Scoreboard { game: Uuid },
+    /// Export a game with its full history.
+    Export {
+        game: Uuid,
+        /// File to write to, instead of the standard output.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        #[clap(short, long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+    /// Recreate a game exported as JSON in a channel, with new IDs.
+    Import {
+        file: PathBuf,
+        #[clap(long)]
+        guild: u64,
+        #[clap(long)]
+        channel: u64,
+        /// Only report what would be imported and the conflicts.
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Reset the scores of a running game.
+    Reset { game: Uuid },
+    /// Check the config file and the database it points to.
+    CheckConfig,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    /// Versioned, can be imported back
+    Json,
+    /// One row per participation, for spreadsheets
+    Csv,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GamesCommand {
+    /// List all games, ended ones included.
+    List,
+}
+
+/// Run a subcommand, with the config at `config_path`.
+pub async fn run(command: Command, config_path: &str) -> CliResult {
+    let config = config::load_config(config_path)
+        .map_err(|e| format!("Failed to load {config_path}: {e}"))?;
+
+    match command {
+        Command::Migrate => {
+            let applied = migrate::run_pending(&config.db_config.database_url).await?;
+            if applied.is_empty() {
+                println!("Nothing to migrate");
+            }
+            for version in applied {
+                println!("Applied {version}");
+            }
+        }
+        Command::Games(GamesCommand::List) => {
+            let mut conn = connect(&config).await?;
+            for game in conn.all_games().await? {
+                let status = match (game.ended_at, game.paused_at) {
+                    (Some(ended_at), _) => format!("ended {}", ended_at.format("%Y-%m-%d")),
+                    (None, Some(_)) => "paused".to_owned(),
+                    (None, None) => "running".to_owned(),
+                };
+                println!(
+                    "{}  guild {}  channel {}  created {}  {status}",
+                    game.id,
+                    game.guild_id,
+                    game.channel_id,
+                    game.created_at.format("%Y-%m-%d"),
+                );
+            }
+        }
+        Command::Scoreboard { game } => {
+            let mut conn = connect(&config).await?;
+            let game = find_game(&mut conn, game).await?;
+            let locale = config.bot_config.locale;
+            for (rank, score) in conn.scoreboard(&game, None).await?.iter().enumerate() {
+                println!(
+                    "{:>3}. {}  {} ({} + {})",
+                    rank + 1,
+                    score.player_id,
+                    score::format(score.total(), locale),
+                    score::format(score.finder, locale),
+                    score::format(score.poster, locale),
+                );
+            }
+        }
+        Command::Export { game, output, format } => {
+            let mut conn = connect(&config).await?;
+            let game = find_game(&mut conn, game).await?;
+            let export = GameExport::new(&mut conn, game).await?;
+            let data = match format {
+                Format::Json => serde_json::to_vec_pretty(&export)?,
+                Format::Csv => {
+                    let mut data = vec![];
+                    export.write_csv(&mut data)?;
+                    data
+                }
+            };
+            match output {
+                Some(path) => std::fs::write(path, data)?,
+                None => std::io::stdout().write_all(&data)?,
+            }
+        }
+        Command::Import { file, guild, channel, dry_run } => {
+            let export: GameExport = serde_json::from_slice(&std::fs::read(file)?)?;
+            let mut conn = connect(&config).await?;
+            let report = conn
+                .build_transaction()
+                .serializable()
+                .run(|conn| {
+                    Box::pin(
+                        async move { export::import(conn, &export, guild, channel, dry_run).await },
+                    )
+                })
+                .await?;
+
+            for conflict in &report.conflicts {
+                let kind = if conflict.is_blocking() { "Conflict" } else { "Warning" };
+                println!("{kind}: {conflict}");
+            }
+            let summary = format!(
+                "{} participations, {} wins and {} seasons",
+                report.participations, report.wins, report.seasons
+            );
+            if report.imported {
+                println!("Imported game {} with {summary}", report.game_id);
+            } else if dry_run {
+                println!("Would import {summary}");
+            } else {
+                return Err("Nothing imported because of conflicts".into());
+            }
+        }
+        Command::Reset { game } => {
+            let mut conn = connect(&config).await?;
+            let game = find_game(&mut conn, game).await?;
+            if game.ended_at.is_some() {
+                return Err(format!("Game {} already ended", game.id).into());
+            }
+            let reset_id = Uuid::new_v4();
+            conn.build_transaction()
+                .serializable()
+                .run(|conn| {
+                    Box::pin(async move {
+                        let store: &mut dyn GameStore = conn;
+                        let part = store.game_with_part(game.guild().0, game.channel().0).await?;
+                        store.reset(&game, reset_id).await?;
+                        // Mark the current participation as skipped (if any)
+                        if let Some((_, Some(part))) = part {
+                            store.skip(&part, 0, false).await?;
+                        }
+                        audit::save(store, &game, Event::new(Action::Reset).after(reset_id)).await
+                    })
+                })
+                .await?;
+            println!("Reset {reset_id}");
+        }
+        Command::CheckConfig => check_config(&config).await?,
+    }
+
+    Ok(())
+}
+
+async fn connect(config: &Config) -> CliResult<AsyncPgConnection> {
+    Ok(AsyncPgConnection::establish(&config.db_config.database_url).await?)
+}
+
+async fn find_game(store: &mut dyn GameStore, id: Uuid) -> CliResult<Game> {
+    Ok(store.game_by_id(id).await?.ok_or_else(|| format!("No game with ID {id}"))?)
+}
+
+/// Print every problem found in the config, failing if there is any.
+async fn check_config(config: &Config) -> CliResult {
+    let bot_config = &config.bot_config;
+    let mut problems = vec![];
+
+    for sentence in bot_config.win_sentences.iter().filter(|s| !s.contains("{}")) {
+        problems.push(format!("Win sentence without a {{}} for the winner: {sentence:?}"));
+    }
+    if let Some(rl) = &bot_config.ratelimit
+        && rl.limit == 0
+    {
+        problems.push("Ratelimit allows no command at all".to_owned());
+    }
+    if let Some(auto_skip) = &bot_config.auto_skip
+        && auto_skip.warn_delay > auto_skip.autoskip_delay
+    {
+        problems.push("Auto-skip warning comes after the skip itself".to_owned());
+    }
+    if let Some(storage) = &config.storage
+        && !storage.directory.is_dir()
+    {
+        problems.push(format!("Storage directory {} does not exist", storage.directory.display()));
+    }
+    match migrate::pending(&config.db_config.database_url).await {
+        Ok(pending) if pending.is_empty() => {}
+        Ok(pending) => problems.push(format!("Pending migrations: {}", pending.join(", "))),
+        Err(e) => problems.push(format!("Database: {e}")),
+    }
+
+    if problems.is_empty() {
+        println!("Config is fine");
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("- {problem}");
+    }
+    Err(format!("{} problem(s) found", problems.len()).into())
+}
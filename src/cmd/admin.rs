@@ -1,110 +1,135 @@
 //! Admin command handlers
 
-use chrono::{DateTime, Utc};
-use diesel::{
-    dsl::{not, now, sql},
-    prelude::{ExpressionMethods, OptionalExtension, QueryDsl},
-};
-use diesel_async::{AsyncPgConnection, RunQueryDsl};
-use itertools::Itertools;
-use serenity::{client::Context, model::prelude::Message, utils::MessageBuilder};
+use serenity::{model::mention::Mentionable, utils::Colour};
 use tracing::info;
 use uuid::Uuid;
 
 use super::*;
-use crate::{error::Error, extensions::MessageExt, models::*};
+use crate::{
+    audit::{self, Action, Event},
+    engine::Env,
+    error::{Error, Result},
+    extensions::MessageExt,
+    i18n::{Locale, tr},
+    models::*,
+    paging::{self, View},
+    settings::{self, Settings},
+    store::GameStore,
+    transport::{Embed, Message, Reply},
+};
 
-#[tracing::instrument(skip(_ctx, msg, conn))]
-pub async fn reset(_ctx: Context, msg: Message, conn: &mut AsyncPgConnection) -> StringResult {
+#[tracing::instrument(skip(env, msg, store))]
+pub async fn reset(env: &Env, msg: &Message, store: &mut dyn GameStore) -> ReplyResult {
     tracing::info!("in reset handler");
-    let Some((game, part)) = msg.game(conn).await? else { return Ok(None) };
+    let Some((game, part)) = msg.game(store).await? else { return Ok(None) };
 
-    match msg.content.split(' ').collect::<Vec<_>>().as_slice() {
-        [_] => Ok(Some("Pour confirmer le reset, envoie `!reset do`.".to_owned())),
-        [_, "do"] => {
+    let text = match msg.args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        [] => env.locale.tr("reset-confirm", &[]),
+        ["do"] => {
             let reset_id = Uuid::new_v4();
-            let win_ids = participation::table
-                .filter(participation::win_id.is_not_null())
-                .filter(participation::game_id.eq(&game.id))
-                .select(participation::win_id)
-                .load::<Option<Uuid>>(conn)
-                .await?
-                .into_iter()
-                .flatten()
-                .collect::<Vec<_>>();
-            diesel::update(win::table.filter(not(win::reset)).filter(win::id.eq_any(win_ids)))
-                .set((win::reset.eq(true), win::reset_at.eq(now), win::reset_id.eq(reset_id)))
-                .execute(conn)
-                .await?;
+            store.reset(&game, reset_id).await?;
 
             // Mark the current participation as skipped (if any)
             if let Some(part) = part {
-                part.skip(conn, false).await?;
+                store.skip(&part, 0, false).await?;
             }
+            let event = Event::new(Action::Reset).actor(msg.author.id).after(reset_id);
+            audit::record(env, store, &game, event).await?;
 
-            Ok(Some(format!("Scores reset avec ID {reset_id}")))
+            tr!(env.locale, "reset-done", id = reset_id.to_string())
         }
-        [_, "list"] => {
-            use diesel::sql_types::Timestamptz;
-            let resets = win::table
-                .select((win::reset_id, sql::<Timestamptz>("max(reset_at) as rst")))
-                .inner_join(participation::table)
-                .filter(participation::game_id.eq(&game.id))
-                .filter(win::reset)
-                .group_by(win::reset_id)
-                .order_by(sql::<Timestamptz>("rst"))
-                .load::<(Option<Uuid>, DateTime<Utc>)>(conn)
-                .await?
-                .into_iter()
-                .enumerate()
-                .filter_map(|(i, (id, at))| id.map(|id| format!("{}. {id} à {at}", i + 1)))
-                .join("\n");
-            Ok(Some(format!("Resets:\n{resets}")))
+        ["list"] => {
+            return Ok(Some(Reply::Paged(
+                paging::render(env, store, &game, View::Resets, 1).await?,
+            )));
         }
-        [_, "cancel", id] => {
+        ["list", page] => {
+            let page = page.parse().map_err(|_| Error::InvalidPage)?;
+            return Ok(Some(Reply::Paged(
+                paging::render(env, store, &game, View::Resets, page).await?,
+            )));
+        }
+        ["cancel", id] => {
             let reset_id: Uuid = id.parse().map_err(|_| Error::InvalidResetId)?;
-            diesel::update(win::table.filter(win::reset).filter(win::reset_id.eq(reset_id)))
-                .set((
-                    win::reset.eq(false),
-                    win::reset_at.eq::<Option<DateTime<Utc>>>(None),
-                    win::reset_id.eq::<Option<Uuid>>(None),
-                ))
-                .execute(conn)
-                .await
-                .optional()?
-                .ok_or(Error::InvalidResetId)?;
-            Ok(Some(format!("Reset {reset_id} annulé")))
+            // Ended seasons stay frozen
+            if store.seasons(&game).await?.iter().any(|s| s.id == reset_id) {
+                return Err(Error::InvalidResetId);
+            }
+            if store.cancel_reset(&game, reset_id).await? == 0 {
+                return Err(Error::InvalidResetId);
+            }
+            let event = Event::new(Action::CancelReset).actor(msg.author.id).before(reset_id);
+            audit::record(env, store, &game, event).await?;
+
+            tr!(env.locale, "reset-cancelled", id = reset_id.to_string())
         }
-        [..] => Err(Error::UnknownArguments),
-    }
+        [..] => return Err(Error::UnknownArguments),
+    };
+    Ok(Some(Reply::Text(text)))
+}
+
+/// Page of the resets of the game, along with the page count.
+pub async fn resets_view(
+    env: &Env,
+    store: &mut dyn GameStore,
+    game: &Game,
+    page: usize,
+) -> Result<(Embed, usize)> {
+    let seasons = store.seasons(game).await?;
+    let resets = store.resets(game).await?;
+    let per_page = env.settings(store, game).await?.page_size;
+    let page_count = paging::page_count(resets.len(), per_page, page)?;
+    let locale = env.locale;
+    let description = resets.is_empty().then(|| locale.tr("resets-empty", &[]));
+
+    let fields = resets.into_iter().enumerate().skip((page - 1) * per_page).take(per_page).map(
+        |(i, (id, at))| {
+            let name = match seasons.iter().find(|s| s.id == id) {
+                Some(season) => {
+                    tr!(locale, "resets-season", number = i + 1, name = season.name.as_str())
+                }
+                None => format!("{}. {id}", i + 1),
+            };
+            (name, locale.format_datetime(at), false)
+        },
+    );
+    let embed = Embed {
+        title: Some(tr!(locale, "resets-title", page = page, page_count = page_count)),
+        colour: Some(Colour::DARK_GREY),
+        description,
+        fields: fields.collect(),
+        ..Default::default()
+    };
+    Ok((embed, page_count))
 }
 
-#[tracing::instrument(skip(_ctx, msg, conn))]
-pub async fn force_skip(_ctx: Context, msg: Message, conn: &mut AsyncPgConnection) -> StringResult {
-    let game = msg.game(conn).await?;
-    let part = match game {
-        Some((_, Some(part))) => part,
+#[tracing::instrument(skip(env, msg, store))]
+pub async fn force_skip(env: &Env, msg: &Message, store: &mut dyn GameStore) -> StringResult {
+    let game = msg.game(store).await?;
+    let (game, part) = match game {
+        Some((game, Some(part))) => (game, part),
         Some(_) => return Err(Error::NoParticipant),
         None => return Ok(None),
     };
 
-    part.skip(conn, true).await?;
+    let settings = env.settings(store, &game).await?;
+    let skipped = store.skip(&part, settings.skip_penalty, false).await?;
+    let event = Event::new(Action::ForceSkip)
+        .actor(msg.author.id)
+        .target(&part.player_id)
+        .before(part.id)
+        .after(skipped.win_id);
+    audit::record(env, store, &game, event).await?;
 
-    Ok(Some(
-        MessageBuilder::new()
-            .push("A vos photos, ")
-            .mention(&part.player())
-            .push(" n'a plus la main, on y a coupé court !")
-            .build(),
-    ))
+    Ok(Some(tr!(env.locale, "force-skip", player = part.player().mention().to_string())))
 }
 
-#[tracing::instrument(skip(_ctx, msg, conn))]
-pub async fn start(_ctx: Context, msg: Message, conn: &mut AsyncPgConnection) -> StringResult {
-    let game = msg.game(conn).await?;
+#[tracing::instrument(skip(env, msg, store))]
+pub async fn start(env: &Env, msg: &Message, store: &mut dyn GameStore) -> StringResult {
+    let game = msg.game(store).await?;
 
     if game.is_some() {
-        return Ok(Some("Il y a déjà une partie en cours dans ce chan".to_owned()));
+        return Ok(Some(env.locale.tr("start-already", &[])));
     }
 
     let Some(guild) = msg.guild_id else { return Ok(None) };
@@ -114,8 +139,135 @@ pub async fn start(_ctx: Context, msg: Message, conn: &mut AsyncPgConnection) ->
         channel_id: &msg.channel_id.to_string(),
         creator_id: &msg.author.id.to_string(),
     };
-    let game: Game = diesel::insert_into(game::table).values(game).get_result(conn).await?;
+    let game = store.create_game(game).await?;
     info!("Created new game: {game:?}");
+    audit::record(env, store, &game, Event::new(Action::Start).actor(msg.author.id).after(game.id))
+        .await?;
+
+    Ok(Some(env.locale.tr("start", &[])))
+}
+
+#[tracing::instrument(skip(env, msg, store))]
+pub async fn stop(env: &Env, msg: &Message, store: &mut dyn GameStore) -> StringResult {
+    let Some((game, part)) = msg.game(store).await? else { return Ok(None) };
+
+    // Nobody is to blame for the game ending, so no penalty for the current hand
+    if let Some(part) = part {
+        store.skip(&part, 0, false).await?;
+    }
+    let game = store.end_game(&game).await?;
+    info!("Ended game: {game:?}");
+    audit::record(env, store, &game, Event::new(Action::Stop).actor(msg.author.id).before(game.id))
+        .await?;
+
+    Ok(Some(env.locale.tr("stop", &[])))
+}
+
+#[tracing::instrument(skip(env, msg, store))]
+pub async fn pause(env: &Env, msg: &Message, store: &mut dyn GameStore) -> StringResult {
+    let Some((game, _)) = msg.game(store).await? else { return Ok(None) };
+
+    if game.is_paused() {
+        return Ok(Some(env.locale.tr("pause-already", &[])));
+    }
+    store.set_paused(&game, true).await?;
+    audit::record(env, store, &game, Event::new(Action::Pause).actor(msg.author.id)).await?;
+
+    Ok(Some(env.locale.tr("pause", &[])))
+}
+
+#[tracing::instrument(skip(env, msg, store))]
+pub async fn resume(env: &Env, msg: &Message, store: &mut dyn GameStore) -> StringResult {
+    let Some((game, _)) = msg.game(store).await? else { return Ok(None) };
+
+    if !game.is_paused() {
+        return Ok(Some(env.locale.tr("resume-not-paused", &[])));
+    }
+    store.set_paused(&game, false).await?;
+    audit::record(env, store, &game, Event::new(Action::Resume).actor(msg.author.id)).await?;
+
+    Ok(Some(env.locale.tr("resume", &[])))
+}
+
+#[tracing::instrument(skip(env, msg, store))]
+pub async fn config(env: &Env, msg: &Message, store: &mut dyn GameStore) -> StringResult {
+    let Some((game, _)) = msg.game(store).await? else { return Ok(None) };
+    let current = store.settings(&game).await?;
+    let effective = Settings::new(&env.config, current.as_ref());
+
+    let (key, value) = match msg.args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        [] | ["get"] => {
+            let settings = effective.describe(current.as_ref(), env.locale);
+            return Ok(Some(format!("{}\n{settings}", env.locale.tr("config-title", &[]))));
+        }
+        ["get", key] => return Ok(Some(format!("`{key}`: {}", effective.get(key, env.locale)?))),
+        ["set", key, value @ ..] if !value.is_empty() => (*key, Some(value.join(" "))),
+        ["unset", key] => (*key, None),
+        [..] => return Err(Error::UnknownArguments),
+    };
+
+    let mut settings = current.unwrap_or(GameSettings { game_id: game.id, ..Default::default() });
+    settings::set(&mut settings, key, value.as_deref())?;
+    let settings = store.save_settings(&settings).await?;
+    info!("Updated settings: {settings:?}");
+
+    let value = Settings::new(&env.config, Some(&settings)).get(key, env.locale)?;
+    let event = Event::new(Action::Config).actor(msg.author.id).details(format!("{key}: {value}"));
+    audit::record(env, store, &game, event).await?;
+
+    Ok(Some(format!("`{key}`: {value}")))
+}
+
+#[tracing::instrument(skip(env, msg, store))]
+pub async fn audit(env: &Env, msg: &Message, store: &mut dyn GameStore) -> ReplyResult {
+    let Some((game, _)) = msg.game(store).await? else { return Ok(None) };
+
+    let page = match msg.args.first() {
+        Some(page) => page.parse().map_err(|_| Error::InvalidPage)?,
+        None => 1,
+    };
+    Ok(Some(Reply::Paged(paging::render(env, store, &game, View::Audit, page).await?)))
+}
+
+/// Page of the audit log, newest first, along with the page count.
+pub async fn audit_view(
+    env: &Env,
+    store: &mut dyn GameStore,
+    game: &Game,
+    page: usize,
+) -> Result<(Embed, usize)> {
+    let per_page = env.settings(store, game).await?.page_size;
+    let page_count = paging::page_count(store.event_count(game).await?, per_page, page)?;
+    let events = store.events(game, (page - 1) * per_page, per_page).await?;
+
+    let embed = Embed {
+        title: Some(tr!(env.locale, "audit-title", page = page, page_count = page_count)),
+        colour: Some(Colour::DARK_GREY),
+        description: events.is_empty().then(|| env.locale.tr("audit-empty", &[])),
+        fields: events.iter().map(|event| audit::field(event, env.locale)).collect(),
+        ..Default::default()
+    };
+    Ok((embed, page_count))
+}
+
+/// Show or change the language of the bot, for all the games of the guild.
+#[tracing::instrument(skip(env, msg, store))]
+pub async fn locale(env: &Env, msg: &Message, store: &mut dyn GameStore) -> StringResult {
+    let Some(guild) = msg.guild_id else { return Ok(None) };
+
+    let locale = match msg.args.as_slice() {
+        [] => return Ok(Some(tr!(env.locale, "locale-current", locale = env.locale.to_string()))),
+        [locale] => locale.parse::<Locale>()?,
+        [..] => return Err(Error::UnknownArguments),
+    };
+    let mut settings = store
+        .guild_settings(guild.0)
+        .await?
+        .unwrap_or(GuildSettings { guild_id: guild.to_string(), ..Default::default() });
+    settings.locale = Some(locale.to_string());
+    let settings = store.save_guild_settings(&settings).await?;
+    info!("Updated guild settings: {settings:?}");
 
-    Ok(Some("Partie démarrée !".to_owned()))
+    // Already in the new language
+    Ok(Some(tr!(locale, "locale-set", locale = locale.to_string())))
 }
@@ -1,13 +1,9 @@
 //! Actual command handlers
 
-use serenity::builder::CreateMessage;
-
-use crate::error::Result;
+use crate::{error::Result, transport::Reply};
 
 pub mod admin;
 pub mod player;
 
 pub type StringResult = Result<Option<String>>;
-type CreateMessageClosure =
-    Box<dyn for<'a, 'b> FnOnce(&'b mut CreateMessage<'a>) -> &'b mut CreateMessage<'a> + Send>;
-type CreateMessageResult = Result<Option<CreateMessageClosure>>;
+pub type ReplyResult = Result<Option<Reply>>;
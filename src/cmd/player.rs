@@ -1,31 +1,41 @@
 //! Regular player command handler
 
-use diesel::{
-    dsl::{not, now, sum},
-    prelude::{ExpressionMethods, QueryDsl},
-};
-use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use chrono::{TimeDelta, Utc};
+use itertools::Itertools;
 use rand::seq::IndexedRandom;
 use serenity::{
-    client::Context,
-    model::prelude::{GuildId, Message},
+    model::{
+        mention::Mentionable,
+        prelude::{GuildId, UserId},
+    },
     utils::{Colour, MessageBuilder},
 };
-use tracing::{Instrument, info_span, instrument};
+use tracing::{Instrument, info, info_span, instrument};
+use uuid::Uuid;
 
 use super::*;
 use crate::{
+    audit::{self, Action, Event},
+    engine::Env,
     error::Error,
-    extensions::{ContextExt, MessageExt},
+    extensions::MessageExt,
+    i18n::tr,
     models::*,
+    paging::{self, View},
+    pictures::{self, Pictures},
+    render::{self, chart, scoreboard},
+    score::{self, Scoring},
+    stats::{self, PlayerStats, format_duration},
+    store::{self, GameStore, PlayerScore},
+    transport::{Embed, File, Message, Reply},
 };
 
-#[instrument(skip(_ctx, msg, conn))]
-pub async fn skip(_ctx: Context, msg: Message, conn: &mut AsyncPgConnection) -> StringResult {
-    let game = msg.game(conn).await?;
+#[instrument(skip(env, msg, store))]
+pub async fn skip(env: &Env, msg: &Message, store: &mut dyn GameStore) -> StringResult {
+    let game = msg.game(store).await?;
 
-    let part = match game {
-        Some((_, Some(part))) => part,
+    let (game, part) = match game {
+        Some((game, Some(part))) => (game, part),
         Some(_) => return Err(Error::NoParticipant),
         None => return Ok(None),
     };
@@ -34,25 +44,21 @@ pub async fn skip(_ctx: Context, msg: Message, conn: &mut AsyncPgConnection) ->
         return Err(Error::NotYourTurn);
     }
 
-    part.skip(conn, true).await?;
+    let settings = env.settings(store, &game).await?;
+    let skipped = store.skip(&part, settings.skip_penalty, false).await?;
+    let event = Event::new(Action::Skip)
+        .actor(msg.author.id)
+        .target(&part.player_id)
+        .before(part.id)
+        .after(skipped.win_id);
+    audit::record(env, store, &game, event).await?;
 
-    Ok(Some(
-        MessageBuilder::new()
-            .push("A vos photos, ")
-            .mention(&msg.author)
-            .push(" passe la main !")
-            .build(),
-    ))
+    Ok(Some(tr!(env.locale, "skip", player = msg.author.mention().to_string())))
 }
 
-#[instrument(skip(ctx, msg, conn))]
-pub async fn win(
-    ctx: Context,
-    msg: Message,
-    conn: &mut AsyncPgConnection,
-    force: bool,
-) -> StringResult {
-    let game = msg.game(conn).await?;
+#[instrument(skip(env, msg, store))]
+pub async fn win(env: &Env, msg: &Message, store: &mut dyn GameStore, force: bool) -> StringResult {
+    let game = msg.game(store).await?;
     let (game, part) = match game {
         Some((game, Some(part))) => (game, part),
         Some(_) => return Err(Error::NoParticipant),
@@ -71,20 +77,14 @@ pub async fn win(
     let winner = match msg.mentions.as_slice() {
         [] => {
             // TODO this should be an error
-            return Ok(Some(
-                MessageBuilder::new().mention(&msg.author).push(", cékiki le gagnant ?").build(),
-            ));
+            let player = msg.author.mention().to_string();
+            return Ok(Some(tr!(env.locale, "win-no-winner", player = player)));
         }
         [winner] => winner,
         [..] => {
             // TODO this should be an error
-            return Ok(Some(
-                MessageBuilder::new()
-                    .push("Hé ")
-                    .mention(&msg.author)
-                    .push(", tu serai pas un peu fada ? Un seul gagnant, un seul !")
-                    .build(),
-            ));
+            let player = msg.author.mention().to_string();
+            return Ok(Some(tr!(env.locale, "win-many-winners", player = player)));
         }
     };
 
@@ -94,142 +94,591 @@ pub async fn win(
     }
     if winner.id == msg.author.id && !force {
         // TODO this should be an error
-        return Ok(Some(
-            MessageBuilder::new()
-                .mention(&msg.author)
-                .push(" be like https://i.imgflip.com/12w3f0.jpg")
-                .build(),
-        ));
+        return Ok(Some(tr!(env.locale, "win-self", player = msg.author.mention().to_string())));
     }
 
-    // Save the win
+    // Save the win, worth less if hints were needed
+    let settings = env.settings(store, &game).await?;
+    let found_in = Utc::now() - part.updated_at;
+    let (score, poster_score) = score::win_scores(&settings, found_in, part.hints.len());
     let win = NewWin {
         player_id: &msg.author.id.0.to_string(),
         winner_id: &winner.id.0.to_string(),
-        score: 1,
+        score,
+        poster_score,
     };
-    let win: Win = diesel::insert_into(win::table).values(win).get_result(conn).await?;
-    println!("Saved win {win:?}");
-
-    // Mark participation as won
-    diesel::update(&part)
-        .set((
-            participation::is_win.eq(true),
-            participation::won_at.eq(now),
-            participation::win_id.eq(&win.id),
-        ))
-        .execute(conn)
-        .await?;
+    let win = store.record_win(&part, win).await?;
+    info!("Saved win {win:?}");
+    let event = Event::new(if force { Action::ForceWin } else { Action::Win })
+        .actor(msg.author.id)
+        .target(&win.winner_id)
+        .before(part.id)
+        .after(win.id);
+    audit::record(env, store, &game, event).await?;
 
     // Mark winner as new participant
-    let part = NewParticipation { player_id: &win.winner_id, picture_url: None, game_id: &game.id };
-    diesel::insert_into(participation::table)
-        .values(part)
-        .get_result::<Participation>(conn)
-        .await?;
+    let next = NewParticipation {
+        player_id: &win.winner_id,
+        picture_url: None,
+        game_id: &game.id,
+        picture_id: None,
+        latitude: None,
+        longitude: None,
+    };
+    store.create_participation(next).await?;
 
-    let def = vec![];
-    let data = ctx.data.read().await;
-    let sentence = data
-        .get::<crate::WinSentences>()
-        .unwrap_or(&def)
+    let sentence = settings
+        .win_sentences
         .choose(&mut rand::rng())
-        .map(String::as_str)
-        .unwrap_or("Bravo {}, à vous la main.")
-        .split("{}")
-        .collect::<Vec<_>>();
-    let (left, right) = match sentence.as_slice() {
-        [l, r, ..] => (*l, *r),
-        _ => ("Bravo ", ", à vous la main."),
+        .map(|sentence| sentence.split("{}").collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let locale = env.locale;
+    let mut reply = MessageBuilder::new();
+    match sentence.as_slice() {
+        [left, right, ..] => reply.push(left).mention(winner).push(right),
+        _ => reply.push(tr!(locale, "win-default", winner = winner.mention().to_string())),
     };
+    if settings.scoring == Scoring::Speed {
+        let duration = format_duration(found_in, locale);
+        let (points, count) = (score::format(score.into(), locale), score::count(score.into()));
+        reply.push("\n").push(tr!(
+            locale,
+            "win-speed",
+            duration = duration,
+            points = points,
+            count = count
+        ));
+    }
+    if poster_score > 0 {
+        let points = score::format(poster_score.into(), locale);
+        let count = score::count(poster_score.into());
+        let poster = part.player().mention().to_string();
+        reply.push("\n").push(tr!(
+            locale,
+            "win-poster",
+            points = points,
+            count = count,
+            poster = poster
+        ));
+    }
+    if settings.reveal_location
+        && let Some(location) = part.location()
+    {
+        let url = location.osm_url();
+        reply.push("\n").push(tr!(locale, "win-location", location = location.to_string()));
+        reply.push("\n").push(url);
+    }
+    Ok(Some(reply.build()))
+}
 
-    Ok(Some(MessageBuilder::new().push(left).mention(winner).push(right).build()))
+/// Give a hint about one's picture, lowering the points of whoever finds it.
+#[instrument(skip(env, msg, store))]
+pub async fn hint(env: &Env, msg: &Message, store: &mut dyn GameStore) -> StringResult {
+    let game = msg.game(store).await?;
+    let (game, part) = match game {
+        Some((game, Some(part))) => (game, part),
+        Some(_) => return Err(Error::NoParticipant),
+        None => return Ok(None),
+    };
+
+    if part.player_id != msg.author.id.to_string() {
+        return Err(Error::NotYourTurn);
+    }
+    if part.picture_url.is_none() {
+        return Err(Error::YouPostedNoPic);
+    }
+    let hint = msg.args.join(" ").trim().to_owned();
+    if hint.is_empty() {
+        return Err(Error::MissingHint);
+    }
+
+    let part = store.add_hint(&part, &hint).await?;
+    let event = Event::new(Action::Hint).actor(msg.author.id).before(part.id).details(&hint);
+    audit::record(env, store, &game, event).await?;
+
+    let settings = env.settings(store, &game).await?;
+    let (points, _) = score::win_scores(&settings, Utc::now() - part.updated_at, part.hints.len());
+    let player = msg.author.mention().to_string();
+    let hint = MessageBuilder::new().push_safe(&hint).build();
+    let (count, points) = (score::count(points.into()), score::format(points.into(), env.locale));
+    Ok(Some(format!(
+        "{}\n{}",
+        tr!(env.locale, "hint", player = player, hint = hint),
+        tr!(env.locale, "hint-points", points = points, count = count)
+    )))
 }
 
-pub async fn show(ctx: Context, msg: Message, conn: &mut AsyncPgConnection) -> CreateMessageResult {
-    tracing::info!("Show command invoked");
-    let Some((game, _)) = msg.game(conn).await? else { return Ok(None) };
+/// Revert the last win or skip of the game.
+#[instrument(skip(env, msg, store))]
+pub async fn undo(env: &Env, msg: &Message, store: &mut dyn GameStore) -> StringResult {
+    let Some((game, current)) = msg.game(store).await? else { return Ok(None) };
+    // Undoing would throw the picture of the next hand away
+    if current.is_some_and(|part| part.picture_url.is_some()) {
+        return Err(Error::NextPicturePosted);
+    }
 
-    let page = msg.content.split(' ').nth(1).and_then(|p| p.parse().ok()).unwrap_or(1);
-    if page < 1 {
-        return Err(Error::InvalidPage);
+    let last = store
+        .participations(&game)
+        .await?
+        .into_iter()
+        .filter_map(|(part, win)| Some((part.won_at.or(part.skipped_at)?, part, win)))
+        .max_by_key(|(at, _, _)| *at);
+    let Some((at, part, win)) = last else { return Err(Error::NothingToUndo) };
+    // Scores frozen by a reset or the end of a season stay as is
+    if win.as_ref().is_some_and(|win| win.reset) {
+        return Err(Error::NothingToUndo);
+    }
+
+    // The one who gave the win, or who skipped their own turn
+    let author = match &win {
+        Some(win) if part.is_win => Some(&win.player_id),
+        _ if part.is_auto_skip => None,
+        _ => Some(&part.player_id),
+    };
+    let settings = env.settings(store, &game).await?;
+    let in_time = Utc::now() - at <= settings.undo_window;
+    if !(in_time && author == Some(&msg.author.id.to_string())) {
+        let permissions = env.transport.permissions(game.guild(), msg.author.id).await?;
+        if !permissions.kick_members() && !permissions.administrator() {
+            return Err(Error::UndoNotAllowed);
+        }
     }
 
-    let (title, board) = scoreboard_message(&ctx, conn, game, msg.guild_id.unwrap(), page).await?;
+    let won = part.is_win;
+    let part = store.undo(&part).await?;
+    info!("Undid {win:?} of participation {part:?}");
+    let event = Event::new(Action::Undo)
+        .actor(msg.author.id)
+        .target(&part.player_id)
+        .before(win.as_ref().map(|win| win.id))
+        .after(part.id);
+    audit::record(env, store, &game, event).await?;
+
+    let player = part.player().mention().to_string();
+    let back = match part.picture_url {
+        Some(_) => tr!(env.locale, "undo-back-picture", player = player),
+        None => tr!(env.locale, "undo-back", player = player),
+    };
+    Ok(Some(match win {
+        Some(win) if won => {
+            let winner = format!("<@{}>", win.winner_id);
+            tr!(env.locale, "undo-win", winner = winner, back = back)
+        }
+        _ => tr!(env.locale, "undo-skip", back = back),
+    }))
+}
+
+pub async fn show(env: &Env, msg: &Message, store: &mut dyn GameStore) -> ReplyResult {
+    tracing::info!("Show command invoked");
+    if let Some(("season", args)) = msg.args.split_first().map(|(a, args)| (a.as_str(), args)) {
+        return season(env, msg, store, args).await;
+    }
+    let Some((game, _)) = msg.game(store).await? else { return Ok(None) };
 
-    Ok(Some(Box::new(move |m| m.embed(|e| e.title(title).colour(Colour::GOLD).fields(board)))))
+    let page = msg.args.first().and_then(|p| p.parse().ok()).unwrap_or(1);
+    Ok(Some(Reply::Paged(paging::render(env, store, &game, View::Scoreboard, page).await?)))
 }
 
-pub async fn scoreboard_message(
-    ctx: &Context,
-    conn: &mut AsyncPgConnection,
-    game: Game,
+/// Page of the current scores, along with the page count.
+pub async fn scoreboard_view(
+    env: &Env,
+    store: &mut dyn GameStore,
+    game: &Game,
+    page: usize,
+) -> Result<(Embed, usize)> {
+    let wins = store.scoreboard(game, None).await?;
+    let settings = env.settings(store, game).await?;
+    let per_page = settings.page_size;
+    let title =
+        |page_count| tr!(env.locale, "scoreboard-title", page = page, page_count = page_count);
+
+    if settings.scoreboard_image && !wins.is_empty() {
+        let best = wins[0].total();
+        let week_ago = Utc::now() - TimeDelta::days(7);
+        let recent = store.wins(game, None).await?;
+        let deltas = store::tally(recent.iter().filter(|(_, win)| win.created_at > week_ago));
+
+        let (ranked, page_count) = ranked_page(env, game.guild(), wins, page, per_page).await?;
+        let rows = ranked
+            .into_iter()
+            .map(|(rank, name, score)| {
+                let delta = deltas.iter().find(|d| d.player_id == score.player_id);
+                let delta = delta.map(PlayerScore::total).unwrap_or_default();
+                scoreboard::Row { rank, name, score, delta }
+            })
+            .collect::<Vec<_>>();
+        let locale = env.locale;
+        let data = render::spawn(move || scoreboard::draw(&rows, best, locale)).await?;
+
+        let embed = Embed {
+            title: Some(title(page_count)),
+            colour: Some(Colour::GOLD),
+            attachment: Some(File { name: format!("scoreboard-{page}.png"), data }),
+            ..Default::default()
+        };
+        return Ok((embed, page_count));
+    }
+
+    let (board, page_count) = scoreboard_page(env, game.guild(), wins, page, per_page).await?;
+    let embed = Embed {
+        title: Some(title(page_count)),
+        colour: Some(Colour::GOLD),
+        fields: board,
+        ..Default::default()
+    };
+    Ok((embed, page_count))
+}
+
+/// Render a page of scores as embed fields, along with the page count.
+pub async fn scoreboard_page(
+    env: &Env,
     guild: GuildId,
+    wins: Vec<PlayerScore>,
     page: usize,
-) -> Result<(String, Vec<(String, i64, bool)>)> {
-    let wins = win::table
-        .group_by(win::winner_id)
-        .select((sum(win::score), win::winner_id))
-        .filter(not(win::reset))
-        .inner_join(participation::table)
-        .filter(participation::game_id.eq(&game.id))
-        .order_by(sum(win::score).desc())
-        .load::<(Option<i64>, String)>(conn)
-        .await?;
+    per_page: usize,
+) -> Result<(Vec<(String, String, bool)>, usize)> {
+    let (ranked, page_count) = ranked_page(env, guild, wins, page, per_page).await?;
+    let locale = env.locale;
 
-    let per_page = 10;
-    let page_count = wins.len() / per_page + 1;
+    let board = ranked
+        .into_iter()
+        .map(|(rank, name, score)| {
+            let position = match rank {
+                1 => "🥇".to_owned(),
+                2 => "🥈".to_owned(),
+                3 => "🥉".to_owned(),
+                p => p.to_string(),
+            };
+            // Split between finding and posting once posting earned something
+            let points = match score.poster {
+                0 => score::format(score.total(), locale),
+                poster => format!(
+                    "{} (🔎 {} · 📷 {})",
+                    score::format(score.total(), locale),
+                    score::format(score.finder, locale),
+                    score::format(poster, locale)
+                ),
+            };
+            (format!("{position}. {name}"), points, false)
+        })
+        .collect();
+
+    Ok((board, page_count))
+}
 
-    if page > page_count {
-        return Err(Error::InvalidPage);
-    }
+/// Page of scores with the rank and display name of each player, along with the page count.
+async fn ranked_page(
+    env: &Env,
+    guild: GuildId,
+    wins: Vec<PlayerScore>,
+    page: usize,
+    per_page: usize,
+) -> Result<(Vec<(usize, String, PlayerScore)>, usize)> {
+    let page_count = paging::page_count(wins.len(), per_page, page)?;
 
-    let cache = ctx.cache().await;
-    let board = wins
+    let ranked = wins
         .into_iter()
         .skip((page - 1) * per_page)
         .take(per_page)
-        .filter_map(|(score, id)| Some((score?, id)))
-        .map(|(score, id)| (score, id.parse::<u64>().unwrap(), cache.clone(), info_span!("map_fn")))
+        .map(|score| (score, info_span!("map_fn")))
         .enumerate()
-        .map(|(i, (score, id, cache, span))| {
+        .map(|(i, (score, span))| {
             async move {
-                tracing::debug!("Scoreboard entry ({i}, ({score}, {id}))");
-                let position = match i + 1 + ((page - 1) * per_page) as usize {
-                    1 => "🥇".to_owned(),
-                    2 => "🥈".to_owned(),
-                    3 => "🥉".to_owned(),
-                    p => p.to_string(),
-                };
-                let member = cache.member(&ctx, guild, id).await;
-                let name = match member {
-                    Ok(member) => Ok(member.display_name().to_string()),
-                    Err(e) => {
-                        tracing::warn!(
-                        "Failed to fetch member #{i} {id}: {e}, falling back to fetching the user. \
-                        Maybe the user left the guild?",
-                    );
-                        cache.user(&ctx, id).await.map(|user| user.name)
-                    }
-                };
-                name.map(|name| (format!("{position}. {name}"), score, false))
+                let id = score.player()?;
+                tracing::debug!("Scoreboard entry ({i}, ({score:?}, {id}))");
+                let rank = i + 1 + (page - 1) * per_page;
+                let member = env.transport.member(guild, id).await;
+                member.map(|member| (rank, member.name, score))
             }
             .instrument(span)
         });
 
     let span = info_span!("wins_map");
-    let board = futures::future::join_all(board)
+    let ranked = futures::future::join_all(ranked)
         .instrument(span)
         .await
         .into_iter()
         .collect::<Result<Vec<_>, _>>()?;
 
-    Ok((format!("👑 👑 👑 Scores ({page}/{page_count}) 👑 👑 👑"), board))
+    Ok((ranked, page_count))
+}
+
+/// List the seasons of the game, or show the scoreboard of one of them.
+#[instrument(skip(env, msg, store))]
+pub async fn season(
+    env: &Env,
+    msg: &Message,
+    store: &mut dyn GameStore,
+    args: &[String],
+) -> ReplyResult {
+    let Some((game, _)) = msg.game(store).await? else { return Ok(None) };
+    let seasons = store.seasons(&game).await?;
+
+    let (name, page) = match args {
+        [] => {
+            let locale = env.locale;
+            if seasons.is_empty() {
+                return Ok(Some(Reply::Text(locale.tr("season-none", &[]))));
+            }
+            let seasons = seasons
+                .iter()
+                .map(|s| {
+                    let (name, start) = (s.name.as_str(), locale.format_date(s.started_at));
+                    match s.ended_at {
+                        Some(end) => {
+                            let end = locale.format_date(end);
+                            tr!(locale, "season-ended", name = name, start = start, end = end)
+                        }
+                        None => tr!(locale, "season-current", name = name, start = start),
+                    }
+                })
+                .join("\n");
+            let title = locale.tr("season-list", &[]);
+            return Ok(Some(Reply::Text(format!("{title}\n{seasons}"))));
+        }
+        [name] => (name, 1),
+        [name, page] => (name, page.parse().map_err(|_| Error::InvalidPage)?),
+        [..] => return Err(Error::UnknownArguments),
+    };
+    let view = View::Season(name.clone());
+    Ok(Some(Reply::Paged(paging::render(env, store, &game, view, page).await?)))
+}
+
+/// Page of the scores of a season, along with the page count.
+pub async fn season_view(
+    env: &Env,
+    store: &mut dyn GameStore,
+    game: &Game,
+    name: &str,
+    page: usize,
+) -> Result<(Embed, usize)> {
+    let seasons = store.seasons(game).await?;
+    let season = seasons.iter().find(|s| s.name == name).ok_or(Error::UnknownSeason)?;
+
+    // The current season is not frozen yet
+    let wins = store.scoreboard(game, season.ended_at.map(|_| season.id)).await?;
+    let per_page = env.settings(store, game).await?.page_size;
+    let (board, page_count) = scoreboard_page(env, game.guild(), wins, page, per_page).await?;
+
+    let embed = Embed {
+        title: Some(tr!(
+            env.locale,
+            "season-title",
+            name = season.name.as_str(),
+            page = page,
+            page_count = page_count
+        )),
+        colour: Some(Colour::GOLD),
+        fields: board,
+        ..Default::default()
+    };
+    Ok((embed, page_count))
+}
+
+/// Browse the pictures played in the game, possibly only the ones a player posted or found.
+#[instrument(skip(env, msg, store))]
+pub async fn history(env: &Env, msg: &Message, store: &mut dyn GameStore) -> ReplyResult {
+    let Some((game, _)) = msg.game(store).await? else { return Ok(None) };
+
+    let player = match msg.mentions.as_slice() {
+        [] => None,
+        [player] => Some(player.id),
+        [..] => return Err(Error::UnknownArguments),
+    };
+    // Mentions are in the arguments of prefixed commands, but not of slash ones
+    let page = match msg.args.iter().filter(|arg| !arg.starts_with("<@")).collect_vec()[..] {
+        [] => 1,
+        [page] => page.parse().map_err(|_| Error::InvalidPage)?,
+        [..] => return Err(Error::UnknownArguments),
+    };
+    let view = View::History(player);
+    Ok(Some(Reply::Paged(paging::render(env, store, &game, view, page).await?)))
+}
+
+/// Page of the game history, along with the page count.
+pub async fn history_view(
+    env: &Env,
+    store: &mut dyn GameStore,
+    game: &Game,
+    player: Option<UserId>,
+    page: usize,
+) -> Result<(Embed, usize)> {
+    let player_id = player.map(|player| player.to_string());
+    let played = store
+        .participations(game)
+        .await?
+        .into_iter()
+        .filter(|(part, _)| part.is_win || part.is_skip)
+        .filter(|(part, win)| {
+            let Some(player_id) = &player_id else { return true };
+            let found = part.is_win && win.as_ref().is_some_and(|w| w.winner_id == *player_id);
+            part.player_id == *player_id || found
+        })
+        .rev()
+        .collect_vec();
+
+    let per_page = env.settings(store, game).await?.page_size;
+    let page_count = paging::page_count(played.len(), per_page, page)?;
+    let locale = env.locale;
+    let description = match player {
+        _ if played.is_empty() => Some(locale.tr("history-empty", &[])),
+        Some(player) => Some(tr!(locale, "history-player", player = player.mention().to_string())),
+        None => None,
+    };
+
+    let fields =
+        played.into_iter().skip((page - 1) * per_page).take(per_page).map(|(part, win)| {
+            let ended_at = part.won_at.or(part.skipped_at).unwrap_or(part.updated_at);
+            let mut value = format!("📷 {}", part.player().mention());
+            if let Some(url) = &part.picture_url {
+                value.push_str(&format!(" · [{}]({url})", locale.tr("history-picture", &[])));
+            }
+            value.push('\n');
+            match (&win, part.won_at) {
+                (Some(win), Some(won_at)) if part.is_win => {
+                    let finder = format!("<@{}>", win.winner_id);
+                    let duration = format_duration(won_at - part.updated_at, locale);
+                    value.push_str(&tr!(
+                        locale,
+                        "history-found",
+                        finder = finder,
+                        duration = duration
+                    ))
+                }
+                _ if part.is_auto_skip => value.push_str(&locale.tr("history-auto-skipped", &[])),
+                _ => value.push_str(&locale.tr("history-skipped", &[])),
+            }
+            (locale.format_datetime(ended_at), value, false)
+        });
+
+    let embed = Embed {
+        title: Some(tr!(locale, "history-title", page = page, page_count = page_count)),
+        colour: Some(Colour::BLUE),
+        description,
+        fields: fields.collect(),
+        ..Default::default()
+    };
+    Ok((embed, page_count))
+}
+
+/// Number of players plotted when none is mentioned.
+const CHART_PLAYERS: usize = 5;
+
+/// Plot the scores since the last reset, or until a given reset, of the mentioned players or the
+/// best ones.
+#[instrument(skip(env, msg, store))]
+pub async fn chart(env: &Env, msg: &Message, store: &mut dyn GameStore) -> ReplyResult {
+    let Some((game, _)) = msg.game(store).await? else { return Ok(None) };
+
+    // Mentions are in the arguments of prefixed commands, but not of slash ones
+    let reset_id = match msg.args.iter().filter(|arg| !arg.starts_with("<@")).collect_vec()[..] {
+        [] => None,
+        [id] => Some(id.parse::<Uuid>().map_err(|_| Error::InvalidResetId)?),
+        [..] => return Err(Error::UnknownArguments),
+    };
+    // A window goes from the previous reset to the next one
+    let resets = store.resets(&game).await?;
+    let (start, end) = match reset_id {
+        None => (resets.last().map_or(game.created_at, |(_, at)| *at), Utc::now()),
+        Some(id) => {
+            let i =
+                resets.iter().position(|(reset, _)| *reset == id).ok_or(Error::InvalidResetId)?;
+            (i.checked_sub(1).map_or(game.created_at, |i| resets[i].1), resets[i].1)
+        }
+    };
+
+    let locale = env.locale;
+    let wins = store.wins(&game, reset_id).await?;
+    if wins.is_empty() {
+        return Ok(Some(Reply::Text(locale.tr("chart-empty", &[]))));
+    }
+    let players = match msg.mentions.as_slice() {
+        [] => store::tally(&wins)
+            .into_iter()
+            .take(CHART_PLAYERS)
+            .map(|score| score.player())
+            .collect::<Result<Vec<_>>>()?,
+        mentions => mentions.iter().map(|user| user.id).unique().collect_vec(),
+    };
+
+    let mut series = vec![];
+    for player in players.into_iter().take(chart::PALETTE.len()) {
+        let member = env.transport.member(game.guild(), player).await?;
+        let points = stats::evolution(&player.to_string(), &wins);
+        series.push(chart::Series { name: member.name, points });
+    }
+    let data = render::spawn(move || chart::draw(&series, start, end, locale)).await?;
+
+    let title = match reset_id {
+        Some(_) => tr!(locale, "chart-title-reset", date = locale.format_datetime(end)),
+        None => locale.tr("chart-title", &[]),
+    };
+    Ok(Some(Reply::Embed(Embed {
+        title: Some(title),
+        colour: Some(Colour::GOLD),
+        attachment: Some(File { name: "chart.png".to_owned(), data }),
+        ..Default::default()
+    })))
 }
 
-//#[instrument(skip(ctx, msg, conn))]
-pub async fn pic(ctx: Context, msg: Message, conn: &mut AsyncPgConnection) -> CreateMessageResult {
-    let game = msg.game(conn).await?;
+#[instrument(skip(env, msg, store))]
+pub async fn stats(env: &Env, msg: &Message, store: &mut dyn GameStore) -> ReplyResult {
+    let Some((game, _)) = msg.game(store).await? else { return Ok(None) };
+
+    let player = match msg.mentions.as_slice() {
+        [] => msg.author,
+        [player] => *player,
+        [..] => return Err(Error::UnknownArguments),
+    };
+    if player.bot {
+        return Err(Error::StfuBot);
+    }
+
+    let parts = store.participations(&game).await?;
+    let stats = PlayerStats::new(&player.id.to_string(), &parts);
+
+    let member = env
+        .transport
+        .member(game.guild(), player.id)
+        .instrument(info_span!("Transport::member"))
+        .await?;
+    let locale = env.locale;
+    let favourite_finder = match stats.favourite_finder {
+        Some((id, count)) => {
+            let finder = env.transport.member(game.guild(), parse_user_id(&id)?).await?;
+            tr!(locale, "stats-finder", player = finder.name, count = count)
+        }
+        None => locale.tr("stats-nobody", &[]),
+    };
+
+    let field = |id: &str, value: String| (locale.tr(id, &[]), value, true);
+    let average_find_time = stats.average_find_time.map(|time| format_duration(time, locale));
+    let embed = Embed {
+        title: Some(tr!(locale, "stats-title", player = member.name.as_str())),
+        colour: Some(Colour::BLUE),
+        author: Some((member.name, member.avatar)),
+        fields: vec![
+            field("stats-points", score::format(stats.points, locale)),
+            field("stats-finder-points", score::format(stats.finder_points, locale)),
+            field("stats-poster-points", score::format(stats.poster_points, locale)),
+            field("stats-total-points", score::format(stats.total_points, locale)),
+            field("stats-pictures", stats.pictures.to_string()),
+            field("stats-finds", stats.finds.to_string()),
+            field("stats-skips", stats.skips.to_string()),
+            field("stats-auto-skips", stats.auto_skips.to_string()),
+            field("stats-average-find-time", average_find_time.unwrap_or_else(|| "-".to_owned())),
+            field("stats-longest-streak", stats.longest_streak.to_string()),
+            field("stats-favourite-finder", favourite_finder),
+        ],
+        ..Default::default()
+    };
+    Ok(Some(Reply::Embed(embed)))
+}
+
+//#[instrument(skip(env, msg, store))]
+pub async fn pic(env: &Env, msg: &Message, store: &mut dyn GameStore) -> ReplyResult {
+    let game = msg.game(store).await?;
     let part = match game {
         Some((_, Some(part))) => part,
         Some(_) => return Err(Error::NoParticipant),
@@ -238,35 +687,55 @@ pub async fn pic(ctx: Context, msg: Message, conn: &mut AsyncPgConnection) -> Cr
 
     let player = part.player();
     let Some(url) = part.picture_url else {
-        return Ok(Some(Box::new(move |m| {
-            m.content(
-                MessageBuilder::new()
-                    .push("C'est au tour de ")
-                    .mention(&player)
-                    .push(" qui n'a pas encore posté de photo.")
-                    .build(),
-            )
-        })));
+        let player = player.mention().to_string();
+        return Ok(Some(Reply::Text(tr!(env.locale, "pic-not-posted", player = player))));
     };
 
-    let player = player.to_user(&ctx.http).instrument(info_span!("UserId::to_user")).await?;
-    let nick = player
-        .nick_in(&ctx.http, msg.guild_id.unwrap())
-        .instrument(info_span!("User::nick_in"))
-        .await
-        .unwrap_or_else(|| player.name.clone());
+    let member = env
+        .transport
+        .member(msg.guild_id.unwrap(), player)
+        .instrument(info_span!("Transport::member"))
+        .await?;
 
-    Ok(Some(Box::new(move |m| {
-        m.embed(|e| e.author(|a| a.name(nick).icon_url(player.face())).image(url))
-    })))
+    let attachment = match (&env.pictures, part.picture_id) {
+        (Some(pictures), Some(id)) => stored_picture(pictures, store, id).await?,
+        _ => None,
+    };
+    let hints = part.hints.into_iter().enumerate();
+    let embed = Embed {
+        author: Some((member.name, member.avatar)),
+        image: Some(url),
+        attachment,
+        fields: hints
+            .map(|(i, hint)| (tr!(env.locale, "pic-hint", number = i + 1), hint, false))
+            .collect(),
+        ..Default::default()
+    };
+    Ok(Some(Reply::Embed(embed)))
 }
 
-#[instrument(skip(_ctx, msg, conn))]
-pub async fn change(_ctx: Context, msg: Message, conn: &mut AsyncPgConnection) -> StringResult {
-    let game = msg.game(conn).await?;
+/// Stored copy of a picture, `None` if it is missing from the storage.
+async fn stored_picture(
+    pictures: &Pictures,
+    store: &mut dyn GameStore,
+    id: Uuid,
+) -> Result<Option<File>> {
+    let Some(picture) = store.picture(id).await? else { return Ok(None) };
+    match pictures.load(&picture).await {
+        Ok(data) => Ok(Some(File { name: pictures::file_name(&picture), data })),
+        Err(e) => {
+            tracing::warn!("Failed to load stored picture {picture:?}, using its URL: {e}");
+            Ok(None)
+        }
+    }
+}
 
-    let part = match game {
-        Some((_, Some(part))) => part,
+#[instrument(skip(env, msg, store))]
+pub async fn change(env: &Env, msg: &Message, store: &mut dyn GameStore) -> StringResult {
+    let game = msg.game(store).await?;
+
+    let (game, part) = match game {
+        Some((game, Some(part))) => (game, part),
         Some(_) => return Err(Error::NoParticipant),
         None => return Ok(None),
     };
@@ -275,13 +744,9 @@ pub async fn change(_ctx: Context, msg: Message, conn: &mut AsyncPgConnection) -
         return Err(Error::NotYourTurn);
     }
 
-    diesel::update(&part)
-        .set((
-            participation::picture_url.eq(Option::<String>::None),
-            participation::updated_at.eq(now),
-        ))
-        .execute(conn)
-        .await?;
+    store.set_picture(&part, None, None, None).await?;
+    let event = Event::new(Action::Change).actor(msg.author.id).before(part.id);
+    audit::record(env, store, &game, event).await?;
 
-    Ok(Some("Ok, ok, puisque t'insistes ...".to_owned()))
+    Ok(Some(env.locale.tr("change", &[])))
 }
@@ -1,7 +1,11 @@
 //! Bot-ticelli config file
 
+use std::{net::SocketAddr, path::PathBuf};
+
 use serde::{Deserialize, Serialize};
 
+use crate::{i18n::Locale, season::Rollover};
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
@@ -9,6 +13,7 @@ pub struct Config {
     pub db_config: DbConfig,
     pub bot_config: BotConfig,
     pub tracing_config: Option<TracingConfig>,
+    pub storage: Option<StorageConfig>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -22,6 +27,9 @@ pub struct AuthConfig {
 pub struct DbConfig {
     /// Database URL
     pub database_url: String,
+    /// Apply pending migrations on startup
+    #[serde(default = "yes")]
+    pub run_migrations: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -35,6 +43,15 @@ pub struct BotConfig {
     pub ratelimit: Option<RatelimitConfig>,
     /// Automatic picture skipping
     pub auto_skip: Option<AutoskipConfig>,
+    /// Automatic season rollover
+    pub season_rollover: Option<Rollover>,
+    /// Language of the guilds that did not pick one
+    #[serde(default)]
+    pub locale: Locale,
+}
+
+const fn yes() -> bool {
+    true
 }
 
 const fn zero() -> u64 {
@@ -45,6 +62,18 @@ const fn one() -> u32 {
     1
 }
 
+const fn max_picture_size() -> u64 {
+    25 * 1024 * 1024
+}
+
+const fn five_minutes() -> u64 {
+    300
+}
+
+const fn ten() -> u32 {
+    10
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct RatelimitConfig {
@@ -73,7 +102,35 @@ pub struct AutoskipConfig {
 pub struct TracingConfig {
     /// Where to send opentelemetry data, in OTEL format. `<ip|hostname>:<port>`.
     pub otel: Option<String>,
-    // TODO sentry, prometheus
+    /// Where to expose prometheus metrics over HTTP. `<ip>:<port>`.
+    pub prometheus: Option<SocketAddr>,
+    /// Where to report internal errors
+    pub error_reporting: Option<ErrorReportingConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ErrorReportingConfig {
+    /// Sentry-compatible DSN to send errors to. `https://<key>@<host>/<project>`.
+    pub sentry_dsn: Option<String>,
+    /// File to append errors to, one JSON object per line.
+    pub file: Option<PathBuf>,
+    /// Delay, in seconds, during which the same error is only reported once.
+    #[serde(default = "five_minutes")]
+    pub dedup_window: u64,
+    /// Number of errors reported per minute at most.
+    #[serde(default = "ten")]
+    pub rate_limit: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct StorageConfig {
+    /// Directory to keep a copy of the pictures in
+    pub directory: PathBuf,
+    /// Biggest picture to download, in bytes
+    #[serde(default = "max_picture_size")]
+    pub max_size: u64,
 }
 
 pub fn load_config(path: &str) -> Result<Config, Box<dyn std::error::Error>> {
@@ -1,81 +1,104 @@
-//! Background jobs, like auto-skip
+//! Background jobs, like auto-skip or season rollover
 
-use std::{sync::Arc, time::Duration};
+use std::time::Duration;
 
-use diesel::{ExpressionMethods, QueryDsl, dsl::*};
-use diesel_async::{AsyncPgConnection, RunQueryDsl, pooled_connection::deadpool::Pool};
-use serenity::{http::Http, utils::MessageBuilder};
+use chrono::Utc;
+use diesel_async::{AsyncPgConnection, pooled_connection::deadpool::Pool};
+use serenity::model::mention::Mentionable;
 use tokio::time::interval;
 use tracing::{error, info, instrument};
 
 use crate::{
-    config::AutoskipConfig,
+    audit::{self, Action, Event},
+    engine::Env,
     error::Result,
-    models::{Game, Participation, game, participation},
+    i18n::tr,
+    metrics,
+    report::{self, Context},
+    season,
+    store::GameStore,
+    transport::Reply,
 };
 
-pub async fn task_auto_skip(
-    http: Arc<Http>,
-    pool: Pool<AsyncPgConnection>,
-    config: AutoskipConfig,
-) -> ! {
+pub async fn task_auto_skip(env: Env, pool: Pool<AsyncPgConnection>) -> ! {
     let mut timer = interval(Duration::from_secs(60));
     loop {
         timer.tick().await;
-        if let Err(e) = try_task_auto_skip(&http, &pool, &config).await {
+        if let Err(e) = try_task_auto_skip(&env, &pool).await {
             error!("task auto skip failed: {e}");
+            report::capture(&e, Context::task("auto_skip"));
         }
     }
 }
 
 #[instrument(skip_all, err)]
-async fn try_task_auto_skip(
-    http: &Http,
-    pool: &Pool<AsyncPgConnection>,
-    config: &AutoskipConfig,
-) -> Result<()> {
+async fn try_task_auto_skip(env: &Env, pool: &Pool<AsyncPgConnection>) -> Result<()> {
     let mut conn = pool.get().await?;
+    let now = Utc::now();
 
-    let autoskip_delay = i64::from(config.autoskip_delay).seconds();
-    let warn_delay = i64::from(config.warn_delay).seconds();
+    for (part, game) in conn.hands().await? {
+        let env = &env.for_guild(&mut *conn, Some(game.guild())).await?;
+        let settings = env.settings(&mut *conn, &game).await?;
+        let Some(autoskip_delay) = settings.autoskip_delay else { continue };
+        let skip_at = part.timer_start() + autoskip_delay;
 
-    let parts = participation::table
-        .filter(not(participation::is_win))
-        .filter(not(participation::is_skip))
-        .filter(participation::warned_at.is_null())
-        .filter((participation::updated_at + autoskip_delay - warn_delay).le(now))
-        .filter((participation::updated_at + autoskip_delay).gt(now))
-        .inner_join(game::table)
-        .load::<(Participation, Game)>(&mut conn)
-        .await?;
-    for (part, game) in parts {
-        diesel::update(&part).set(participation::warned_at.eq(now)).execute(&mut conn).await?;
-        let m = MessageBuilder::new()
-            .push("⏰ ")
-            .mention(&part.player())
-            .push(" ça va autoskip !")
-            .build();
-        game.channel().say(http, m).await?;
-    }
+        if now < skip_at {
+            // Warn the player once the warning delay is elapsed
+            if part.warned_at.is_none() && now >= skip_at - settings.warn_delay {
+                conn.mark_warned(&part).await?;
+                metrics::AUTOSKIP_WARNINGS.inc();
+                let player = part.player().mention().to_string();
+                let m = tr!(env.locale, "autoskip-warning", player = player);
+                env.transport.send(game.channel(), Reply::Text(m)).await?;
+            }
+            continue;
+        }
 
-    // Participations that we need to skip *now*
-    let parts = participation::table
-        .filter(not(participation::is_win))
-        .filter(not(participation::is_skip))
-        .filter((participation::updated_at + autoskip_delay).le(now))
-        .inner_join(game::table)
-        .load::<(Participation, Game)>(&mut conn)
-        .await?;
-    for (part, game) in parts {
         info!("Auto-skipping participation {part:?}");
-        part.skip(&mut conn, true).await?;
+        let skipped = conn.skip(&part, settings.skip_penalty, true).await?;
+        metrics::AUTOSKIPS.inc();
+        let event = Event::new(Action::AutoSkip)
+            .target(&part.player_id)
+            .before(part.id)
+            .after(skipped.win_id);
+        audit::record(env, &mut *conn, &game, event).await?;
+        // Not in a transaction, the skip is already saved
+        env.audit.flush(&*env.transport, true).await;
 
-        let m = MessageBuilder::new()
-            .push("Sorry ")
-            .mention(&part.player())
-            .push(", les gens sont nuls, prends ton point en moins ¯\\_(ツ)_/¯.")
-            .build();
-        game.channel().say(http, m).await?;
+        let m = tr!(env.locale, "autoskip", player = part.player().mention().to_string());
+        env.transport.send(game.channel(), Reply::Text(m)).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn task_season_rollover(env: Env, pool: Pool<AsyncPgConnection>) -> ! {
+    let mut timer = interval(Duration::from_secs(600));
+    loop {
+        timer.tick().await;
+        if let Err(e) = try_task_season_rollover(&env, &pool).await {
+            error!("task season rollover failed: {e}");
+            report::capture(&e, Context::task("season_rollover"));
+        }
+    }
+}
+
+#[instrument(skip_all, err)]
+async fn try_task_season_rollover(env: &Env, pool: &Pool<AsyncPgConnection>) -> Result<()> {
+    let mut conn = pool.get().await?;
+
+    for game in conn.games().await? {
+        let env = &env.for_guild(&mut *conn, Some(game.guild())).await?;
+        let res = conn
+            .build_transaction()
+            .serializable()
+            .run(|conn| Box::pin(season::rollover(env, conn, &game)))
+            .await;
+        env.audit.flush(&*env.transport, res.is_ok()).await;
+        let replies = res?;
+        for reply in replies {
+            env.transport.send(game.channel(), reply).await?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,367 @@
+//! Game engine, turning incoming events into replies without knowing anything about Discord
+
+use std::sync::Arc;
+
+use serenity::model::{
+    Permissions,
+    id::{ChannelId, GuildId, MessageId},
+};
+use tracing::instrument;
+
+use crate::{
+    audit::Mirrors,
+    cmd::{self, ReplyResult, StringResult},
+    config::BotConfig,
+    error::{Error, Result},
+    extensions::MessageExt,
+    i18n::{Locale, tr},
+    metrics,
+    models::*,
+    paging::{self, PageState, Paginator},
+    pictures::{self, Duplicate, DuplicatePolicy, Pictures, Saved},
+    report,
+    settings::Settings,
+    store::GameStore,
+    transport::{Message, Reaction, Reply, Transport},
+};
+
+/// Everything a command needs besides the store.
+#[derive(Clone)]
+pub struct Env {
+    pub transport: Arc<dyn Transport>,
+    pub config: Arc<BotConfig>,
+    /// Storage of the pictures, only keeping their URL if `None`
+    pub pictures: Option<Arc<Pictures>>,
+    /// Paged messages browsable with reactions
+    pub pages: Arc<Paginator>,
+    /// Language of the replies
+    pub locale: Locale,
+    /// Audit events to mirror once the transaction recording them is over
+    pub audit: Arc<Mirrors>,
+}
+
+impl Env {
+    /// Same environment, speaking the language picked by a guild, with no audit mirror pending.
+    pub async fn for_guild(
+        &self,
+        store: &mut dyn GameStore,
+        guild: Option<GuildId>,
+    ) -> Result<Self> {
+        let locale = match guild {
+            Some(guild) => store.guild_settings(guild.0).await?.and_then(|s| s.locale),
+            None => None,
+        };
+        // Anything else than a locale is the default one
+        let locale = locale.and_then(|l| l.parse().ok()).unwrap_or(self.config.locale);
+        Ok(Self { locale, audit: Arc::default(), ..self.clone() })
+    }
+
+    /// Effective settings of a game, with the config file as fallback.
+    pub async fn settings(&self, store: &mut dyn GameStore, game: &Game) -> Result<Settings> {
+        Ok(Settings::new(&self.config, store.settings(game).await?.as_ref()))
+    }
+}
+
+/// Names of the commands understood by [`command`].
+pub const COMMANDS: &[&str] = &[
+    "win",
+    "skip",
+    "show",
+    "reset",
+    "pic",
+    "force_skip",
+    "start",
+    "force_win",
+    "change",
+    "stop",
+    "pause",
+    "resume",
+    "config",
+    "season",
+    "stats",
+    "undo",
+    "audit",
+    "hint",
+    "history",
+    "locale",
+    "chart",
+];
+
+/// Permissions needed to run a command, administrators being allowed everything.
+pub fn required_permissions(name: &str) -> Permissions {
+    match name {
+        "reset" | "start" | "stop" | "config" | "audit" | "locale" => Permissions::ADMINISTRATOR,
+        "force_skip" | "force_win" | "pause" | "resume" => Permissions::KICK_MEMBERS,
+        _ => Permissions::empty(),
+    }
+}
+
+/// Make sure the author of a command has the [`required_permissions`] on the guild.
+pub async fn check_permissions(env: &Env, name: &str, msg: &Message) -> Result<()> {
+    let required = required_permissions(name);
+    if required.is_empty() {
+        return Ok(());
+    }
+    let Some(guild) = msg.guild_id else { return Err(Error::MissingPermissions) };
+    let permissions = env.transport.permissions(guild, msg.author.id).await?;
+    if permissions.administrator() || permissions.contains(required) {
+        Ok(())
+    } else {
+        Err(Error::MissingPermissions)
+    }
+}
+
+/// Run a command by name. Permissions are expected to have been checked by the caller, see
+/// [`check_permissions`].
+#[instrument(skip(env, store, msg))]
+pub async fn command(
+    env: &Env,
+    store: &mut dyn GameStore,
+    name: &str,
+    msg: &Message,
+) -> ReplyResult {
+    metrics::COMMANDS.with_label_values(&[name]).inc();
+    let _timer = metrics::HANDLER_DURATION.with_label_values(&[name]).start_timer();
+
+    let res = dispatch(env, store, name, msg).await;
+    if let Err(e) = &res {
+        metrics::COMMAND_ERRORS.with_label_values(&[name, e.kind()]).inc();
+    }
+    res
+}
+
+async fn dispatch(env: &Env, store: &mut dyn GameStore, name: &str, msg: &Message) -> ReplyResult {
+    let reply = match name {
+        "win" => cmd::player::win(env, msg, store, false).await?.map(Reply::Text),
+        "skip" => cmd::player::skip(env, msg, store).await?.map(Reply::Text),
+        "show" => cmd::player::show(env, msg, store).await?,
+        "reset" => cmd::admin::reset(env, msg, store).await?,
+        "pic" => cmd::player::pic(env, msg, store).await?,
+        "force_skip" => cmd::admin::force_skip(env, msg, store).await?.map(Reply::Text),
+        "start" => cmd::admin::start(env, msg, store).await?.map(Reply::Text),
+        "force_win" => cmd::player::win(env, msg, store, true).await?.map(Reply::Text),
+        "change" => cmd::player::change(env, msg, store).await?.map(Reply::Text),
+        "stop" => cmd::admin::stop(env, msg, store).await?.map(Reply::Text),
+        "pause" => cmd::admin::pause(env, msg, store).await?.map(Reply::Text),
+        "resume" => cmd::admin::resume(env, msg, store).await?.map(Reply::Text),
+        "config" => cmd::admin::config(env, msg, store).await?.map(Reply::Text),
+        "season" => cmd::player::season(env, msg, store, &msg.args).await?,
+        "stats" => cmd::player::stats(env, msg, store).await?,
+        "undo" => cmd::player::undo(env, msg, store).await?.map(Reply::Text),
+        "audit" => cmd::admin::audit(env, msg, store).await?,
+        "hint" => cmd::player::hint(env, msg, store).await?.map(Reply::Text),
+        "history" => cmd::player::history(env, msg, store).await?,
+        "locale" => cmd::admin::locale(env, msg, store).await?.map(Reply::Text),
+        "chart" => cmd::player::chart(env, msg, store).await?,
+        _ => return Err(Error::UnknownArguments),
+    };
+    Ok(reply)
+}
+
+/// Send a reply, along with its reaction controls.
+pub async fn send(env: &Env, msg: &Message, reply: Reply) -> Result<()> {
+    let state = match &reply {
+        Reply::Paged(paged) => Some(paged.state.clone()),
+        _ => None,
+    };
+    let id = env.transport.send(msg.channel_id, reply).await?;
+    if let Some(state) = state {
+        add_controls(env, msg.channel_id, id, state).await?;
+    }
+    Ok(())
+}
+
+/// Track a paged message and add its page switching reactions.
+pub async fn add_controls(
+    env: &Env,
+    channel: ChannelId,
+    message: MessageId,
+    state: PageState,
+) -> Result<()> {
+    env.pages.register(message, state);
+    for control in paging::CONTROLS {
+        env.transport.react(channel, message, control).await?;
+    }
+    Ok(())
+}
+
+/// Download and store the picture of a message about to be played, before opening the
+/// transaction handling it with [`on_message`], so that it is not held open meanwhile.
+///
+/// The checks of [`on_message`] are previewed, not to download every picture posted around a
+/// game. Failing to store is reported but does not prevent playing, the picture URL being still
+/// there.
+pub async fn save_picture(
+    env: &Env,
+    store: &mut dyn GameStore,
+    msg: &Message,
+) -> Result<Option<Saved>> {
+    let Some(pictures) = &env.pictures else { return Ok(None) };
+    let Some(url) = msg.pictures.first() else { return Ok(None) };
+    let Some((game, part)) = msg.game(store).await? else { return Ok(None) };
+    let author = msg.author.id.to_string();
+    if game.is_paused()
+        || part.is_some_and(|part| part.player_id != author || part.picture_url.is_some())
+    {
+        return Ok(None);
+    }
+
+    match pictures.save(url).await {
+        Ok(saved) => Ok(Some(saved)),
+        Err(e) => {
+            tracing::warn!("Failed to store picture {url}: {e}");
+            report::capture(&e, report::Context::message(None, msg));
+            Ok(None)
+        }
+    }
+}
+
+/// Handle a message that is not a command, looking for a new picture to play with, stored
+/// beforehand by [`save_picture`].
+pub async fn on_message(
+    env: &Env,
+    store: &mut dyn GameStore,
+    msg: &Message,
+    saved: Option<&Saved>,
+) -> StringResult {
+    let _timer = metrics::HANDLER_DURATION.with_label_values(&["message"]).start_timer();
+    // Find picture attachment
+    let Some(picture) = msg.pictures.first() else { return Ok(None) };
+    on_participation(env, msg, store, picture, saved).await
+}
+
+#[instrument(skip(env, msg, store, saved))]
+async fn on_participation(
+    env: &Env,
+    msg: &Message,
+    store: &mut dyn GameStore,
+    picture: &str,
+    saved: Option<&Saved>,
+) -> StringResult {
+    let res = participate(env, msg, store, picture, saved).await;
+    // The transaction is rolled back on errors, so nothing refers to the stored copy anymore
+    if let (Err(_), Some(saved)) = (&res, saved) {
+        discard_picture(env, saved).await;
+    }
+    res
+}
+
+async fn participate(
+    env: &Env,
+    msg: &Message,
+    store: &mut dyn GameStore,
+    picture: &str,
+    saved: Option<&Saved>,
+) -> StringResult {
+    // Find game itself
+    let Some((game, part)) = msg.game(store).await? else { return Ok(None) };
+    if game.is_paused() {
+        return Ok(None);
+    }
+
+    if let Some(part) = &part {
+        // Check the participant
+        if part.player_id != msg.author.id.to_string() {
+            // Don't send any error message as this is annoying when people post guess pics etc
+            return Ok(None);
+        }
+        if part.picture_url.is_some() {
+            return Err(Error::PicAlreadyPosted);
+        }
+    }
+
+    let stored = match saved {
+        Some(saved) => Some(store.create_picture(saved.picture.clone()).await?),
+        None => None,
+    };
+    let duplicate = match &stored {
+        Some(stored) => check_duplicate(env, store, &game, stored).await?,
+        None => None,
+    };
+    let picture_id = stored.map(|stored| stored.id);
+    let location = saved.and_then(|saved| saved.location);
+
+    let part = match part {
+        Some(part) => store.set_picture(&part, Some(picture), picture_id, location).await?,
+        None => {
+            // Create the participation itself as nobody has a hand
+            let part = NewParticipation {
+                player_id: &msg.author.id.to_string(),
+                picture_url: Some(picture),
+                game_id: &game.id,
+                picture_id,
+                latitude: location.map(|l| l.latitude),
+                longitude: location.map(|l| l.longitude),
+            };
+            store.create_participation(part).await?
+        }
+    };
+
+    tracing::info!("Saved participation {part:?}");
+
+    let mut reply = env.locale.tr("participation-new", &[]);
+    if let Some(duplicate) = duplicate {
+        let duplicate = duplicate.describe(env.locale);
+        reply.push('\n');
+        reply.push_str(&tr!(env.locale, "participation-duplicate", duplicate = duplicate));
+    }
+    Ok(Some(reply))
+}
+
+/// Drop the stored copy of a refused picture, leaving it behind if that fails.
+async fn discard_picture(env: &Env, saved: &Saved) {
+    let Some(pictures) = &env.pictures else { return };
+    if let Err(e) = pictures.discard(saved).await {
+        tracing::warn!("Failed to delete picture {}: {e}", saved.picture.storage_key);
+    }
+}
+
+/// Look for a previous play of a stored picture in the game, refusing it if the game says so.
+async fn check_duplicate(
+    env: &Env,
+    store: &mut dyn GameStore,
+    game: &Game,
+    picture: &Picture,
+) -> Result<Option<Duplicate>> {
+    let Some(policy) = env.settings(store, game).await?.duplicate_pictures else {
+        return Ok(None);
+    };
+    let played = store.played_pictures(game).await?;
+    match pictures::find_duplicate(&played, picture)? {
+        Some(duplicate) if policy == DuplicatePolicy::Reject => {
+            Err(Error::DuplicatePicture(duplicate))
+        }
+        duplicate => Ok(duplicate),
+    }
+}
+
+/// Handle page switching on paged messages.
+pub async fn on_reaction(env: &Env, store: &mut dyn GameStore, react: &Reaction) -> Result<()> {
+    let _timer = metrics::HANDLER_DURATION.with_label_values(&["reaction"]).start_timer();
+    let Some(bot_id) = env.transport.bot_id() else {
+        tracing::warn!("Got react on message but bot is not cached");
+        return Ok(());
+    };
+    if react.user_id == Some(bot_id) {
+        return Ok(());
+    }
+    let Some(state) = env.pages.get(react.message_id) else { return Ok(()) };
+    let Some(page) = state.target(&react.emoji) else { return Ok(()) };
+    let Some(guild_id) = react.guild_id else { return Ok(()) };
+
+    // The view belongs to a game that is over
+    let Some(game) = store.game(guild_id.0, react.channel_id.0).await? else {
+        return Ok(());
+    };
+    if game.id != state.game_id {
+        return Ok(());
+    }
+
+    let paged = match paging::render(env, store, &game, state.view, page).await {
+        Ok(paged) => paged,
+        Err(Error::InvalidPage) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    env.pages.register(react.message_id, paged.state.clone());
+    env.transport.edit(react.channel_id, react.message_id, Reply::Paged(paged)).await
+}
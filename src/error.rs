@@ -5,7 +5,14 @@ use std::{
     fmt::{Display, Formatter, Result as FmtResult},
 };
 
-use serenity::{http::client::Http, model::id::ChannelId};
+use serenity::model::id::ChannelId;
+
+use crate::{
+    engine::Env,
+    i18n::{self, Locale, tr},
+    pictures::Duplicate,
+    transport::Reply,
+};
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -14,7 +21,12 @@ pub enum Error {
     // Lib errors
     Db(diesel::result::Error),
     Pool(diesel_async::pooled_connection::deadpool::PoolError),
-    Serenity(serenity::Error),
+    // Boxed as it is much larger than the others
+    Serenity(Box<serenity::Error>),
+    /// Downloading, decoding or storing a picture
+    Storage(Box<dyn StdError + Send + Sync>),
+    /// Drawing a picture
+    Render(Box<dyn StdError + Send + Sync>),
 
     // Errors from handlers
     NoParticipant,
@@ -25,6 +37,16 @@ pub enum Error {
     InvalidPage,
     InvalidResetId,
     UnknownArguments,
+    UnknownSetting,
+    InvalidSettingValue,
+    UnknownSeason,
+    NothingToUndo,
+    UndoNotAllowed,
+    NextPicturePosted,
+    MissingHint,
+    DuplicatePicture(Duplicate),
+    UnknownLocale,
+    MissingPermissions,
 }
 
 impl Display for Error {
@@ -38,26 +60,84 @@ impl StdError for Error {
         match self {
             Self::Db(e) => Some(e),
             Self::Pool(e) => Some(e),
-            Self::Serenity(e) => Some(e),
+            Self::Serenity(e) => Some(&**e),
+            Self::Storage(e) => Some(&**e),
+            Self::Render(e) => Some(&**e),
             _ => None,
         }
     }
 }
 
 impl Error {
-    pub fn as_message(&self) -> Option<String> {
-        let ret = match self {
-            Self::Db(_) | Self::Pool(_) | Self::Serenity(_) => Some("Erreur interne"),
-            Self::NoParticipant => Some("⁉️ Mais personne n'a la main ..."),
-            Self::NotYourTurn => Some("❌ Tut tut tut, c'est pas toi qui a la main..."),
-            Self::YouPostedNoPic => Some("🤦 Hrmpf t'as pas mis de photo toi ..."),
-            Self::StfuBot => Some("🤖 Tg le bot !"),
-            Self::PicAlreadyPosted => Some("🦜 T'as déjà mis une photo coco."),
-            Self::InvalidPage => Some("Page invalide"),
-            Self::InvalidResetId => Some("ID de reset invalide"),
-            Self::UnknownArguments => Some("Arguments inconnus"),
+    /// Name of the variant, as a metrics label.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Db(_) => "Db",
+            Self::Pool(_) => "Pool",
+            Self::Serenity(_) => "Serenity",
+            Self::Storage(_) => "Storage",
+            Self::Render(_) => "Render",
+            Self::NoParticipant => "NoParticipant",
+            Self::NotYourTurn => "NotYourTurn",
+            Self::YouPostedNoPic => "YouPostedNoPic",
+            Self::StfuBot => "StfuBot",
+            Self::PicAlreadyPosted => "PicAlreadyPosted",
+            Self::InvalidPage => "InvalidPage",
+            Self::InvalidResetId => "InvalidResetId",
+            Self::UnknownArguments => "UnknownArguments",
+            Self::UnknownSetting => "UnknownSetting",
+            Self::InvalidSettingValue => "InvalidSettingValue",
+            Self::UnknownSeason => "UnknownSeason",
+            Self::NothingToUndo => "NothingToUndo",
+            Self::UndoNotAllowed => "UndoNotAllowed",
+            Self::NextPicturePosted => "NextPicturePosted",
+            Self::MissingHint => "MissingHint",
+            Self::DuplicatePicture(_) => "DuplicatePicture",
+            Self::UnknownLocale => "UnknownLocale",
+            Self::MissingPermissions => "MissingPermissions",
+        }
+    }
+
+    /// Whether this is a bug or an outage rather than a player mistake.
+    pub fn is_internal(&self) -> bool {
+        matches!(
+            self,
+            Self::Db(_) | Self::Pool(_) | Self::Serenity(_) | Self::Storage(_) | Self::Render(_)
+        )
+    }
+
+    pub fn as_message(&self, locale: Locale) -> Option<String> {
+        let id = match self {
+            Self::Db(_)
+            | Self::Pool(_)
+            | Self::Serenity(_)
+            | Self::Storage(_)
+            | Self::Render(_) => "error-internal",
+            Self::NoParticipant => "error-no-participant",
+            Self::NotYourTurn => "error-not-your-turn",
+            Self::YouPostedNoPic => "error-you-posted-no-pic",
+            Self::StfuBot => "error-stfu-bot",
+            Self::PicAlreadyPosted => "error-pic-already-posted",
+            Self::InvalidPage => "error-invalid-page",
+            Self::InvalidResetId => "error-invalid-reset-id",
+            Self::UnknownArguments => "error-unknown-arguments",
+            Self::UnknownSetting => "error-unknown-setting",
+            Self::InvalidSettingValue => "error-invalid-setting-value",
+            Self::UnknownSeason => "error-unknown-season",
+            Self::NothingToUndo => "error-nothing-to-undo",
+            Self::UndoNotAllowed => "error-undo-not-allowed",
+            Self::NextPicturePosted => "error-next-picture-posted",
+            Self::MissingHint => "error-missing-hint",
+            Self::MissingPermissions => "error-missing-permissions",
+            Self::DuplicatePicture(duplicate) => {
+                let duplicate = duplicate.describe(locale);
+                return Some(tr!(locale, "error-duplicate-picture", duplicate = duplicate));
+            }
+            Self::UnknownLocale => {
+                return Some(tr!(locale, "error-unknown-locale", locales = i18n::available()));
+            }
         };
-        ret.map(|s| s.to_owned())
+        Some(locale.tr(id, &[]))
     }
 }
 
@@ -75,22 +155,40 @@ impl From<diesel_async::pooled_connection::deadpool::PoolError> for Error {
 
 impl From<serenity::Error> for Error {
     fn from(e: serenity::Error) -> Error {
-        Error::Serenity(e)
+        Error::Serenity(Box::new(e))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Storage(Box::new(e))
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Error {
+        Error::Storage(Box::new(e))
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(e: image::ImageError) -> Error {
+        Error::Storage(Box::new(e))
     }
 }
 
 #[async_trait::async_trait]
 pub trait ErrorResultExt: Send {
-    async fn handle_err(self, chan: &ChannelId, http: &Http) -> Self;
+    async fn handle_err(self, chan: &ChannelId, env: &Env) -> Self;
 }
 
 #[async_trait::async_trait]
 impl<T: Send> ErrorResultExt for Result<T> {
-    async fn handle_err(self, chan: &ChannelId, http: &Http) -> Self {
-        if let Err(ref e) = self {
-            if let Some(s) = e.as_message() {
-                chan.say(http, s).await?;
-            }
+    async fn handle_err(self, chan: &ChannelId, env: &Env) -> Self {
+        if let Err(ref e) = self
+            && let Some(s) = e.as_message(env.locale)
+        {
+            env.transport.send(*chan, Reply::Text(s)).await?;
         }
         self
     }
@@ -0,0 +1,378 @@
+//! Export of the full history of a game, to back it up or move it to another instance
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io::Write,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{error::Result, models::*, settings, store::GameStore};
+
+/// Version of the export format, bumped on breaking changes.
+pub const VERSION: u32 = 1;
+
+/// A game along with its settings, seasons, and all its participations and wins, resets included.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GameExport {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub game: Game,
+    #[serde(default)]
+    pub settings: Option<GameSettings>,
+    /// Oldest first, their ID being the one of the reset freezing their scores
+    #[serde(default)]
+    pub seasons: Vec<Season>,
+    /// Oldest first
+    pub participations: Vec<Participation>,
+    pub wins: Vec<Win>,
+}
+
+/// One participation along with its win, flattened for spreadsheets.
+///
+/// Where the pictures were taken is left out, as it gives the answers away to anyone the
+/// spreadsheet is shared with.
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    game_id: Uuid,
+    guild_id: &'a str,
+    channel_id: &'a str,
+    participation_id: Uuid,
+    created_at: DateTime<Utc>,
+    player_id: &'a str,
+    picture_url: Option<&'a str>,
+    picture_id: Option<Uuid>,
+    /// One per line
+    hints: String,
+    warned_at: Option<DateTime<Utc>>,
+    is_skip: bool,
+    is_auto_skip: bool,
+    skipped_at: Option<DateTime<Utc>>,
+    is_win: bool,
+    won_at: Option<DateTime<Utc>>,
+    win_id: Option<Uuid>,
+    winner_id: Option<&'a str>,
+    /// In hundredths of a point, like in the database
+    score: Option<i32>,
+    poster_score: Option<i32>,
+    reset: Option<bool>,
+    reset_at: Option<DateTime<Utc>>,
+    reset_id: Option<Uuid>,
+}
+
+impl GameExport {
+    pub async fn new(store: &mut dyn GameStore, game: Game) -> Result<Self> {
+        let (participations, wins): (Vec<_>, Vec<_>) =
+            store.participations(&game).await?.into_iter().unzip();
+        Ok(Self {
+            version: VERSION,
+            exported_at: Utc::now(),
+            settings: store.settings(&game).await?,
+            seasons: store.seasons(&game).await?,
+            game,
+            participations,
+            wins: wins.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Write one row per participation. Not meant to be imported back, use JSON for that.
+    pub fn write_csv(&self, writer: impl Write) -> csv::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        for part in &self.participations {
+            let win = part.win_id.and_then(|id| self.wins.iter().find(|w| w.id == id));
+            writer.serialize(CsvRow {
+                game_id: self.game.id,
+                guild_id: &self.game.guild_id,
+                channel_id: &self.game.channel_id,
+                participation_id: part.id,
+                created_at: part.created_at,
+                player_id: &part.player_id,
+                picture_url: part.picture_url.as_deref(),
+                picture_id: part.picture_id,
+                hints: part.hints.join("\n"),
+                warned_at: part.warned_at,
+                is_skip: part.is_skip,
+                is_auto_skip: part.is_auto_skip,
+                skipped_at: part.skipped_at,
+                is_win: part.is_win,
+                won_at: part.won_at,
+                win_id: part.win_id,
+                winner_id: win.map(|w| w.winner_id.as_str()),
+                score: win.map(|w| w.score),
+                poster_score: win.map(|w| w.poster_score),
+                reset: win.map(|w| w.reset),
+                reset_at: win.and_then(|w| w.reset_at),
+                reset_id: win.and_then(|w| w.reset_id),
+            })?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Same history with fresh IDs, moved to another channel.
+    fn remap(&self, guild_id: u64, channel_id: u64) -> Self {
+        let mut ids = HashMap::new();
+        let mut remap = |id: Uuid| *ids.entry(id).or_insert_with(Uuid::new_v4);
+
+        let game = Game {
+            id: remap(self.game.id),
+            guild_id: guild_id.to_string(),
+            channel_id: channel_id.to_string(),
+            ..self.game.clone()
+        };
+        let settings =
+            self.settings.as_ref().map(|s| GameSettings { game_id: game.id, ..s.clone() });
+        let seasons = self
+            .seasons
+            .iter()
+            .map(|season| Season { id: remap(season.id), game_id: game.id, ..season.clone() })
+            .collect();
+        let wins = self
+            .wins
+            .iter()
+            .map(|win| Win {
+                id: remap(win.id),
+                // Several wins share the ID of the reset that froze them
+                reset_id: win.reset_id.map(&mut remap),
+                ..win.clone()
+            })
+            .collect();
+        let participations = self
+            .participations
+            .iter()
+            .map(|part| Participation {
+                id: remap(part.id),
+                game_id: game.id,
+                win_id: part.win_id.map(&mut remap),
+                ..part.clone()
+            })
+            .collect();
+
+        Self { game, settings, seasons, participations, wins, ..self.clone() }
+    }
+}
+
+/// Something in the way of an import.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Conflict {
+    /// Export made by an incompatible version of the bot
+    UnsupportedVersion(u32),
+    /// The imported game is running, and so is this one in the target channel
+    RunningGame(Uuid),
+    /// This game of the target channel was created at the same time, likely a previous import
+    AlreadyImported(Uuid),
+    /// This stored picture is unknown here, only its URL is kept
+    MissingPicture(Uuid),
+    /// This audit channel belongs to the guild the game was exported from
+    ForeignAuditChannel(String),
+    /// This setting has a value that could not be set with `!config`
+    InvalidSetting(&'static str),
+}
+
+impl Conflict {
+    /// Whether the import cannot go on because of it.
+    pub fn is_blocking(&self) -> bool {
+        !matches!(self, Self::MissingPicture(_) | Self::ForeignAuditChannel(_))
+    }
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::UnsupportedVersion(v) => write!(f, "export version {v}, expected {VERSION}"),
+            Self::RunningGame(id) => write!(f, "game {id} is already running in the channel"),
+            Self::AlreadyImported(id) => write!(f, "game {id} looks like a previous import"),
+            Self::MissingPicture(id) => write!(f, "picture {id} is not stored here, dropped"),
+            Self::ForeignAuditChannel(id) => {
+                write!(f, "audit channel {id} is in another guild, dropped")
+            }
+            Self::InvalidSetting(key) => write!(f, "setting {key} has an invalid value"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ImportReport {
+    /// ID of the game, once imported
+    pub game_id: Uuid,
+    pub participations: usize,
+    pub wins: usize,
+    pub seasons: usize,
+    pub conflicts: Vec<Conflict>,
+    /// Whether anything was written, `false` on dry runs or blocking conflicts
+    pub imported: bool,
+}
+
+/// Recreate an exported game in the given channel, unless `dry_run` or blocked by a conflict.
+pub async fn import(
+    store: &mut dyn GameStore,
+    export: &GameExport,
+    guild_id: u64,
+    channel_id: u64,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let moved = export.game.guild_id != guild_id.to_string();
+    let mut export = export.remap(guild_id, channel_id);
+    let mut conflicts = vec![];
+
+    if export.version != VERSION {
+        conflicts.push(Conflict::UnsupportedVersion(export.version));
+    }
+    let (guild_id, channel_id) = (guild_id.to_string(), channel_id.to_string());
+    for game in store.all_games().await? {
+        if game.guild_id != guild_id || game.channel_id != channel_id {
+            continue;
+        }
+        if game.created_at == export.game.created_at {
+            conflicts.push(Conflict::AlreadyImported(game.id));
+        } else if game.ended_at.is_none() && export.game.ended_at.is_none() {
+            conflicts.push(Conflict::RunningGame(game.id));
+        }
+    }
+    if let Some(settings) = &export.settings {
+        conflicts.extend(settings::invalid(settings).into_iter().map(Conflict::InvalidSetting));
+    }
+    // Channels of another guild are out of reach
+    if let Some(settings) = &mut export.settings
+        && moved
+        && let Some(channel) = settings.audit_channel.take()
+    {
+        conflicts.push(Conflict::ForeignAuditChannel(channel));
+    }
+    for part in &mut export.participations {
+        if let Some(id) = part.picture_id
+            && store.picture(id).await?.is_none()
+        {
+            conflicts.push(Conflict::MissingPicture(id));
+            part.picture_id = None;
+        }
+    }
+
+    let imported = !dry_run && !conflicts.iter().any(Conflict::is_blocking);
+    if imported {
+        store
+            .restore_game(
+                &export.game,
+                export.settings.as_ref(),
+                &export.seasons,
+                &export.participations,
+                &export.wins,
+            )
+            .await?;
+    }
+    Ok(ImportReport {
+        game_id: export.game.id,
+        participations: export.participations.len(),
+        wins: export.wins.len(),
+        seasons: export.seasons.len(),
+        conflicts,
+        imported,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    /// Game of a single participation, whose picture was taken in Paris.
+    async fn played() -> (MemoryStore, GameExport) {
+        let mut store = MemoryStore::new();
+        let game = NewGame { guild_id: "1", channel_id: "2", creator_id: "3" };
+        let game = store.create_game(game).await.unwrap();
+        let part = NewParticipation {
+            player_id: "3",
+            picture_url: Some("https://example.com/pic.jpg"),
+            game_id: &game.id,
+            picture_id: None,
+            latitude: Some(48.8584),
+            longitude: Some(2.2945),
+        };
+        store.create_participation(part).await.unwrap();
+        let export = GameExport::new(&mut store, game).await.unwrap();
+        (store, export)
+    }
+
+    #[tokio::test]
+    async fn csv_leaves_locations_out() {
+        let (_, export) = played().await;
+        assert_eq!(export.participations[0].latitude, Some(48.8584));
+
+        let mut data = vec![];
+        export.write_csv(&mut data).unwrap();
+        let csv = String::from_utf8(data).unwrap();
+        assert!(!csv.contains("latitude") && !csv.contains("48.8584"), "{csv}");
+        assert_eq!(csv.lines().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn import_keeps_seasons_and_settings() {
+        let (mut store, export) = played().await;
+        let game = export.game;
+        let part = store.participations(&game).await.unwrap().remove(0).0;
+        let win = NewWin { player_id: "3", winner_id: "4", score: 100, poster_score: 0 };
+        store.record_win(&part, win).await.unwrap();
+        let season = NewSeason { game_id: &game.id, name: "2026-09" };
+        let season = store.create_season(season).await.unwrap();
+        store.reset(&game, season.id).await.unwrap();
+        store.end_season(&season).await.unwrap();
+        let settings = GameSettings {
+            game_id: game.id,
+            skip_penalty: Some(0),
+            audit_channel: Some("5".to_owned()),
+            ..Default::default()
+        };
+        store.save_settings(&settings).await.unwrap();
+        let export = GameExport::new(&mut store, game).await.unwrap();
+
+        // Through JSON, to another guild
+        let export = serde_json::from_str(&serde_json::to_string(&export).unwrap()).unwrap();
+        let report = import(&mut store, &export, 10, 20, false).await.unwrap();
+        assert!(report.imported);
+        assert_eq!(report.seasons, 1);
+        assert_eq!(report.conflicts, [Conflict::ForeignAuditChannel("5".to_owned())]);
+
+        let imported = store.games.iter().find(|g| g.id == report.game_id).unwrap().clone();
+        let seasons = store.seasons(&imported).await.unwrap();
+        assert_eq!(seasons.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["2026-09"]);
+        assert_ne!(seasons[0].id, season.id);
+        let wins = store.wins(&imported, Some(seasons[0].id)).await.unwrap();
+        assert_eq!(wins.len(), 1, "Season scores follow the new reset ID");
+        let settings = store.settings(&imported).await.unwrap().unwrap();
+        assert_eq!((settings.skip_penalty, settings.audit_channel), (Some(0), None));
+    }
+
+    #[tokio::test]
+    async fn import_checks_settings() {
+        let (mut store, mut export) = played().await;
+        export.settings = Some(GameSettings {
+            game_id: export.game.id,
+            page_size: Some(0),
+            hint_penalty: Some(-50),
+            ..Default::default()
+        });
+
+        let report = import(&mut store, &export, 1, 20, false).await.unwrap();
+        assert!(!report.imported);
+        assert_eq!(
+            report.conflicts,
+            [Conflict::InvalidSetting("page-size"), Conflict::InvalidSetting("hint-penalty")]
+        );
+        assert!(report.conflicts.iter().all(Conflict::is_blocking));
+        assert_eq!(store.games.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn exports_without_seasons_still_load() {
+        let (_, export) = played().await;
+        let mut json = serde_json::to_value(&export).unwrap();
+        let object = json.as_object_mut().unwrap();
+        object.remove("settings");
+        object.remove("seasons");
+        let export: GameExport = serde_json::from_value(json).unwrap();
+        assert!(export.settings.is_none() && export.seasons.is_empty());
+    }
+}
@@ -1,31 +1,44 @@
 //! Extensions to some builtin or external types
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
-use diesel::result::Error;
 use diesel_async::{
     AsyncPgConnection,
     pooled_connection::deadpool::{Object, Pool, PoolError},
 };
-use serenity::{client::Context, model::prelude::Message};
+use serenity::{client::Context, model::id::GuildId};
+use tracing::warn;
 
-use crate::{PgPool, cache::Cache, models::*};
+use crate::{
+    PgPool,
+    cache::Cache,
+    config::BotConfig,
+    engine::Env,
+    error::Result,
+    models::*,
+    paging::Paginator,
+    pictures::Pictures,
+    store::GameStore,
+    transport::{Message, discord::DiscordTransport},
+};
 
 #[async_trait]
 pub trait MessageExt {
     async fn game(
         &self,
-        conn: &mut AsyncPgConnection,
-    ) -> Result<Option<(Game, Option<Participation>)>, Error>;
+        store: &mut dyn GameStore,
+    ) -> Result<Option<(Game, Option<Participation>)>>;
 }
 
 #[async_trait]
 impl MessageExt for Message {
     async fn game(
         &self,
-        conn: &mut AsyncPgConnection,
-    ) -> Result<Option<(Game, Option<Participation>)>, Error> {
+        store: &mut dyn GameStore,
+    ) -> Result<Option<(Game, Option<Participation>)>> {
         Ok(match self.guild_id {
-            Some(id) => Game::get_with_part(conn, *id.as_u64(), *self.channel_id.as_u64()).await?,
+            Some(id) => store.game_with_part(*id.as_u64(), *self.channel_id.as_u64()).await?,
             None => None,
         })
     }
@@ -36,6 +49,8 @@ pub trait ContextExt {
     async fn cache(&self) -> Cache;
     async fn pool(&self) -> Pool<AsyncPgConnection>;
     async fn conn(&self) -> Result<Object<AsyncPgConnection>, PoolError>;
+    /// Environment of the commands, speaking the language of `guild`.
+    async fn env(&self, guild: Option<GuildId>) -> Env;
 }
 
 #[serenity::async_trait]
@@ -49,4 +64,24 @@ impl ContextExt for Context {
     async fn conn(&self) -> Result<Object<AsyncPgConnection>, PoolError> {
         self.pool().await.get().await
     }
+    async fn env(&self, guild: Option<GuildId>) -> Env {
+        let data = self.data.read().await;
+        let config = data.get::<BotConfig>().unwrap().clone();
+        let pictures = data.get::<Pictures>().cloned();
+        let pages = data.get::<Paginator>().unwrap().clone();
+        drop(data);
+        let transport = Arc::new(DiscordTransport::from_context(self).await);
+        let locale = config.locale;
+        let env = Env { transport, config, pictures, pages, locale, audit: Arc::default() };
+
+        // Replying in the default language beats not replying at all
+        let localized = match self.conn().await {
+            Ok(mut conn) => env.for_guild(&mut *conn, guild).await,
+            Err(e) => Err(e.into()),
+        };
+        localized.unwrap_or_else(|e| {
+            warn!("Failed to get the locale of guild {guild:?}: {e}");
+            env
+        })
+    }
 }
@@ -0,0 +1,148 @@
+//! Translations of the messages of the bot, kept in the Fluent catalogs of `locales/`
+//!
+//! Each guild picks its locale, messages missing from its catalog falling back to French, the
+//! language the bot was first written in.
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+    sync::LazyLock,
+};
+
+use chrono::{DateTime, Utc};
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue, concurrent::FluentBundle};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use unic_langid::LanguageIdentifier;
+
+use crate::error::{Error, Result};
+
+/// Locale of the messages missing from the other catalogs.
+pub const FALLBACK: Locale = Locale::Fr;
+
+/// Bundled catalogs, by locale.
+const CATALOGS: &[(Locale, &str)] = &[
+    (Locale::Fr, include_str!("../locales/fr.ftl")),
+    (Locale::En, include_str!("../locales/en.ftl")),
+];
+
+static BUNDLES: LazyLock<HashMap<Locale, FluentBundle<FluentResource>>> = LazyLock::new(|| {
+    CATALOGS
+        .iter()
+        .map(|(locale, source)| {
+            let resource = FluentResource::try_new(source.to_string())
+                .unwrap_or_else(|(_, e)| panic!("Invalid {locale} catalog: {e:?}"));
+            let id = locale.to_string().parse::<LanguageIdentifier>().unwrap();
+            let mut bundle = FluentBundle::new_concurrent(vec![id]);
+            // Unicode isolation marks would break the mentions
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .unwrap_or_else(|e| panic!("Duplicate messages in {locale} catalog: {e:?}"));
+            (*locale, bundle)
+        })
+        .collect()
+});
+
+/// Translate a message, with named arguments: `tr!(env.locale, "pic-hint", number = 2)`.
+macro_rules! tr {
+    ($locale:expr, $id:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $locale.tr($id, &[$((stringify!($name), ::fluent_bundle::FluentValue::from($value))),*])
+    };
+}
+pub(crate) use tr;
+
+/// Language of the messages of the bot.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Locale {
+    #[default]
+    Fr,
+    En,
+}
+
+impl Locale {
+    pub const ALL: &[Self] = &[Self::Fr, Self::En];
+
+    /// Message of the catalog, or of the fallback one if missing. Use [`tr!`] to pass arguments.
+    pub fn tr(self, id: &str, args: &[(&str, FluentValue)]) -> String {
+        self.message(id, args).or_else(|| FALLBACK.message(id, args)).unwrap_or_else(|| {
+            warn!("Missing message {id}");
+            id.to_owned()
+        })
+    }
+
+    /// Message of this very catalog, if there.
+    pub fn message(self, id: &str, args: &[(&str, FluentValue)]) -> Option<String> {
+        let bundle = &BUNDLES[&self];
+        let pattern = bundle.get_message(id)?.value()?;
+        let args = args.iter().cloned().collect::<FluentArgs>();
+
+        let mut errors = vec![];
+        let message = bundle.format_pattern(pattern, Some(&args), &mut errors);
+        if !errors.is_empty() {
+            warn!("Failed to format {self} message {id}: {errors:?}");
+        }
+        Some(message.into_owned())
+    }
+
+    /// Locales of the Discord clients to show the slash commands in this locale to.
+    pub fn discord_locales(self) -> &'static [&'static str] {
+        match self {
+            Self::Fr => &["fr"],
+            Self::En => &["en-US", "en-GB"],
+        }
+    }
+
+    pub fn format_date(self, date: DateTime<Utc>) -> String {
+        date.format(match self {
+            Self::Fr => "%d/%m/%Y",
+            Self::En => "%Y-%m-%d",
+        })
+        .to_string()
+    }
+
+    pub fn format_datetime(self, date: DateTime<Utc>) -> String {
+        date.format(match self {
+            Self::Fr => "%d/%m/%Y %H:%M",
+            Self::En => "%Y-%m-%d %H:%M",
+        })
+        .to_string()
+    }
+
+    /// Separator of the decimals of the scores.
+    pub fn decimal_separator(self) -> char {
+        match self {
+            Self::Fr => ',',
+            Self::En => '.',
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fr" => Ok(Self::Fr),
+            "en" => Ok(Self::En),
+            _ => Err(Error::UnknownLocale),
+        }
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Fr => write!(f, "fr"),
+            Self::En => write!(f, "en"),
+        }
+    }
+}
+
+/// Available locales, for messages.
+pub fn available() -> String {
+    Locale::ALL.iter().map(|locale| format!("`{locale}`")).join(", ")
+}
@@ -0,0 +1,59 @@
+//! Bot-ticelli, a small Discord bot for managing picture-guessing games.
+
+#[macro_use]
+extern crate diesel;
+
+use std::sync::Arc;
+
+use diesel_async::{pg::AsyncPgConnection, pooled_connection::deadpool::Pool};
+use serenity::{model::id::UserId, prelude::*};
+
+pub mod audit;
+pub mod bot;
+pub mod cache;
+pub mod cli;
+pub mod cmd;
+pub mod config;
+pub mod cron;
+pub mod engine;
+pub mod error;
+pub mod export;
+pub mod extensions;
+pub mod i18n;
+pub mod metrics;
+pub mod migrate;
+pub mod models;
+pub mod paging;
+pub mod pictures;
+pub mod render;
+pub mod report;
+pub mod schema;
+pub mod score;
+pub mod season;
+pub mod settings;
+pub mod slash;
+pub mod stats;
+pub mod store;
+pub mod transport;
+
+pub struct PgPool;
+impl TypeMapKey for PgPool {
+    type Value = Pool<AsyncPgConnection>;
+}
+
+impl TypeMapKey for config::BotConfig {
+    type Value = Arc<config::BotConfig>;
+}
+
+impl TypeMapKey for pictures::Pictures {
+    type Value = Arc<pictures::Pictures>;
+}
+
+impl TypeMapKey for paging::Paginator {
+    type Value = Arc<paging::Paginator>;
+}
+
+pub struct BotUserId;
+impl TypeMapKey for BotUserId {
+    type Value = UserId;
+}
@@ -1,6 +1,19 @@
-#[macro_use]
-extern crate diesel;
+use std::sync::Arc;
 
+use bot_ticelli::{
+    PgPool,
+    bot::{self, Bot},
+    cache::Cache,
+    cli,
+    config::{self, BotConfig},
+    cron,
+    engine::Env,
+    metrics, migrate,
+    paging::Paginator,
+    pictures::Pictures,
+    report,
+    transport::discord::DiscordTransport,
+};
 use clap::Parser;
 use diesel_async::{
     pg::AsyncPgConnection,
@@ -9,38 +22,10 @@ use diesel_async::{
 use opentelemetry::{KeyValue, trace::TracerProvider};
 use opentelemetry_otlp::{Protocol, WithExportConfig};
 use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
-use serenity::{framework::StandardFramework, model::id::UserId, prelude::*};
+use serenity::{framework::StandardFramework, prelude::*};
 use tokio::spawn;
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
-mod bot;
-mod cache;
-mod cmd;
-mod config;
-mod cron;
-mod error;
-mod extensions;
-mod models;
-mod schema;
-
-use bot::Bot;
-use cache::Cache;
-
-struct PgPool;
-impl TypeMapKey for PgPool {
-    type Value = Pool<AsyncPgConnection>;
-}
-
-struct WinSentences;
-impl TypeMapKey for WinSentences {
-    type Value = Vec<String>;
-}
-
-struct BotUserId;
-impl TypeMapKey for BotUserId {
-    type Value = UserId;
-}
-
 /// A small Discord bot for managing picture-guessing games.
 #[derive(Parser, Debug)]
 #[clap(author, version)]
@@ -48,44 +33,81 @@ struct Args {
     /// Config file path.
     #[clap(short, long, default_value = "config.toml")]
     config: String,
+    /// Run an operator command instead of the bot.
+    #[clap(subcommand)]
+    command: Option<cli::Command>,
 }
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
     let args = Args::parse();
+    if let Some(command) = args.command {
+        if let Err(e) = cli::run(command, &args.config).await {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let config = config::load_config(&args.config)
         .map_err(|e| format!("Failed to load {}: {e}", args.config))
         .unwrap();
 
     // Install tracing framework with OTLP sink
-    if let Some(ref tracing) = config.tracing_config {
-        if let Some(ref otel) = tracing.otel {
-            // You can thank the OTEL guys for such a complicated setup.
-            let provider = SdkTracerProvider::builder()
-                .with_resource(
-                    Resource::builder()
-                        .with_service_name("bot-ticelli")
-                        .with_attribute(KeyValue::new("version", env!("CARGO_PKG_VERSION")))
-                        .build(),
-                )
-                .with_batch_exporter(
-                    opentelemetry_otlp::SpanExporter::builder()
-                        .with_http()
-                        .with_protocol(Protocol::HttpBinary)
-                        .with_endpoint(otel)
-                        .build()
-                        .expect("Failed to build exporter"),
-                )
-                .build();
-            tracing_subscriber::registry()
-                .with(tracing_subscriber::fmt::layer())
-                .with(
-                    EnvFilter::try_from_default_env()
-                        .unwrap_or_else(|_| EnvFilter::new("bot_ticelli=debug,warn")),
-                )
-                .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("bot-ticelli")))
-                .init();
-            tracing::info!("Installed tracing");
+    if let Some(ref tracing) = config.tracing_config
+        && let Some(ref otel) = tracing.otel
+    {
+        // You can thank the OTEL guys for such a complicated setup.
+        let provider = SdkTracerProvider::builder()
+            .with_resource(
+                Resource::builder()
+                    .with_service_name("bot-ticelli")
+                    .with_attribute(KeyValue::new("version", env!("CARGO_PKG_VERSION")))
+                    .build(),
+            )
+            .with_batch_exporter(
+                opentelemetry_otlp::SpanExporter::builder()
+                    .with_http()
+                    .with_protocol(Protocol::HttpBinary)
+                    .with_endpoint(otel)
+                    .build()
+                    .expect("Failed to build exporter"),
+            )
+            .build();
+        tracing_subscriber::registry()
+            .with(tracing_subscriber::fmt::layer())
+            .with(
+                EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| EnvFilter::new("bot_ticelli=debug,warn")),
+            )
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("bot-ticelli")))
+            .init();
+        tracing::info!("Installed tracing");
+    }
+
+    if let Some(reporting) = config.tracing_config.as_ref().and_then(|t| t.error_reporting.as_ref())
+    {
+        report::init(reporting).expect("Failed to install error reporting");
+    }
+
+    // Bring the schema up to date, or at least make sure we know it
+    let database_url = &config.db_config.database_url;
+    if config.db_config.run_migrations {
+        tracing::info!("Running migrations...");
+        let applied = migrate::run_pending(database_url)
+            .await
+            .map_err(|e| format!("Failed to run migrations: {e}"))
+            .unwrap();
+        for version in &applied {
+            tracing::info!("Applied migration {version}");
+        }
+    } else {
+        let pending = migrate::pending(database_url)
+            .await
+            .map_err(|e| format!("Failed to check migrations: {e}"))
+            .unwrap();
+        if !pending.is_empty() {
+            tracing::warn!("Migrations not applied: {}", pending.join(", "));
         }
     }
 
@@ -104,7 +126,7 @@ async fn main() {
         .normal_message(bot::on_message)
         .before(bot::filter_command);
 
-    if let Some(rl) = config.bot_config.ratelimit {
+    if let Some(ref rl) = config.bot_config.ratelimit {
         for bucket in ["show_limiter", "pic_limiter"] {
             framework = framework
                 .bucket(bucket, |b| b.delay(rl.delay).time_span(rl.time_span).limit(rl.limit))
@@ -112,6 +134,10 @@ async fn main() {
         }
     }
 
+    let bot_config = Arc::new(config.bot_config);
+    let pictures = config.storage.as_ref().map(|s| Arc::new(Pictures::from_config(s)));
+    let pages = Arc::new(Paginator::default());
+    let cache = Cache::default();
     let intents = GatewayIntents::GUILDS
         | GatewayIntents::GUILD_MEMBERS
         | GatewayIntents::GUILD_MESSAGES
@@ -121,15 +147,37 @@ async fn main() {
         .event_handler(Bot)
         .framework(framework)
         .type_map_insert::<PgPool>(pool.clone())
-        .type_map_insert::<WinSentences>(config.bot_config.win_sentences)
-        .type_map_insert::<Cache>(Cache::default())
-        .await
-        .expect("Failed to create discord client");
+        .type_map_insert::<BotConfig>(bot_config.clone())
+        .type_map_insert::<Paginator>(pages.clone())
+        .type_map_insert::<Cache>(cache.clone());
+    if let Some(ref pictures) = pictures {
+        client = client.type_map_insert::<Pictures>(pictures.clone());
+    }
+    let mut client = client.await.expect("Failed to create discord client");
 
-    if let Some(autoskip) = config.bot_config.auto_skip {
-        spawn(cron::task_auto_skip(client.cache_and_http.http.clone(), pool, autoskip));
+    if let Some(addr) = config.tracing_config.as_ref().and_then(|t| t.prometheus) {
+        spawn(metrics::serve(addr, pool.clone()));
     }
 
+    // Always running, as auto-skip and seasons can be enabled per game
+    let transport = DiscordTransport::new(
+        client.cache_and_http.http.clone(),
+        client.cache_and_http.cache.clone(),
+        cache,
+        None,
+    );
+    let locale = bot_config.locale;
+    let env = Env {
+        transport: Arc::new(transport),
+        config: bot_config,
+        pictures,
+        pages,
+        locale,
+        audit: Arc::default(),
+    };
+    spawn(cron::task_auto_skip(env.clone(), pool.clone()));
+    spawn(cron::task_season_rollover(env, pool));
+
     tracing::info!("Runing app...");
     client.start().await.expect("Client error")
 }
@@ -0,0 +1,138 @@
+//! Prometheus metrics, optionally served over HTTP
+
+use std::{convert::Infallible, net::SocketAddr, sync::LazyLock};
+
+use diesel_async::{AsyncPgConnection, pooled_connection::deadpool::Pool};
+use hyper::{
+    Body, Request, Response, Server, StatusCode,
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+};
+use prometheus::{
+    Encoder, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec,
+};
+use tracing::{info, warn};
+
+use crate::{error::Result, store::GameStore};
+
+pub static COMMANDS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("bot_ticelli_commands_total", "Command invocations", &["command"])
+        .unwrap()
+});
+
+pub static COMMAND_ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "bot_ticelli_command_errors_total",
+        "Command errors, by error kind",
+        &["command", "error"]
+    )
+    .unwrap()
+});
+
+pub static HANDLER_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "bot_ticelli_handler_duration_seconds",
+        "Time spent handling commands, messages and reactions",
+        &["handler"]
+    )
+    .unwrap()
+});
+
+pub static ACTIVE_GAMES: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("bot_ticelli_active_games", "Games not ended yet").unwrap()
+});
+
+pub static WAITING_HANDS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("bot_ticelli_waiting_hands", "Hands waiting for a picture").unwrap()
+});
+
+pub static AUTOSKIP_WARNINGS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!("bot_ticelli_autoskip_warnings_total", "Auto-skip warnings sent").unwrap()
+});
+
+pub static AUTOSKIPS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!("bot_ticelli_autoskips_total", "Hands auto-skipped").unwrap()
+});
+
+pub static CACHE_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "bot_ticelli_cache_requests_total",
+        "Lookups in the member and user cache",
+        &["kind", "result"]
+    )
+    .unwrap()
+});
+
+pub static DB_POOL: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "bot_ticelli_db_pool_connections",
+        "Connections of the database pool",
+        &["state"]
+    )
+    .unwrap()
+});
+
+/// Serve the metrics on `/metrics` forever.
+pub async fn serve(addr: SocketAddr, pool: Pool<AsyncPgConnection>) {
+    // Register everything upfront, so that metrics show up before their first use
+    LazyLock::force(&COMMANDS);
+    LazyLock::force(&COMMAND_ERRORS);
+    LazyLock::force(&HANDLER_DURATION);
+    LazyLock::force(&ACTIVE_GAMES);
+    LazyLock::force(&WAITING_HANDS);
+    LazyLock::force(&AUTOSKIP_WARNINGS);
+    LazyLock::force(&AUTOSKIPS);
+    LazyLock::force(&CACHE_REQUESTS);
+    LazyLock::force(&DB_POOL);
+
+    let make_service = make_service_fn(move |_| {
+        let pool = pool.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle(req, pool.clone()))) }
+    });
+
+    info!("Serving metrics on {addr}");
+    if let Err(e) = Server::bind(&addr).serve(make_service).await {
+        tracing::error!("Metrics server failed: {e}");
+    }
+}
+
+async fn handle(
+    req: Request<Body>,
+    pool: Pool<AsyncPgConnection>,
+) -> Result<Response<Body>, Infallible> {
+    if req.uri().path() != "/metrics" {
+        let mut res = Response::new(Body::from("Not found"));
+        *res.status_mut() = StatusCode::NOT_FOUND;
+        return Ok(res);
+    }
+
+    // Gauges that are cheaper to compute on scrape than to keep up to date
+    if let Err(e) = refresh(&pool).await {
+        warn!("Failed to refresh metrics: {e}");
+    }
+
+    let encoder = TextEncoder::new();
+    let mut body = vec![];
+    if let Err(e) = encoder.encode(&prometheus::gather(), &mut body) {
+        warn!("Failed to encode metrics: {e}");
+    }
+    let mut res = Response::new(Body::from(body));
+    res.headers_mut().insert(CONTENT_TYPE, encoder.format_type().parse().unwrap());
+    Ok(res)
+}
+
+async fn refresh(pool: &Pool<AsyncPgConnection>) -> Result<()> {
+    let status = pool.status();
+    DB_POOL.with_label_values(&["size"]).set(status.size as i64);
+    DB_POOL.with_label_values(&["available"]).set(status.available as i64);
+    DB_POOL.with_label_values(&["max"]).set(status.max_size as i64);
+    DB_POOL.with_label_values(&["waiting"]).set(status.waiting as i64);
+
+    let mut conn = pool.get().await?;
+    ACTIVE_GAMES.set(conn.games().await?.len() as i64);
+    let waiting = conn.hands().await?.iter().filter(|(part, _)| part.picture_url.is_none()).count();
+    WAITING_HANDS.set(waiting as i64);
+    Ok(())
+}
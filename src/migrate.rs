@@ -0,0 +1,63 @@
+//! Database migrations, embedded in the binary so that it can upgrade the schema by itself
+
+use std::error::Error as StdError;
+
+use diesel::{Connection, migration::MigrationSource, pg::Pg};
+use diesel_async::{AsyncPgConnection, async_connection_wrapper::AsyncConnectionWrapper};
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+
+pub type MigrationResult<T> = Result<T, Box<dyn StdError + Send + Sync>>;
+
+type Conn = AsyncConnectionWrapper<AsyncPgConnection>;
+
+/// Content of the `migrations/` directory at build time.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// Run the pending migrations, returning the versions applied, oldest first.
+pub async fn run_pending(database_url: &str) -> MigrationResult<Vec<String>> {
+    with_conn(database_url, |conn| {
+        let versions = conn.run_pending_migrations(MIGRATIONS)?;
+        Ok(versions.iter().map(ToString::to_string).collect())
+    })
+    .await
+}
+
+/// Versions of the migrations not applied yet, oldest first.
+pub async fn pending(database_url: &str) -> MigrationResult<Vec<String>> {
+    with_conn(database_url, |conn| {
+        let migrations = conn.pending_migrations(MIGRATIONS)?;
+        Ok(migrations.iter().map(|m| m.name().version().to_string()).collect())
+    })
+    .await
+}
+
+/// Run `f` on a fresh connection, once made sure the schema is not ahead of this binary.
+async fn with_conn<T: Send + 'static>(
+    database_url: &str,
+    f: impl FnOnce(&mut Conn) -> MigrationResult<T> + Send + 'static,
+) -> MigrationResult<T> {
+    let database_url = database_url.to_owned();
+    // The migration harness is sync, and blocks on the async connection
+    tokio::task::spawn_blocking(move || {
+        let mut conn = Conn::establish(&database_url)?;
+        let known = MigrationSource::<Pg>::migrations(&MIGRATIONS)?
+            .iter()
+            .map(|m| m.name().version().to_string())
+            .collect::<Vec<_>>();
+        let unknown = conn
+            .applied_migrations()?
+            .iter()
+            .map(ToString::to_string)
+            .filter(|version| !known.contains(version))
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            return Err(format!(
+                "Database schema is ahead of this binary, unknown migrations: {}",
+                unknown.join(", ")
+            )
+            .into());
+        }
+        f(&mut conn)
+    })
+    .await?
+}
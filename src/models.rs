@@ -7,13 +7,23 @@ use diesel::{
     result::Error as DError,
 };
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
-use serenity::model::id::{ChannelId, UserId};
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, GuildId, UserId};
 use tracing::info;
 use uuid::Uuid;
 
-pub use crate::schema::{game, participation, win};
+pub use crate::schema::{
+    audit_event, game, game_settings, guild_settings, participation, picture, season, win,
+};
+use crate::{error::Error, pictures::Location};
 
-#[derive(Queryable, Identifiable, Debug, Clone)]
+/// Discord user of a stored ID, failing rather than panicking on one that is not a Discord ID.
+pub fn parse_user_id(id: &str) -> crate::error::Result<UserId> {
+    let id = id.parse().map_err(|e| Error::Db(DError::DeserializationError(Box::new(e))))?;
+    Ok(UserId(id))
+}
+
+#[derive(Queryable, Identifiable, Insertable, Serialize, Deserialize, Debug, Clone)]
 #[diesel(table_name = win)]
 pub struct Win {
     pub id: Uuid,
@@ -23,7 +33,10 @@ pub struct Win {
     pub reset: bool,
     pub reset_at: Option<DateTime<Utc>>,
     pub reset_id: Option<Uuid>,
+    /// Points won, in hundredths of a point
     pub score: i32,
+    /// Points of the poster of the picture, in hundredths of a point
+    pub poster_score: i32,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -32,9 +45,10 @@ pub struct NewWin<'a> {
     pub player_id: &'a str,
     pub winner_id: &'a str,
     pub score: i32,
+    pub poster_score: i32,
 }
 
-#[derive(Queryable, Identifiable, Debug, Clone)]
+#[derive(Queryable, Identifiable, Insertable, Serialize, Deserialize, Debug, Clone)]
 #[diesel(table_name = participation)]
 pub struct Participation {
     pub id: Uuid,
@@ -49,9 +63,30 @@ pub struct Participation {
     pub game_id: Uuid,
     pub warned_at: Option<DateTime<Utc>>,
     pub updated_at: DateTime<Utc>,
+    pub is_auto_skip: bool,
+    /// Hints given by the player about their picture
+    pub hints: Vec<String>,
+    /// Stored copy of the picture, if storage is enabled and it succeeded
+    pub picture_id: Option<Uuid>,
+    /// Where the picture was taken according to its EXIF tags, not to be shown before it is found
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Last time the game was resumed while this was the current hand
+    #[serde(default)]
+    pub resumed_at: Option<DateTime<Utc>>,
 }
 
 impl Participation {
+    pub fn location(&self) -> Option<Location> {
+        Some(Location { latitude: self.latitude?, longitude: self.longitude? })
+    }
+
+    /// When the auto-skip delay started: the latest of the picture being posted and the game
+    /// being resumed.
+    pub fn timer_start(&self) -> DateTime<Utc> {
+        self.resumed_at.map_or(self.updated_at, |at| at.max(self.updated_at))
+    }
+
     pub async fn get_current(
         conn: &mut AsyncPgConnection,
     ) -> Result<Option<Participation>, DError> {
@@ -69,9 +104,20 @@ impl Participation {
         }
     }
 
-    pub async fn skip(&self, conn: &mut AsyncPgConnection, negative: bool) -> Result<Self, DError> {
-        let win_id = if negative {
-            let win = NewWin { player_id: &self.player_id, winner_id: &self.player_id, score: -1 };
+    /// Skip the participation, with the player losing `penalty` hundredths of a point.
+    pub async fn skip(
+        &self,
+        conn: &mut AsyncPgConnection,
+        penalty: i32,
+        auto: bool,
+    ) -> Result<Self, DError> {
+        let win_id = if penalty != 0 {
+            let win = NewWin {
+                player_id: &self.player_id,
+                winner_id: &self.player_id,
+                score: -penalty,
+                poster_score: 0,
+            };
             let win = diesel::insert_into(win::table).values(win).get_result::<Win>(conn).await?;
             info!("Saved (negative) win {win:?}");
             Some(win.id)
@@ -83,6 +129,7 @@ impl Participation {
                 participation::is_skip.eq(true),
                 participation::skipped_at.eq(now),
                 participation::win_id.eq(win_id),
+                participation::is_auto_skip.eq(auto),
             ))
             .get_result(conn)
             .await
@@ -99,9 +146,12 @@ pub struct NewParticipation<'a> {
     pub player_id: &'a str,
     pub picture_url: Option<&'a str>,
     pub game_id: &'a Uuid,
+    pub picture_id: Option<Uuid>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
 }
 
-#[derive(Queryable, Identifiable, Debug, Clone)]
+#[derive(Queryable, Identifiable, Insertable, Serialize, Deserialize, Debug, Clone)]
 #[diesel(table_name = game)]
 pub struct Game {
     pub id: Uuid,
@@ -109,6 +159,8 @@ pub struct Game {
     pub guild_id: String,
     pub channel_id: String,
     pub creator_id: String,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub paused_at: Option<DateTime<Utc>>,
 }
 
 impl Game {
@@ -120,6 +172,7 @@ impl Game {
         game::table
             .filter(game::guild_id.eq(guild_id.to_string()))
             .filter(game::channel_id.eq(chan_id.to_string()))
+            .filter(game::ended_at.is_null())
             .first(conn)
             .await
             .optional()
@@ -133,6 +186,7 @@ impl Game {
         game::table
             .filter(game::guild_id.eq(guild_id.to_string()))
             .filter(game::channel_id.eq(chan_id.to_string()))
+            .filter(game::ended_at.is_null())
             .left_join(
                 participation::table.on(game::id
                     .eq(participation::game_id)
@@ -144,9 +198,17 @@ impl Game {
             .optional()
     }
 
+    pub fn guild(&self) -> GuildId {
+        GuildId(self.guild_id.parse().unwrap())
+    }
+
     pub fn channel(&self) -> ChannelId {
         ChannelId(self.channel_id.parse().unwrap())
     }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -156,3 +218,112 @@ pub struct NewGame<'a> {
     pub channel_id: &'a str,
     pub creator_id: &'a str,
 }
+
+/// Settings of a game, overriding the ones of the config file when set.
+#[derive(
+    Queryable, Insertable, AsChangeset, Identifiable, Serialize, Deserialize, Debug, Clone, Default,
+)]
+#[diesel(table_name = game_settings, primary_key(game_id), treat_none_as_null = true)]
+pub struct GameSettings {
+    pub game_id: Uuid,
+    pub autoskip_delay: Option<i32>,
+    pub warn_delay: Option<i32>,
+    pub skip_penalty: Option<i32>,
+    pub win_sentences: Option<Vec<String>>,
+    pub page_size: Option<i32>,
+    pub season_rollover: Option<String>,
+    pub undo_window: Option<i32>,
+    pub audit_channel: Option<String>,
+    pub hint_penalty: Option<i32>,
+    pub scoring: Option<String>,
+    pub speed_brackets: Option<String>,
+    pub poster_brackets: Option<String>,
+    pub poster_points: Option<i32>,
+    pub duplicate_pictures: Option<String>,
+    pub reveal_location: Option<bool>,
+    pub scoreboard_image: Option<bool>,
+}
+
+/// Settings of a guild, shared by all its games.
+#[derive(Queryable, Insertable, AsChangeset, Identifiable, Debug, Clone, Default)]
+#[diesel(table_name = guild_settings, primary_key(guild_id), treat_none_as_null = true)]
+pub struct GuildSettings {
+    pub guild_id: String,
+    pub locale: Option<String>,
+}
+
+#[derive(Queryable, Identifiable, Insertable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = season)]
+pub struct Season {
+    pub id: Uuid,
+    pub game_id: Uuid,
+    pub name: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = season)]
+pub struct NewSeason<'a> {
+    pub game_id: &'a Uuid,
+    pub name: &'a str,
+}
+
+#[derive(Queryable, Identifiable, Debug, Clone)]
+#[diesel(table_name = audit_event)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub game_id: Uuid,
+    pub actor_id: Option<String>,
+    pub action: String,
+    pub target_id: Option<String>,
+    pub before_id: Option<Uuid>,
+    pub after_id: Option<Uuid>,
+    pub details: Option<String>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = audit_event)]
+pub struct NewAuditEvent {
+    pub game_id: Uuid,
+    pub actor_id: Option<String>,
+    pub action: String,
+    pub target_id: Option<String>,
+    pub before_id: Option<Uuid>,
+    pub after_id: Option<Uuid>,
+    pub details: Option<String>,
+}
+
+#[derive(Queryable, Identifiable, Debug, Clone)]
+#[diesel(table_name = picture)]
+pub struct Picture {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    /// URL the picture was downloaded from
+    pub url: String,
+    /// Key of the picture in the storage backend
+    pub storage_key: String,
+    /// SHA-256 of the content, hex encoded
+    pub hash: String,
+    /// Size in bytes
+    pub size: i32,
+    pub width: i32,
+    pub height: i32,
+    pub mime_type: String,
+    /// Perceptual hash, close for similar pictures
+    pub phash: Option<i64>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = picture)]
+pub struct NewPicture {
+    pub url: String,
+    pub storage_key: String,
+    pub hash: String,
+    pub size: i32,
+    pub width: i32,
+    pub height: i32,
+    pub mime_type: String,
+    pub phash: Option<i64>,
+}
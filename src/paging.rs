@@ -0,0 +1,175 @@
+//! Paginated views: messages of the bot browsable with reactions
+//!
+//! Commands render a page of a [`View`]. Once sent, the message is tracked by the [`Paginator`]
+//! until it expires, so that the controls can render the other pages whatever the view.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serenity::model::id::{MessageId, UserId};
+use uuid::Uuid;
+
+use crate::{
+    cmd::{admin, player},
+    engine::Env,
+    error::{Error, Result},
+    models::Game,
+    store::GameStore,
+    transport::Embed,
+};
+
+/// Reactions switching pages.
+pub const FIRST: &str = "⏮️";
+pub const PREVIOUS: &str = "⬅️";
+pub const NEXT: &str = "➡️";
+pub const LAST: &str = "⏭️";
+pub const CONTROLS: &[&str] = &[FIRST, PREVIOUS, NEXT, LAST];
+
+/// How long a paged message stays browsable after being shown.
+const EXPIRY: TimeDelta = TimeDelta::days(1);
+
+/// What a paged message shows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum View {
+    /// Current scores
+    Scoreboard,
+    /// Scores of a season, by name
+    Season(String),
+    /// Played pictures, only the ones posted or found by a player if set
+    History(Option<UserId>),
+    /// Audit log, newest first
+    Audit,
+    /// Resets of the scores
+    Resets,
+}
+
+/// Which page of a view a message shows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PageState {
+    pub game_id: Uuid,
+    pub view: View,
+    pub page: usize,
+    pub page_count: usize,
+}
+
+impl PageState {
+    /// Page to show on a control, `None` if the reaction is not one or the page is already shown.
+    pub fn target(&self, emoji: &str) -> Option<usize> {
+        let page = match emoji {
+            FIRST => 1,
+            PREVIOUS => self.page.saturating_sub(1).max(1),
+            NEXT => (self.page + 1).min(self.page_count),
+            LAST => self.page_count,
+            _ => return None,
+        };
+        (page != self.page).then_some(page)
+    }
+}
+
+/// Rendered page of a view.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Paged {
+    pub embed: Embed,
+    pub state: PageState,
+}
+
+/// Render a page of a view.
+pub async fn render(
+    env: &Env,
+    store: &mut dyn GameStore,
+    game: &Game,
+    view: View,
+    page: usize,
+) -> Result<Paged> {
+    if page < 1 {
+        return Err(Error::InvalidPage);
+    }
+    let (embed, page_count) = match &view {
+        View::Scoreboard => player::scoreboard_view(env, store, game, page).await?,
+        View::Season(name) => player::season_view(env, store, game, name, page).await?,
+        View::History(player) => player::history_view(env, store, game, *player, page).await?,
+        View::Audit => admin::audit_view(env, store, game, page).await?,
+        View::Resets => admin::resets_view(env, store, game, page).await?,
+    };
+    Ok(Paged { embed, state: PageState { game_id: game.id, view, page, page_count } })
+}
+
+/// Number of pages needed to show `len` entries, checking `page` is one of them.
+pub fn page_count(len: usize, per_page: usize, page: usize) -> Result<usize> {
+    let page_count = len.div_ceil(per_page).max(1);
+    if page < 1 || page > page_count {
+        return Err(Error::InvalidPage);
+    }
+    Ok(page_count)
+}
+
+/// Paged messages of the bot still browsable.
+#[derive(Default)]
+pub struct Paginator {
+    pages: Mutex<HashMap<MessageId, (PageState, DateTime<Utc>)>>,
+}
+
+impl Paginator {
+    /// Track the page shown by a message, forgetting about the expired ones.
+    pub fn register(&self, message: MessageId, state: PageState) {
+        let now = Utc::now();
+        let mut pages = self.pages.lock().unwrap();
+        pages.retain(|_, (_, expires_at)| *expires_at > now);
+        pages.insert(message, (state, now + EXPIRY));
+    }
+
+    /// Page shown by a message, if it is still browsable.
+    pub fn get(&self, message: MessageId) -> Option<PageState> {
+        let pages = self.pages.lock().unwrap();
+        let (state, expires_at) = pages.get(&message)?;
+        (*expires_at > Utc::now()).then(|| state.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(page: usize, page_count: usize) -> PageState {
+        PageState { game_id: Uuid::nil(), view: View::Scoreboard, page, page_count }
+    }
+
+    #[test]
+    fn target_moves_within_pages() {
+        let middle = state(2, 3);
+        assert_eq!(middle.target(FIRST), Some(1));
+        assert_eq!(middle.target(PREVIOUS), Some(1));
+        assert_eq!(middle.target(NEXT), Some(3));
+        assert_eq!(middle.target(LAST), Some(3));
+        assert_eq!(middle.target("👍"), None);
+    }
+
+    #[test]
+    fn target_stops_at_the_ends() {
+        let first = state(1, 3);
+        assert_eq!(first.target(FIRST), None);
+        assert_eq!(first.target(PREVIOUS), None);
+        let last = state(3, 3);
+        assert_eq!(last.target(NEXT), None);
+        assert_eq!(last.target(LAST), None);
+
+        let single = state(1, 1);
+        assert!(CONTROLS.iter().all(|control| single.target(control).is_none()));
+    }
+
+    #[test]
+    fn page_count_rounds_up() {
+        assert_eq!(page_count(0, 10, 1).unwrap(), 1);
+        assert_eq!(page_count(10, 10, 1).unwrap(), 1);
+        assert_eq!(page_count(11, 10, 2).unwrap(), 2);
+        assert_eq!(page_count(30, 10, 3).unwrap(), 3);
+    }
+
+    #[test]
+    fn page_count_checks_page() {
+        assert!(matches!(page_count(0, 10, 0), Err(Error::InvalidPage)));
+        assert!(matches!(page_count(0, 10, 2), Err(Error::InvalidPage)));
+        assert!(matches!(page_count(10, 10, 2), Err(Error::InvalidPage)));
+        assert!(matches!(page_count(11, 10, 3), Err(Error::InvalidPage)));
+    }
+}
@@ -0,0 +1,222 @@
+//! Spotting pictures played again, through a perceptual hash surviving resizing and recompression
+
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Utc};
+use image::{DynamicImage, imageops::FilterType};
+use serenity::model::{id::UserId, mention::Mentionable};
+
+use crate::{
+    error::{Error, Result},
+    i18n::{Locale, tr},
+    models::*,
+};
+
+/// Most differing bits between the hashes of two pictures considered the same.
+const MAX_DISTANCE: u32 = 6;
+
+/// What to do with a picture played before in the same game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Tell everybody, but play it anyway
+    Warn,
+    /// Refuse the participation
+    Reject,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "warn" => Ok(Self::Warn),
+            "reject" => Ok(Self::Reject),
+            _ => Err(Error::InvalidSettingValue),
+        }
+    }
+}
+
+impl Display for DuplicatePolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Warn => write!(f, "warn"),
+            Self::Reject => write!(f, "reject"),
+        }
+    }
+}
+
+/// Previous play of a picture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    pub played_at: DateTime<Utc>,
+    /// Who found it, if anybody did
+    pub finder: Option<UserId>,
+}
+
+impl Duplicate {
+    /// When the picture was played, and by whom it was found.
+    pub fn describe(&self, locale: Locale) -> String {
+        let date = locale.format_date(self.played_at);
+        match self.finder {
+            Some(finder) => {
+                tr!(locale, "duplicate-found", date = date, finder = finder.mention().to_string())
+            }
+            None => tr!(locale, "duplicate-never-found", date = date),
+        }
+    }
+}
+
+/// Difference hash: one bit per pair of neighbour pixels of the picture shrunk to 9x8 in gray,
+/// set when the left one is brighter.
+pub fn dhash(image: &DynamicImage) -> i64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0];
+            hash = hash << 1 | u64::from(brighter);
+        }
+    }
+    hash as i64
+}
+
+/// Last play of a picture similar to `picture` among the played ones, oldest first.
+pub fn find(
+    played: &[(Participation, Picture, Option<Win>)],
+    picture: &Picture,
+) -> Result<Option<Duplicate>> {
+    let Some(phash) = picture.phash else { return Ok(None) };
+    let Some((part, _, win)) = played.iter().rev().find(|(_, played, _)| {
+        played.id != picture.id
+            && played.phash.is_some_and(|other| (phash ^ other).count_ones() <= MAX_DISTANCE)
+    }) else {
+        return Ok(None);
+    };
+    let finder = match win.as_ref().filter(|_| part.is_win) {
+        Some(win) => Some(parse_user_id(&win.winner_id)?),
+        None => None,
+    };
+    // The hand may have been taken long before its picture was posted
+    Ok(Some(Duplicate { played_at: part.updated_at, finder }))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+    use image::{GrayImage, Luma};
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// Horizontal gradient, getting brighter to the right unless `reversed`.
+    fn gradient(width: u32, height: u32, reversed: bool) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, _| {
+            let x = if reversed { width - 1 - x } else { x };
+            Luma([(x * 255 / (width - 1)) as u8])
+        }))
+    }
+
+    fn picture(phash: Option<i64>) -> Picture {
+        Picture {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            url: "https://example.com/pic.jpg".to_owned(),
+            storage_key: "key.jpg".to_owned(),
+            hash: String::new(),
+            size: 0,
+            width: 0,
+            height: 0,
+            mime_type: "image/jpeg".to_owned(),
+            phash,
+        }
+    }
+
+    /// Play of `picture`, posted a day after the hand was taken, and found by `winner` if any.
+    fn played(
+        day: u32,
+        picture: &Picture,
+        winner: Option<&str>,
+    ) -> (Participation, Picture, Option<Win>) {
+        let created_at = Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap();
+        let win = winner.map(|winner| Win {
+            id: Uuid::new_v4(),
+            created_at,
+            player_id: "1".to_owned(),
+            winner_id: winner.to_owned(),
+            reset: false,
+            reset_at: None,
+            reset_id: None,
+            score: 100,
+            poster_score: 0,
+        });
+        let part = Participation {
+            id: Uuid::new_v4(),
+            created_at,
+            player_id: "1".to_owned(),
+            is_win: win.is_some(),
+            won_at: win.as_ref().map(|win| win.created_at),
+            win_id: win.as_ref().map(|win| win.id),
+            is_skip: win.is_none(),
+            skipped_at: None,
+            picture_url: Some(picture.url.clone()),
+            game_id: Uuid::new_v4(),
+            warned_at: None,
+            updated_at: created_at + Duration::days(1),
+            is_auto_skip: false,
+            hints: vec![],
+            picture_id: Some(picture.id),
+            latitude: None,
+            longitude: None,
+            resumed_at: None,
+        };
+        (part, picture.clone(), win)
+    }
+
+    #[test]
+    fn dhash_survives_resizing() {
+        let hash = dhash(&gradient(90, 80, false));
+        assert_eq!(hash, dhash(&gradient(900, 800, false)));
+        assert_eq!(hash, dhash(&gradient(45, 40, false)));
+        // Every pixel is darker than its right neighbour
+        assert_eq!(hash, 0);
+        assert_eq!(dhash(&gradient(90, 80, true)), -1);
+    }
+
+    #[test]
+    fn find_needs_close_hashes() {
+        let played = [played(1, &picture(Some(0)), Some("2"))];
+
+        // Up to 6 differing bits
+        let duplicate = find(&played, &picture(Some(0b11_1111))).unwrap().unwrap();
+        assert_eq!(duplicate.finder, Some(UserId(2)));
+        assert_eq!(find(&played, &picture(Some(0b111_1111))).unwrap(), None);
+        assert_eq!(find(&played, &picture(Some(-1))).unwrap(), None);
+        assert_eq!(find(&played, &picture(None)).unwrap(), None);
+    }
+
+    #[test]
+    fn find_reports_last_play() {
+        let first = picture(Some(0));
+        let played = [
+            played(1, &first, Some("2")),
+            played(3, &picture(Some(1)), None),
+            played(5, &picture(Some(-1)), Some("4")),
+        ];
+
+        // Posting time rather than the time the hand was taken
+        let duplicate = find(&played, &picture(Some(0))).unwrap().unwrap();
+        assert_eq!(duplicate.played_at, Utc.with_ymd_and_hms(2024, 1, 4, 12, 0, 0).unwrap());
+        assert_eq!(duplicate.finder, None);
+        // Not a duplicate of itself
+        assert_eq!(find(&played[..1], &first).unwrap(), None);
+    }
+
+    #[test]
+    fn find_fails_on_malformed_finder() {
+        let played = [played(1, &picture(Some(0)), Some("nobody"))];
+        assert!(matches!(find(&played, &picture(Some(0))), Err(Error::Db(_))));
+    }
+}
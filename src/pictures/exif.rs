@@ -0,0 +1,301 @@
+//! Metadata of the pictures: where they were taken, often giving the answer away
+//!
+//! The location is read when a picture is stored, and every metadata block is stripped from the
+//! copies posted back, so that nobody finds the answer by downloading them.
+
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    io::Cursor,
+};
+
+use exif::{In, Reader, Tag, Value};
+
+use crate::error::{Error, Result};
+
+/// GPS coordinates of a picture, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Location {
+    /// OpenStreetMap page with a marker on the location.
+    pub fn osm_url(&self) -> String {
+        let Self { latitude, longitude } = self;
+        format!(
+            "https://www.openstreetmap.org/?mlat={latitude:.5}&mlon={longitude:.5}#map=16/{latitude:.5}/{longitude:.5}"
+        )
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{:.5}, {:.5}", self.latitude, self.longitude)
+    }
+}
+
+/// Location found in the EXIF tags of a picture, if any.
+pub fn location(data: &[u8]) -> Option<Location> {
+    let exif = Reader::new().read_from_container(&mut Cursor::new(data)).ok()?;
+    let coordinate = |tag, ref_tag, negative: &[u8]| {
+        let Value::Rational(parts) = &exif.get_field(tag, In::PRIMARY)?.value else { return None };
+        let [degrees, minutes, seconds] = parts.as_slice() else { return None };
+        let value = degrees.to_f64() + minutes.to_f64() / 60. + seconds.to_f64() / 3600.;
+
+        // Missing references are seen in the wild, north and east being the usual defaults
+        let reference = exif.get_field(ref_tag, In::PRIMARY).and_then(|field| match &field.value {
+            Value::Ascii(values) => values.first().cloned(),
+            _ => None,
+        });
+        let sign = if reference.is_some_and(|r| r.starts_with(negative)) { -1. } else { 1. };
+        Some(sign * value).filter(|value| value.is_finite())
+    };
+
+    let latitude = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, b"S")?;
+    let longitude = coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, b"W")?;
+    ((-90. ..=90.).contains(&latitude) && (-180. ..=180.).contains(&longitude))
+        .then_some(Location { latitude, longitude })
+}
+
+/// Picture without its metadata blocks: EXIF, XMP, IPTC and comments.
+///
+/// The pixels are left untouched, so this works on JPEG, PNG and WebP files only, other formats
+/// being returned as is.
+pub fn strip(data: &[u8]) -> Result<Vec<u8>> {
+    let stripped = match data {
+        [0xff, 0xd8, ..] => strip_jpeg(data),
+        [0x89, b'P', b'N', b'G', ..] => strip_png(data),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => strip_webp(data),
+        _ => Some(data.to_vec()),
+    };
+    stripped.ok_or(Error::Storage("Malformed picture".into()))
+}
+
+/// JPEG segments before the image data, dropping APP1 (EXIF, XMP), APP13 (IPTC) and comments.
+fn strip_jpeg(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = data[..2].to_vec();
+    let mut pos = 2;
+    loop {
+        let &[0xff, marker, ..] = data.get(pos..)? else { return None };
+        match marker {
+            // Padding
+            0xff => pos += 1,
+            // Standalone markers
+            0x01 | 0xd0..=0xd7 => {
+                out.extend_from_slice(&data[pos..pos + 2]);
+                pos += 2;
+            }
+            // Start of scan, followed by the image data up to the end
+            0xda => {
+                out.extend_from_slice(&data[pos..]);
+                return Some(out);
+            }
+            _ => {
+                let len = u16::from_be_bytes(data.get(pos + 2..pos + 4)?.try_into().ok()?);
+                let end = pos + 2 + usize::from(len);
+                let segment = data.get(pos..end)?;
+                if !matches!(marker, 0xe1 | 0xed | 0xfe) {
+                    out.extend_from_slice(segment);
+                }
+                pos = end;
+            }
+        }
+    }
+}
+
+/// PNG chunks, dropping the EXIF and text ones.
+fn strip_png(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = data.get(..8)?.to_vec();
+    let mut pos = 8;
+    while pos < data.len() {
+        let len = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?);
+        // Length, type, data and CRC
+        let end = pos.checked_add(12)?.checked_add(usize::try_from(len).ok()?)?;
+        let chunk = data.get(pos..end)?;
+        if !matches!(&chunk[4..8], b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt") {
+            out.extend_from_slice(chunk);
+        }
+        pos = end;
+    }
+    Some(out)
+}
+
+/// WebP chunks, dropping the EXIF and XMP ones along with their flags.
+fn strip_webp(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = data.get(..12)?.to_vec();
+    let mut pos = 12;
+    while pos < data.len() {
+        let len = u32::from_le_bytes(data.get(pos + 4..pos + 8)?.try_into().ok()?);
+        // Chunks are padded to an even length
+        let len = usize::try_from(len).ok()?.checked_add(usize::try_from(len % 2).ok()?)?;
+        let end = pos.checked_add(8)?.checked_add(len)?;
+        let chunk = data.get(pos..end)?;
+        match &chunk[..4] {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" => {
+                let start = out.len();
+                out.extend_from_slice(chunk);
+                // Flags of the EXIF and XMP chunks
+                *out.get_mut(start + 8)? &= !0x0c;
+            }
+            _ => out.extend_from_slice(chunk),
+        }
+        pos = end;
+    }
+    let size = u32::try_from(out.len() - 8).ok()?;
+    out[4..8].copy_from_slice(&size.to_le_bytes());
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use exif::{Field, Rational, experimental::Writer};
+
+    use super::*;
+
+    /// TIFF file with the GPS tags of a location given in degrees, minutes and seconds, and its
+    /// references if any.
+    fn tagged(latitude: [u32; 3], longitude: [u32; 3], refs: Option<(&str, &str)>) -> Vec<u8> {
+        let coordinate = |tag, dms: [u32; 3]| Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(dms.map(|n| Rational::from((n, 1))).to_vec()),
+        };
+        let reference = |tag, r: &str| Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![r.as_bytes().to_vec()]),
+        };
+        let mut fields =
+            vec![coordinate(Tag::GPSLatitude, latitude), coordinate(Tag::GPSLongitude, longitude)];
+        if let Some((lat_ref, long_ref)) = refs {
+            fields.push(reference(Tag::GPSLatitudeRef, lat_ref));
+            fields.push(reference(Tag::GPSLongitudeRef, long_ref));
+        }
+
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut data = Cursor::new(vec![]);
+        writer.write(&mut data, false).unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn location_signs() {
+        let at = |refs| {
+            let location = location(&tagged([48, 51, 36], [2, 17, 24], refs)).unwrap();
+            (location.latitude, location.longitude)
+        };
+        assert_eq!(at(Some(("N", "E"))), (48.86, 2.29));
+        assert_eq!(at(Some(("S", "W"))), (-48.86, -2.29));
+        assert_eq!(at(Some(("N", "W"))), (48.86, -2.29));
+        // North and east by default
+        assert_eq!(at(None), (48.86, 2.29));
+    }
+
+    #[test]
+    fn location_range() {
+        assert_eq!(
+            location(&tagged([90, 0, 0], [180, 0, 0], Some(("S", "W")))).unwrap(),
+            Location { latitude: -90., longitude: -180. }
+        );
+        assert_eq!(location(&tagged([90, 0, 1], [0, 0, 0], None)), None);
+        assert_eq!(location(&tagged([0, 0, 0], [180, 1, 0], None)), None);
+        assert_eq!(location(b"not a picture"), None);
+    }
+
+    fn jpeg_segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let len = u16::try_from(payload.len() + 2).unwrap();
+        [&[0xff, marker][..], &len.to_be_bytes(), payload].concat()
+    }
+
+    #[test]
+    fn strip_jpeg_segments() {
+        let jfif = jpeg_segment(0xe0, b"JFIF\0");
+        let quantization = jpeg_segment(0xdb, &[0; 8]);
+        let scan = [&jpeg_segment(0xda, &[1, 2, 3])[..], &[4, 5, 0xff, 0xe1, 6], &[0xff, 0xd9]];
+        let data = [
+            &[0xff, 0xd8][..],
+            &jfif,
+            &jpeg_segment(0xe1, b"Exif\0\0tags"),
+            // Padding before a marker
+            &[0xff],
+            &jpeg_segment(0xed, b"Photoshop 3.0\0"),
+            &quantization,
+            &jpeg_segment(0xfe, b"comment"),
+            &scan.concat(),
+        ]
+        .concat();
+
+        // The image data is kept as is, even when looking like a marker, but not the padding
+        let expected = [&[0xff, 0xd8][..], &jfif, &quantization, &scan.concat()].concat();
+        assert_eq!(strip(&data).unwrap(), expected);
+        assert!(strip(&data[..data.len() - scan.concat().len()]).is_err());
+        assert!(strip(&data[..jfif.len() + 4]).is_err());
+    }
+
+    fn png_chunk(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let len = u32::try_from(payload.len()).unwrap();
+        [&len.to_be_bytes()[..], kind, payload, &[0; 4]].concat()
+    }
+
+    #[test]
+    fn strip_png_chunks() {
+        let signature = b"\x89PNG\r\n\x1a\n";
+        let header = png_chunk(b"IHDR", &[0; 13]);
+        let pixels = png_chunk(b"IDAT", &[1, 2, 3]);
+        let end = png_chunk(b"IEND", &[]);
+        let data = [
+            &signature[..],
+            &header,
+            &png_chunk(b"eXIf", b"tags"),
+            &png_chunk(b"tEXt", b"Comment\0text"),
+            &pixels,
+            &png_chunk(b"zTXt", b"compressed"),
+            &png_chunk(b"iTXt", b"international"),
+            &end,
+        ]
+        .concat();
+
+        assert_eq!(strip(&data).unwrap(), [&signature[..], &header, &pixels, &end].concat());
+        assert!(strip(&data[..data.len() - 1]).is_err());
+    }
+
+    fn webp_chunk(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let len = u32::try_from(payload.len()).unwrap();
+        let padding: &[u8] = if payload.len() % 2 == 1 { &[0] } else { &[] };
+        [&kind[..], &len.to_le_bytes(), payload, padding].concat()
+    }
+
+    fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let chunks = chunks.concat();
+        let size = u32::try_from(chunks.len() + 4).unwrap();
+        [&b"RIFF"[..], &size.to_le_bytes(), b"WEBP", &chunks].concat()
+    }
+
+    #[test]
+    fn strip_webp_chunks() {
+        let pixels = webp_chunk(b"VP8 ", &[1, 2, 3]);
+        let animation = webp_chunk(b"ANIM", &[0; 6]);
+        let data = riff(&[
+            // ICC, alpha, EXIF and XMP flags
+            webp_chunk(b"VP8X", &[0x3c, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            webp_chunk(b"EXIF", b"tags"),
+            animation.clone(),
+            pixels.clone(),
+            webp_chunk(b"XMP ", b"<x:xmpmeta/>"),
+        ]);
+
+        let flags = webp_chunk(b"VP8X", &[0x30, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(strip(&data).unwrap(), riff(&[flags, animation, pixels]));
+        assert!(strip(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn strip_keeps_other_formats() {
+        assert_eq!(strip(b"GIF89a...").unwrap(), b"GIF89a...");
+    }
+}
@@ -0,0 +1,55 @@
+//! Pictures stored as files in a local directory
+
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use tokio::fs;
+
+use super::Backend;
+use crate::error::{Error, Result};
+
+pub struct FsBackend {
+    directory: PathBuf,
+}
+
+impl FsBackend {
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        Self { directory: directory.as_ref().to_owned() }
+    }
+
+    /// Files are spread in subdirectories named after the first characters of their key, to keep
+    /// directories small.
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        if key.len() < 2 || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '.') {
+            return Err(Error::Storage(format!("Invalid picture key {key:?}").into()));
+        }
+        Ok(self.directory.join(&key[..2]).join(key))
+    }
+}
+
+#[async_trait]
+impl Backend for FsBackend {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        // Written aside then renamed, so that a crash never leaves a truncated picture
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, data).await?;
+        fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        Ok(fs::read(self.path(key)?).await?)
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        Ok(fs::try_exists(self.path(key)?).await?)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        Ok(fs::remove_file(self.path(key)?).await?)
+    }
+}
@@ -0,0 +1,148 @@
+//! Storage of the pictures to guess, as Discord CDN URLs expire and break with deleted messages
+//!
+//! Pictures are downloaded when posted, and kept in a [`Backend`] under a key derived from their
+//! content. Only the filesystem is supported for now, but anything S3-like fits the trait.
+//!
+//! Stored pictures also get a perceptual hash, to spot the ones played again, and their location is
+//! read from their EXIF tags.
+
+use std::{io::Cursor, path::Path};
+
+use async_trait::async_trait;
+use image::ImageReader;
+use sha2::{Digest, Sha256};
+use tracing::{info, instrument};
+
+use crate::{
+    config::StorageConfig,
+    error::{Error, Result},
+    models::{NewPicture, Picture},
+};
+
+mod duplicate;
+mod exif;
+mod fs;
+
+pub use duplicate::{Duplicate, DuplicatePolicy, find as find_duplicate};
+pub use exif::Location;
+pub use fs::FsBackend;
+
+/// Where the pictures are kept, by key.
+#[async_trait]
+pub trait Backend: Send + Sync {
+    /// Store a picture, replacing any previous one with the same key.
+    async fn put(&self, key: &str, data: &[u8]) -> Result<()>;
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+
+    async fn exists(&self, key: &str) -> Result<bool>;
+
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// Picture just stored, with what was read from it.
+#[derive(Debug, Clone)]
+pub struct Saved {
+    pub picture: NewPicture,
+    pub location: Option<Location>,
+    /// Whether the file was written by this save, rather than kept from an identical picture
+    pub new: bool,
+}
+
+pub struct Pictures {
+    backend: Box<dyn Backend>,
+    client: reqwest::Client,
+    max_size: u64,
+}
+
+impl Pictures {
+    pub fn new(backend: Box<dyn Backend>, max_size: u64) -> Self {
+        Self { backend, client: reqwest::Client::new(), max_size }
+    }
+
+    /// Pictures stored on the filesystem, as set in the config file.
+    pub fn from_config(config: &StorageConfig) -> Self {
+        Self::new(Box::new(FsBackend::new(&config.directory)), config.max_size)
+    }
+
+    /// Download a picture and store it, returning its metadata to save along with where it was
+    /// taken.
+    #[instrument(skip(self))]
+    pub async fn save(&self, url: &str) -> Result<Saved> {
+        let data = self.download(url).await?;
+        // Decoding and hashing take a while on large pictures
+        let owned_url = url.to_owned();
+        let (picture, location, data) = tokio::task::spawn_blocking(move || {
+            let picture = analyze(&owned_url, &data);
+            (picture, exif::location(&data), data)
+        })
+        .await
+        .map_err(|e| Error::Storage(e.into()))?;
+        let picture = picture?;
+
+        // Keys come from the content, so an existing file is the same picture
+        let new = !self.backend.exists(&picture.storage_key).await?;
+        if new {
+            self.backend.put(&picture.storage_key, &data).await?;
+        }
+        info!("Stored picture {picture:?}");
+        Ok(Saved { picture, location, new })
+    }
+
+    /// Delete a picture that ended up not being played, unless it was already stored before.
+    pub async fn discard(&self, saved: &Saved) -> Result<()> {
+        if saved.new {
+            self.backend.delete(&saved.picture.storage_key).await?;
+            info!("Discarded picture {:?}", saved.picture);
+        }
+        Ok(())
+    }
+
+    /// Content of a stored picture, without the metadata that could give the answer away.
+    pub async fn load(&self, picture: &Picture) -> Result<Vec<u8>> {
+        exif::strip(&self.backend.get(&picture.storage_key).await?)
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        let mut res = self.client.get(url).send().await?.error_for_status()?;
+        if res.content_length().is_some_and(|len| len > self.max_size) {
+            return Err(Error::Storage("Picture too big".into()));
+        }
+
+        // The announced length may be missing, or lie
+        let mut data = vec![];
+        while let Some(chunk) = res.chunk().await? {
+            data.extend_from_slice(&chunk);
+            if data.len() as u64 > self.max_size {
+                return Err(Error::Storage("Picture too big".into()));
+            }
+        }
+        Ok(data)
+    }
+}
+
+/// Hashes, size, dimensions and type of a picture.
+fn analyze(url: &str, data: &[u8]) -> Result<NewPicture> {
+    let reader = ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+    let format = reader.format().ok_or(Error::Storage("Unknown picture format".into()))?;
+    let image = reader.decode()?;
+
+    let hash = format!("{:x}", Sha256::digest(data));
+    let extension = format.extensions_str().first().copied().unwrap_or("bin");
+    Ok(NewPicture {
+        url: url.to_owned(),
+        storage_key: format!("{hash}.{extension}"),
+        hash,
+        size: i32::try_from(data.len()).unwrap_or(i32::MAX),
+        width: i32::try_from(image.width()).unwrap_or(i32::MAX),
+        height: i32::try_from(image.height()).unwrap_or(i32::MAX),
+        mime_type: format.to_mime_type().to_owned(),
+        phash: Some(duplicate::dhash(&image)),
+    })
+}
+
+/// File name to upload a stored picture as.
+pub fn file_name(picture: &Picture) -> String {
+    let extension = Path::new(&picture.storage_key).extension().and_then(|e| e.to_str());
+    format!("picture.{}", extension.unwrap_or("bin"))
+}
@@ -0,0 +1,122 @@
+//! Evolution of the scores drawn as a line chart
+
+use chrono::{DateTime, TimeDelta, Utc};
+use tiny_skia::ColorU8;
+
+use super::*;
+use crate::{i18n::Locale, score};
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 420;
+const PADDING: f32 = 20.;
+/// Room left to the scores on the left, and to the legend on the right
+const AXIS_WIDTH: f32 = 60.;
+const LEGEND_WIDTH: f32 = 190.;
+const LEGEND_ROW: f32 = 28.;
+
+/// Colours of the curves, one per player.
+pub const PALETTE: &[ColorU8] = &[
+    ACCENT,
+    ColorU8::from_rgba(0xf1, 0xc4, 0x0f, 0xff),
+    GREEN,
+    RED,
+    ColorU8::from_rgba(0xeb, 0x45, 0x9e, 0xff),
+    ColorU8::from_rgba(0x1a, 0xbc, 0x9c, 0xff),
+    ColorU8::from_rgba(0xe6, 0x7e, 0x22, 0xff),
+    TEXT,
+];
+
+/// Curve of a player.
+#[derive(Clone, Debug)]
+pub struct Series {
+    pub name: String,
+    /// Score after each change, oldest first, in hundredths of a point
+    pub points: Vec<(DateTime<Utc>, i64)>,
+}
+
+impl Series {
+    fn last(&self) -> i64 {
+        self.points.last().map(|(_, score)| *score).unwrap_or_default()
+    }
+}
+
+/// Draw the scores of players from 0 at `start` to their last value at `end`, as a PNG.
+///
+/// Only as many series as colours in the [`PALETTE`] are drawn.
+pub fn draw(
+    series: &[Series],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    locale: Locale,
+) -> Result<Vec<u8>> {
+    let series = &series[..series.len().min(PALETTE.len())];
+    let mut pixmap = canvas(WIDTH, HEIGHT)?;
+
+    // Round the score axis to steps of 1, 2 or 5 times a power of ten, half a point at least
+    let scores = series.iter().flat_map(|s| s.points.iter().map(|(_, score)| *score));
+    let (low, high) = scores
+        .fold((0, i64::from(score::POINT)), |(low, high), score| (low.min(score), high.max(score)));
+    let step = nice_step((high - low) / 5).max(i64::from(score::POINT / 2));
+    let (low, high) = (low.div_euclid(step) * step, (high + step - 1).div_euclid(step) * step);
+
+    let (left, right) = (PADDING + AXIS_WIDTH, WIDTH as f32 - LEGEND_WIDTH - PADDING);
+    let (top, bottom) = (PADDING, HEIGHT as f32 - PADDING - 24.);
+    let span = (end - start).max(TimeDelta::seconds(1)).num_seconds() as f32;
+    let x = |at: DateTime<Utc>| left + (at - start).num_seconds() as f32 / span * (right - left);
+    let y = |score: i64| bottom - (score - low) as f32 / (high - low) as f32 * (bottom - top);
+
+    for tick in (low..=high).step_by(step as usize) {
+        let colour = if tick == 0 { MUTED } else { TRACK };
+        fill_rect(&mut pixmap, left, y(tick).round(), right - left, 1., colour);
+        let label = score::format(tick, locale);
+        draw_text(&mut pixmap, &label, left - 8., y(tick) - 8., 13., Align::Right, MUTED);
+    }
+    let format = |at| {
+        if end - start < TimeDelta::days(2) {
+            locale.format_datetime(at)
+        } else {
+            locale.format_date(at)
+        }
+    };
+    draw_text(&mut pixmap, &format(start), left, bottom + 8., 13., Align::Left, MUTED);
+    draw_text(&mut pixmap, &format(end), right, bottom + 8., 13., Align::Right, MUTED);
+
+    for (i, (series, &colour)) in series.iter().zip(PALETTE).enumerate() {
+        // Scores only change on wins, hence the steps
+        let mut line = vec![(x(start), y(0))];
+        let mut score = 0;
+        for &(at, after) in &series.points {
+            line.push((x(at), y(score)));
+            line.push((x(at), y(after)));
+            score = after;
+        }
+        line.push((x(end), y(score)));
+        stroke_line(&mut pixmap, &line, 2.5, colour);
+        fill_circle(&mut pixmap, x(end), y(score), 4., colour);
+
+        let row = top + LEGEND_ROW * i as f32;
+        let legend = right + PADDING;
+        fill_circle(&mut pixmap, legend + 6., row + 9., 6., colour);
+        let total = score::format(series.last(), locale);
+        let total_width = text_width(&total, 15.);
+        draw_text(&mut pixmap, &total, WIDTH as f32 - PADDING, row, 15., Align::Right, TEXT);
+        let name_width = WIDTH as f32 - PADDING - legend - 20. - total_width - 8.;
+        let name = fit_text(&series.name, 15., name_width);
+        draw_text(&mut pixmap, &name, legend + 20., row, 15., Align::Left, TEXT);
+    }
+
+    encode(&pixmap)
+}
+
+/// Smallest of 1, 2 or 5 times a power of ten at least `raw`.
+fn nice_step(raw: i64) -> i64 {
+    let mut magnitude = 1;
+    loop {
+        for factor in [1, 2, 5] {
+            if factor * magnitude >= raw {
+                return factor * magnitude;
+            }
+        }
+        magnitude *= 10;
+    }
+}
@@ -0,0 +1,174 @@
+//! Pictures drawn by the bot, with a bundled font so that they look the same everywhere
+
+use std::sync::LazyLock;
+
+use ab_glyph::{Font, FontRef, OutlinedGlyph, PxScale, ScaleFont, point};
+use tiny_skia::{
+    Color, ColorU8, LineJoin, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Rect, Stroke,
+    Transform,
+};
+
+use crate::error::{Error, Result};
+
+pub mod chart;
+pub mod scoreboard;
+
+static FONT: LazyLock<FontRef<'static>> = LazyLock::new(|| {
+    FontRef::try_from_slice(include_bytes!("../../assets/fonts/DejaVuSans.ttf"))
+        .expect("Invalid bundled font")
+});
+
+/// Colours shared by all pictures, close to the dark theme of Discord.
+pub const BACKGROUND: ColorU8 = ColorU8::from_rgba(0x31, 0x33, 0x38, 0xff);
+pub const STRIPE: ColorU8 = ColorU8::from_rgba(0x2b, 0x2d, 0x31, 0xff);
+pub const TEXT: ColorU8 = ColorU8::from_rgba(0xf2, 0xf3, 0xf5, 0xff);
+pub const MUTED: ColorU8 = ColorU8::from_rgba(0x94, 0x9b, 0xa4, 0xff);
+pub const TRACK: ColorU8 = ColorU8::from_rgba(0x4e, 0x50, 0x58, 0xff);
+pub const ACCENT: ColorU8 = ColorU8::from_rgba(0x58, 0x65, 0xf2, 0xff);
+pub const GREEN: ColorU8 = ColorU8::from_rgba(0x23, 0xa5, 0x59, 0xff);
+pub const RED: ColorU8 = ColorU8::from_rgba(0xf2, 0x3f, 0x43, 0xff);
+
+/// Where a text is anchored horizontally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Run a drawing on the threads meant for blocking work, as rasterizing takes a while.
+pub async fn spawn<T: Send + 'static>(
+    draw: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(draw).await.map_err(|e| Error::Render(Box::new(e)))?
+}
+
+/// Blank picture of the given size.
+pub fn canvas(width: u32, height: u32) -> Result<Pixmap> {
+    let mut pixmap = Pixmap::new(width, height)
+        .ok_or_else(|| Error::Render(format!("Invalid picture size {width}x{height}").into()))?;
+    let (r, g, b, a) =
+        (BACKGROUND.red(), BACKGROUND.green(), BACKGROUND.blue(), BACKGROUND.alpha());
+    pixmap.fill(Color::from_rgba8(r, g, b, a));
+    Ok(pixmap)
+}
+
+fn paint(color: ColorU8) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(color.red(), color.green(), color.blue(), color.alpha());
+    paint
+}
+
+pub fn fill_rect(pixmap: &mut Pixmap, x: f32, y: f32, width: f32, height: f32, color: ColorU8) {
+    if let Some(rect) = Rect::from_xywh(x, y, width, height) {
+        pixmap.fill_rect(rect, &paint(color), Transform::identity(), None);
+    }
+}
+
+pub fn fill_circle(pixmap: &mut Pixmap, x: f32, y: f32, radius: f32, color: ColorU8) {
+    if let Some(path) = PathBuilder::from_circle(x, y, radius) {
+        let rule = tiny_skia::FillRule::Winding;
+        pixmap.fill_path(&path, &paint(color), rule, Transform::identity(), None);
+    }
+}
+
+/// Draw a polyline, such as the curve of a chart.
+pub fn stroke_line(pixmap: &mut Pixmap, points: &[(f32, f32)], width: f32, color: ColorU8) {
+    let mut path = PathBuilder::new();
+    for (i, &(x, y)) in points.iter().enumerate() {
+        if i == 0 { path.move_to(x, y) } else { path.line_to(x, y) }
+    }
+    if let Some(path) = path.finish() {
+        let stroke = Stroke { width, line_join: LineJoin::Round, ..Default::default() };
+        pixmap.stroke_path(&path, &paint(color), &stroke, Transform::identity(), None);
+    }
+}
+
+/// Glyphs of a text laid out on a single line starting at the origin.
+fn layout(text: &str, size: f32) -> (Vec<OutlinedGlyph>, f32) {
+    let font = FONT.as_scaled(PxScale::from(size));
+    let mut glyphs = vec![];
+    let mut x = 0.;
+    let mut previous = None;
+    for c in text.chars() {
+        let mut glyph = font.scaled_glyph(c);
+        if let Some(previous) = previous {
+            x += font.kern(previous, glyph.id);
+        }
+        previous = Some(glyph.id);
+        glyph.position = point(x, font.ascent());
+        x += font.h_advance(glyph.id);
+        glyphs.extend(FONT.outline_glyph(glyph));
+    }
+    (glyphs, x)
+}
+
+/// Width of a text on a single line, in pixels.
+pub fn text_width(text: &str, size: f32) -> f32 {
+    layout(text, size).1
+}
+
+/// Shorten a text with an ellipsis until it fits in `width` pixels.
+pub fn fit_text(text: &str, size: f32, width: f32) -> String {
+    if text_width(text, size) <= width {
+        return text.to_owned();
+    }
+    let mut chars = text.chars().collect::<Vec<_>>();
+    while !chars.is_empty() {
+        chars.pop();
+        let shortened = format!("{}…", chars.iter().collect::<String>().trim_end());
+        if text_width(&shortened, size) <= width {
+            return shortened;
+        }
+    }
+    String::new()
+}
+
+/// Draw a text with the top of its line at `y`, `x` being where it is anchored.
+pub fn draw_text(
+    pixmap: &mut Pixmap,
+    text: &str,
+    x: f32,
+    y: f32,
+    size: f32,
+    align: Align,
+    color: ColorU8,
+) {
+    let (glyphs, width) = layout(text, size);
+    let left = match align {
+        Align::Left => x,
+        Align::Center => x - width / 2.,
+        Align::Right => x - width,
+    };
+    let (pix_width, pix_height) = (pixmap.width() as i64, pixmap.height() as i64);
+    let pixels = pixmap.pixels_mut();
+    for glyph in glyphs {
+        let bounds = glyph.px_bounds();
+        let (min_x, min_y) = ((left + bounds.min.x) as i64, (y + bounds.min.y) as i64);
+        glyph.draw(|gx, gy, coverage| {
+            let (px, py) = (min_x + i64::from(gx), min_y + i64::from(gy));
+            if px < 0 || py < 0 || px >= pix_width || py >= pix_height {
+                return;
+            }
+            let pixel = &mut pixels[(py * pix_width + px) as usize];
+            *pixel = blend(*pixel, color, coverage);
+        });
+    }
+}
+
+/// Paint `color` over a pixel, `coverage` being how much of the pixel it covers.
+fn blend(dst: PremultipliedColorU8, color: ColorU8, coverage: f32) -> PremultipliedColorU8 {
+    let alpha = f32::from(color.alpha()) / 255. * coverage.clamp(0., 1.);
+    let mix = |src: u8, dst: u8| (f32::from(src) * alpha + f32::from(dst) * (1. - alpha)) as u8;
+    PremultipliedColorU8::from_rgba(
+        mix(color.red(), dst.red()),
+        mix(color.green(), dst.green()),
+        mix(color.blue(), dst.blue()),
+        mix(255, dst.alpha()),
+    )
+    .unwrap_or(dst)
+}
+
+pub fn encode(pixmap: &Pixmap) -> Result<Vec<u8>> {
+    pixmap.encode_png().map_err(|e| Error::Render(Box::new(e)))
+}
@@ -0,0 +1,83 @@
+//! Scoreboard drawn as a picture, reading better than embed fields on small screens
+
+use tiny_skia::ColorU8;
+
+use super::*;
+use crate::{
+    i18n::{Locale, tr},
+    score,
+    store::PlayerScore,
+};
+
+const WIDTH: u32 = 720;
+const PADDING: f32 = 20.;
+const ROW_HEIGHT: f32 = 64.;
+const MEDAL_RADIUS: f32 = 20.;
+/// Room left to the scores on the right
+const SCORE_WIDTH: f32 = 170.;
+
+const GOLD: ColorU8 = ColorU8::from_rgba(0xf1, 0xc4, 0x0f, 0xff);
+const SILVER: ColorU8 = ColorU8::from_rgba(0xc0, 0xc5, 0xce, 0xff);
+const BRONZE: ColorU8 = ColorU8::from_rgba(0xcd, 0x7f, 0x32, 0xff);
+
+/// Entry of the scoreboard.
+#[derive(Clone, Debug)]
+pub struct Row {
+    pub rank: usize,
+    pub name: String,
+    pub score: PlayerScore,
+    /// Points earned during the last 7 days, in hundredths of a point
+    pub delta: i64,
+}
+
+/// Draw a page of the scoreboard as a PNG, bars being relative to the `best` total.
+pub fn draw(rows: &[Row], best: i64, locale: Locale) -> Result<Vec<u8>> {
+    let height = 2. * PADDING + ROW_HEIGHT * rows.len().max(1) as f32;
+    let mut pixmap = canvas(WIDTH, height as u32)?;
+
+    let width = WIDTH as f32;
+    let name_x = PADDING + 2. * MEDAL_RADIUS + 16.;
+    let name_width = width - name_x - SCORE_WIDTH - PADDING;
+    for (i, row) in rows.iter().enumerate() {
+        let top = PADDING + ROW_HEIGHT * i as f32;
+        if i % 2 == 1 {
+            fill_rect(&mut pixmap, PADDING / 2., top, width - PADDING, ROW_HEIGHT, STRIPE);
+        }
+
+        // Medals for the podium, plain disks for the others
+        let (medal, ink) = match row.rank {
+            1 => (GOLD, STRIPE),
+            2 => (SILVER, STRIPE),
+            3 => (BRONZE, STRIPE),
+            _ => (TRACK, TEXT),
+        };
+        let center = (PADDING + MEDAL_RADIUS, top + ROW_HEIGHT / 2.);
+        fill_circle(&mut pixmap, center.0, center.1, MEDAL_RADIUS, medal);
+        let rank = row.rank.to_string();
+        let size = if rank.len() > 2 { 13. } else { 18. };
+        draw_text(&mut pixmap, &rank, center.0, center.1 - size * 0.6, size, Align::Center, ink);
+
+        let name = fit_text(&row.name, 22., name_width);
+        draw_text(&mut pixmap, &name, name_x, top + 8., 22., Align::Left, TEXT);
+
+        let progress = match best {
+            best if best > 0 => (row.score.total() as f32 / best as f32).clamp(0., 1.),
+            _ => 0.,
+        };
+        fill_rect(&mut pixmap, name_x, top + 42., name_width, 8., TRACK);
+        fill_rect(&mut pixmap, name_x, top + 42., name_width * progress, 8., ACCENT);
+
+        let total = score::format(row.score.total(), locale);
+        draw_text(&mut pixmap, &total, width - PADDING, top + 8., 22., Align::Right, TEXT);
+
+        let (delta, colour) = match row.delta {
+            0 => ("=".to_owned(), MUTED),
+            d if d > 0 => (format!("+{}", score::format(d, locale)), GREEN),
+            d => (score::format(d, locale), RED),
+        };
+        let delta = tr!(locale, "scoreboard-week-delta", delta = delta);
+        draw_text(&mut pixmap, &delta, width - PADDING, top + 38., 15., Align::Right, colour);
+    }
+
+    encode(&pixmap)
+}
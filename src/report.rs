@@ -0,0 +1,323 @@
+//! Reporting of internal errors, to a Sentry-compatible endpoint and/or a JSON-lines file
+//!
+//! Reports of the same error are deduplicated and the overall rate is limited, so that an outage
+//! of the database does not turn into a flood of reports.
+
+use std::{
+    collections::HashMap,
+    error::Error as StdError,
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::json;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use tracing::{Span, info, warn};
+use uuid::Uuid;
+
+use crate::{config::ErrorReportingConfig, error::Error, transport::Message};
+
+type SinkResult = Result<(), Box<dyn StdError + Send + Sync>>;
+
+static REPORTER: OnceLock<Reporter> = OnceLock::new();
+
+/// Where an error happened.
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    pub command: Option<String>,
+    pub guild_id: Option<GuildId>,
+    pub channel_id: Option<ChannelId>,
+    pub user_id: Option<UserId>,
+}
+
+impl Context {
+    /// Error outside of any message, like in a background task.
+    pub fn task(name: &str) -> Self {
+        Self { command: Some(name.to_owned()), ..Default::default() }
+    }
+
+    /// Error while handling a message, `command` being `None` if it is not a command.
+    pub fn message(command: Option<&str>, msg: &Message) -> Self {
+        Self {
+            command: command.map(str::to_owned),
+            guild_id: msg.guild_id,
+            channel_id: Some(msg.channel_id),
+            user_id: Some(msg.author.id),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub timestamp: DateTime<Utc>,
+    /// Variant of the error
+    pub kind: &'static str,
+    pub message: String,
+    pub command: Option<String>,
+    pub guild_id: Option<u64>,
+    pub channel_id: Option<u64>,
+    pub user_id: Option<u64>,
+    /// Tracing span active when the error was captured
+    pub span: Option<String>,
+    /// Occurrences of the same error dropped since it was last reported
+    pub suppressed: u64,
+}
+
+#[async_trait]
+trait Sink: Send + Sync {
+    async fn send(&self, report: &Report) -> SinkResult;
+}
+
+/// Appends reports to a file, one JSON object per line.
+struct FileSink {
+    path: PathBuf,
+}
+
+#[async_trait]
+impl Sink for FileSink {
+    async fn send(&self, report: &Report) -> SinkResult {
+        let mut line = serde_json::to_vec(report)?;
+        line.push(b'\n');
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || {
+            OpenOptions::new().create(true).append(true).open(path)?.write_all(&line)
+        })
+        .await??;
+        Ok(())
+    }
+}
+
+/// Sends reports as events to the store endpoint of a Sentry-compatible server.
+struct SentrySink {
+    client: reqwest::Client,
+    url: Url,
+    auth: String,
+}
+
+impl SentrySink {
+    /// Parse a DSN like `https://<key>@<host>/<project>`.
+    fn new(dsn: &str) -> Result<Self, Box<dyn StdError>> {
+        let dsn = Url::parse(dsn)?;
+        let key = dsn.username();
+        let (prefix, project) = dsn.path().rsplit_once('/').ok_or("DSN without project ID")?;
+        if key.is_empty() || project.is_empty() {
+            return Err("DSN without key or project ID".into());
+        }
+
+        let mut url = dsn.clone();
+        url.set_username("").map_err(|_| "Invalid DSN")?;
+        url.set_password(None).map_err(|_| "Invalid DSN")?;
+        url.set_path(&format!("{prefix}/api/{project}/store/"));
+        let auth = format!(
+            "Sentry sentry_version=7, sentry_key={key}, sentry_client=bot-ticelli/{}",
+            env!("CARGO_PKG_VERSION"),
+        );
+        Ok(Self { client: reqwest::Client::new(), url, auth })
+    }
+}
+
+#[async_trait]
+impl Sink for SentrySink {
+    async fn send(&self, report: &Report) -> SinkResult {
+        let event = json!({
+            "event_id": Uuid::new_v4().simple().to_string(),
+            "timestamp": report.timestamp.to_rfc3339(),
+            "level": "error",
+            "platform": "other",
+            "logger": "bot-ticelli",
+            "release": concat!("bot-ticelli@", env!("CARGO_PKG_VERSION")),
+            "exception": { "values": [{ "type": report.kind, "value": report.message }] },
+            "tags": {
+                "command": report.command,
+                "guild_id": report.guild_id.map(|id| id.to_string()),
+                "channel_id": report.channel_id.map(|id| id.to_string()),
+                "span": report.span,
+            },
+            "user": { "id": report.user_id.map(|id| id.to_string()) },
+            "extra": { "suppressed": report.suppressed },
+        });
+        self.client
+            .post(self.url.clone())
+            .header("X-Sentry-Auth", &self.auth)
+            .json(&event)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+struct Reporter {
+    sinks: Vec<Box<dyn Sink>>,
+    dedup_window: Duration,
+    rate_limit: u32,
+    state: Mutex<State>,
+}
+
+struct State {
+    /// Last report time of each error, and how many times it was dropped since
+    seen: HashMap<String, (Instant, u64)>,
+    window_start: Instant,
+    window_count: u32,
+}
+
+impl Reporter {
+    /// Whether to report an error now, returning how many times it was dropped since last time.
+    fn admit(&self, fingerprint: String) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        if let Some((at, suppressed)) = state.seen.get_mut(&fingerprint)
+            && now.duration_since(*at) < self.dedup_window
+        {
+            *suppressed += 1;
+            return None;
+        }
+
+        if now.duration_since(state.window_start) >= Duration::from_secs(60) {
+            state.window_start = now;
+            state.window_count = 0;
+        }
+        if state.window_count >= self.rate_limit {
+            return None;
+        }
+        state.window_count += 1;
+
+        let suppressed = state.seen.remove(&fingerprint).map_or(0, |(_, suppressed)| suppressed);
+        let window = self.dedup_window;
+        state.seen.retain(|_, (at, _)| now.duration_since(*at) < window);
+        state.seen.insert(fingerprint, (now, 0));
+        Some(suppressed)
+    }
+}
+
+/// Install the error reporter. Errors are only logged until this is called.
+pub fn init(config: &ErrorReportingConfig) -> Result<(), Box<dyn StdError>> {
+    let mut sinks: Vec<Box<dyn Sink>> = vec![];
+    if let Some(dsn) = &config.sentry_dsn {
+        sinks.push(Box::new(SentrySink::new(dsn)?));
+    }
+    if let Some(path) = &config.file {
+        sinks.push(Box::new(FileSink { path: path.clone() }));
+    }
+
+    let reporter = Reporter {
+        sinks,
+        dedup_window: Duration::from_secs(config.dedup_window),
+        rate_limit: config.rate_limit,
+        state: Mutex::new(State {
+            seen: HashMap::new(),
+            window_start: Instant::now(),
+            window_count: 0,
+        }),
+    };
+    if REPORTER.set(reporter).is_err() {
+        return Err("Error reporter already installed".into());
+    }
+    info!("Installed error reporting");
+    Ok(())
+}
+
+/// Report an internal error in the background. Errors meant for the players are ignored.
+pub fn capture(error: &Error, context: Context) {
+    let Some(reporter) = REPORTER.get() else { return };
+    if !error.is_internal() {
+        return;
+    }
+
+    let message = error.to_string();
+    let fingerprint = format!("{}:{:?}:{message}", error.kind(), context.command);
+    let Some(suppressed) = reporter.admit(fingerprint) else { return };
+
+    let report = Report {
+        timestamp: Utc::now(),
+        kind: error.kind(),
+        message,
+        command: context.command,
+        guild_id: context.guild_id.map(|id| id.0),
+        channel_id: context.channel_id.map(|id| id.0),
+        user_id: context.user_id.map(|id| id.0),
+        span: Span::current().metadata().map(|meta| meta.name().to_owned()),
+        suppressed,
+    };
+    tokio::spawn(async move {
+        for sink in &reporter.sinks {
+            if let Err(e) = sink.send(&report).await {
+                warn!("Failed to report error: {e}");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reporter(dedup_window: u64, rate_limit: u32) -> Reporter {
+        Reporter {
+            sinks: vec![],
+            dedup_window: Duration::from_secs(dedup_window),
+            rate_limit,
+            state: Mutex::new(State {
+                seen: HashMap::new(),
+                window_start: Instant::now(),
+                window_count: 0,
+            }),
+        }
+    }
+
+    /// Pretend the last report of `fingerprint` happened `secs` seconds ago.
+    fn age(reporter: &Reporter, fingerprint: &str, secs: u64) {
+        let mut state = reporter.state.lock().unwrap();
+        let (at, _) = state.seen.get_mut(fingerprint).unwrap();
+        *at -= Duration::from_secs(secs);
+    }
+
+    #[test]
+    fn repeats_are_dropped_inside_dedup_window() {
+        let reporter = reporter(300, 100);
+        assert_eq!(reporter.admit("db".into()), Some(0));
+        assert_eq!(reporter.admit("db".into()), None);
+        assert_eq!(reporter.admit("db".into()), None);
+        // Other errors are not held back
+        assert_eq!(reporter.admit("pool".into()), Some(0));
+
+        age(&reporter, "db", 300);
+        assert_eq!(reporter.admit("db".into()), Some(2));
+        assert_eq!(reporter.admit("db".into()), None);
+    }
+
+    #[test]
+    fn rate_is_capped_per_minute() {
+        let reporter = reporter(300, 2);
+        assert_eq!(reporter.admit("a".into()), Some(0));
+        assert_eq!(reporter.admit("b".into()), Some(0));
+        assert_eq!(reporter.admit("c".into()), None);
+        assert_eq!(reporter.admit("d".into()), None);
+
+        reporter.state.lock().unwrap().window_start -= Duration::from_secs(60);
+        assert_eq!(reporter.admit("c".into()), Some(0));
+    }
+
+    #[test]
+    fn sentry_dsn() {
+        let sink = SentrySink::new("https://abc@sentry.example.com/42").unwrap();
+        assert_eq!(sink.url.as_str(), "https://sentry.example.com/api/42/store/");
+        assert!(sink.auth.contains("sentry_key=abc"));
+
+        let sink = SentrySink::new("https://abc@example.com/sentry/42").unwrap();
+        assert_eq!(sink.url.as_str(), "https://example.com/sentry/api/42/store/");
+
+        for dsn in ["not a dsn", "https://sentry.example.com/42", "https://abc@sentry.example.com/"]
+        {
+            assert!(SentrySink::new(dsn).is_err(), "{dsn}");
+        }
+    }
+}
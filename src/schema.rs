@@ -1,3 +1,17 @@
+table! {
+    audit_event (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        game_id -> Uuid,
+        actor_id -> Nullable<Text>,
+        action -> Text,
+        target_id -> Nullable<Text>,
+        before_id -> Nullable<Uuid>,
+        after_id -> Nullable<Uuid>,
+        details -> Nullable<Text>,
+    }
+}
+
 table! {
     game (id) {
         id -> Uuid,
@@ -5,6 +19,37 @@ table! {
         guild_id -> Text,
         channel_id -> Text,
         creator_id -> Text,
+        ended_at -> Nullable<Timestamptz>,
+        paused_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    game_settings (game_id) {
+        game_id -> Uuid,
+        autoskip_delay -> Nullable<Int4>,
+        warn_delay -> Nullable<Int4>,
+        skip_penalty -> Nullable<Int4>,
+        win_sentences -> Nullable<Array<Text>>,
+        page_size -> Nullable<Int4>,
+        season_rollover -> Nullable<Text>,
+        undo_window -> Nullable<Int4>,
+        audit_channel -> Nullable<Text>,
+        hint_penalty -> Nullable<Int4>,
+        scoring -> Nullable<Text>,
+        speed_brackets -> Nullable<Text>,
+        poster_brackets -> Nullable<Text>,
+        poster_points -> Nullable<Int4>,
+        duplicate_pictures -> Nullable<Text>,
+        reveal_location -> Nullable<Bool>,
+        scoreboard_image -> Nullable<Bool>,
+    }
+}
+
+table! {
+    guild_settings (guild_id) {
+        guild_id -> Text,
+        locale -> Nullable<Text>,
     }
 }
 
@@ -22,6 +67,37 @@ table! {
         game_id -> Uuid,
         warned_at -> Nullable<Timestamptz>,
         updated_at -> Timestamptz,
+        is_auto_skip -> Bool,
+        hints -> Array<Text>,
+        picture_id -> Nullable<Uuid>,
+        latitude -> Nullable<Float8>,
+        longitude -> Nullable<Float8>,
+        resumed_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    picture (id) {
+        id -> Uuid,
+        created_at -> Timestamptz,
+        url -> Text,
+        storage_key -> Text,
+        hash -> Text,
+        size -> Int4,
+        width -> Int4,
+        height -> Int4,
+        mime_type -> Text,
+        phash -> Nullable<Int8>,
+    }
+}
+
+table! {
+    season (id) {
+        id -> Uuid,
+        game_id -> Uuid,
+        name -> Text,
+        started_at -> Timestamptz,
+        ended_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -35,10 +111,24 @@ table! {
         reset_at -> Nullable<Timestamptz>,
         reset_id -> Nullable<Uuid>,
         score -> Int4,
+        poster_score -> Int4,
     }
 }
 
+joinable!(audit_event -> game (game_id));
+joinable!(game_settings -> game (game_id));
 joinable!(participation -> game (game_id));
+joinable!(participation -> picture (picture_id));
 joinable!(participation -> win (win_id));
+joinable!(season -> game (game_id));
 
-allow_tables_to_appear_in_same_query!(game, participation, win,);
+allow_tables_to_appear_in_same_query!(
+    audit_event,
+    game,
+    game_settings,
+    guild_settings,
+    participation,
+    picture,
+    season,
+    win,
+);
@@ -0,0 +1,301 @@
+//! Scores, stored in hundredths of a point so that hints can take away part of a point
+
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+use chrono::TimeDelta;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    i18n::Locale,
+    settings::Settings,
+};
+
+/// Stored value of a single point.
+pub const POINT: i32 = 100;
+
+/// Separator of the brackets when set from a command.
+const BRACKET_SEPARATOR: char = '|';
+
+/// Human readable score, like `1`, `0,5` or `-0,25` in French.
+pub fn format(score: i64, locale: Locale) -> String {
+    format_with(score, locale.decimal_separator())
+}
+
+/// Number of points of a score, to pick the plural form of messages.
+pub fn count(score: i64) -> f64 {
+    score as f64 / f64::from(POINT)
+}
+
+fn format_with(score: i64, separator: char) -> String {
+    let point = i64::from(POINT);
+    let sign = if score < 0 { "-" } else { "" };
+    let (units, cents) = (score.abs() / point, score.abs() % point);
+    match cents {
+        0 => format!("{sign}{units}"),
+        cents if cents % 10 == 0 => format!("{sign}{units}{separator}{}", cents / 10),
+        cents => format!("{sign}{units}{separator}{cents:02}"),
+    }
+}
+
+/// Parse a human readable score with at most two decimals, either `0,5` or `0.5`.
+pub fn parse(s: &str) -> Option<i32> {
+    let s = s.trim().replace(',', ".");
+    let (units, decimals) = s.split_once('.').unwrap_or((&s, ""));
+    if decimals.len() > 2 || !decimals.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let units = units.parse::<i32>().ok()?;
+    let cents = format!("{decimals:0<2}").parse::<i32>().ok()?;
+    let score = units.checked_abs()?.checked_mul(POINT)?.checked_add(cents)?;
+    Some(if s.starts_with('-') { -score } else { score })
+}
+
+/// How the finder of a picture is rewarded.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scoring {
+    /// A single point per picture
+    Fixed,
+    /// Points depending on how fast the picture was found, and a bonus for the poster of the
+    /// pictures that resisted long
+    Speed,
+}
+
+impl FromStr for Scoring {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fixed" => Ok(Self::Fixed),
+            "speed" => Ok(Self::Speed),
+            _ => Err(Error::InvalidSettingValue),
+        }
+    }
+}
+
+impl Display for Scoring {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Fixed => write!(f, "fixed"),
+            Self::Speed => write!(f, "speed"),
+        }
+    }
+}
+
+/// Points depending on a delay, as `(delay, points)` sorted by delay. Written `300:3|1800:2`, with
+/// delays in seconds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Brackets(pub Vec<(TimeDelta, i32)>);
+
+impl Brackets {
+    /// Points of the first bracket not over after `delay`, for finding a picture fast.
+    pub fn within(&self, delay: TimeDelta) -> Option<i32> {
+        self.0.iter().find(|(max, _)| delay <= *max).map(|(_, points)| *points)
+    }
+
+    /// Points of the last bracket over after `delay`, for a picture resisting long.
+    pub fn after(&self, delay: TimeDelta) -> Option<i32> {
+        self.0.iter().rev().find(|(min, _)| delay >= *min).map(|(_, points)| *points)
+    }
+}
+
+impl FromStr for Brackets {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let brackets = s
+            .split(BRACKET_SEPARATOR)
+            .map(|bracket| {
+                let (delay, points) = bracket.trim().split_once(':')?;
+                let delay = delay.trim().parse::<u32>().ok()?;
+                let points = parse(points).filter(|points| *points >= 0)?;
+                Some((TimeDelta::seconds(delay.into()), points))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::InvalidSettingValue)?;
+        Ok(Self(brackets.into_iter().sorted_by_key(|(delay, _)| *delay).collect()))
+    }
+}
+
+impl Display for Brackets {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let brackets = self
+            .0
+            .iter()
+            .map(|(delay, points)| format!("{}:{}", delay.num_seconds(), stored(*points)));
+        write!(f, "{}", brackets.format(&BRACKET_SEPARATOR.to_string()))
+    }
+}
+
+/// Score as stored in the settings, whatever the locale of the guild.
+fn stored(points: i32) -> String {
+    format_with(points.into(), ',')
+}
+
+/// Points of the finder and of the poster of a picture found after `delay` and `hints` hints.
+///
+/// Finds slower than all the speed brackets are worth a single point. The poster always gets the
+/// `poster-points`, plus the bonus of the poster brackets in speed scoring.
+pub fn win_scores(settings: &Settings, delay: TimeDelta, hints: usize) -> (i32, i32) {
+    let (base, bonus) = match settings.scoring {
+        Scoring::Fixed => (POINT, 0),
+        Scoring::Speed => (
+            settings.speed_brackets.within(delay).unwrap_or(POINT),
+            settings.poster_brackets.after(delay).unwrap_or(0),
+        ),
+    };
+    let poster = settings.poster_points.saturating_add(bonus);
+    let penalty = i32::try_from(hints).unwrap_or(i32::MAX).saturating_mul(settings.hint_penalty);
+    (base.saturating_sub(penalty).max(0), poster)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::BotConfig, models::GameSettings};
+
+    #[test]
+    fn format_trims_decimals() {
+        assert_eq!(format(0, Locale::Fr), "0");
+        assert_eq!(format(300, Locale::Fr), "3");
+        assert_eq!(format(50, Locale::Fr), "0,5");
+        assert_eq!(format(50, Locale::En), "0.5");
+        assert_eq!(format(105, Locale::En), "1.05");
+        assert_eq!(format(-25, Locale::Fr), "-0,25");
+        assert_eq!(format(-150, Locale::En), "-1.5");
+    }
+
+    #[test]
+    fn parse_reads_both_separators() {
+        assert_eq!(parse("1"), Some(100));
+        assert_eq!(parse(" 2 "), Some(200));
+        assert_eq!(parse("0,5"), Some(50));
+        assert_eq!(parse("0.5"), Some(50));
+        assert_eq!(parse("1.05"), Some(105));
+        assert_eq!(parse("1."), Some(100));
+        assert_eq!(parse("+1"), Some(100));
+    }
+
+    #[test]
+    fn parse_keeps_the_sign_of_fractions() {
+        assert_eq!(parse("-0,5"), Some(-50));
+        assert_eq!(parse("-0.05"), Some(-5));
+        assert_eq!(parse("-1,25"), Some(-125));
+        assert_eq!(parse("-0"), Some(0));
+    }
+
+    #[test]
+    fn parse_refuses_rounding() {
+        // Hundredths are the smallest unit, anything smaller would have to be rounded
+        assert_eq!(parse("0.555"), None);
+        assert_eq!(parse("0,001"), None);
+        assert_eq!(parse("1.2.3"), None);
+        assert_eq!(parse("1.-5"), None);
+        assert_eq!(parse(".5"), None);
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("un"), None);
+        assert_eq!(parse("21474837"), None);
+    }
+
+    #[test]
+    fn format_and_parse_round_trip() {
+        for score in [0, 1, 10, 99, 100, 101, 250, -5, -50, -1234] {
+            for locale in Locale::ALL {
+                assert_eq!(parse(&format(score.into(), *locale)), Some(score), "{score} {locale}");
+            }
+        }
+    }
+
+    fn settings(game: GameSettings) -> Settings {
+        let config = BotConfig {
+            command_prefix: "!".to_owned(),
+            win_sentences: vec![],
+            ratelimit: None,
+            auto_skip: None,
+            season_rollover: None,
+            locale: Locale::default(),
+        };
+        Settings::new(&config, Some(&game))
+    }
+
+    fn speed(poster_points: i32) -> Settings {
+        settings(GameSettings {
+            scoring: Some("speed".to_owned()),
+            poster_points: Some(poster_points),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn brackets_parse_sorted() {
+        let brackets = "1800:2 | 300:0,5".parse::<Brackets>().unwrap();
+        assert_eq!(
+            brackets,
+            Brackets(vec![(TimeDelta::minutes(5), 50), (TimeDelta::minutes(30), 200)])
+        );
+        assert_eq!(brackets.to_string(), "300:0,5|1800:2");
+        assert_eq!(brackets.to_string().parse::<Brackets>().unwrap(), brackets);
+    }
+
+    #[test]
+    fn brackets_refuse_garbage() {
+        for brackets in ["", "300", "300:", "-300:1", "300:-1", "five:1", "300:1|", "300:0.001"] {
+            assert!(brackets.parse::<Brackets>().is_err(), "{brackets:?}");
+        }
+    }
+
+    #[test]
+    fn brackets_bounds() {
+        let brackets = "300:3|1800:2".parse::<Brackets>().unwrap();
+        assert_eq!(brackets.within(TimeDelta::zero()), Some(300));
+        assert_eq!(brackets.within(TimeDelta::minutes(5)), Some(300));
+        assert_eq!(brackets.within(TimeDelta::seconds(301)), Some(200));
+        assert_eq!(brackets.within(TimeDelta::seconds(1801)), None);
+
+        assert_eq!(brackets.after(TimeDelta::seconds(299)), None);
+        assert_eq!(brackets.after(TimeDelta::minutes(5)), Some(300));
+        assert_eq!(brackets.after(TimeDelta::hours(1)), Some(200));
+    }
+
+    #[test]
+    fn fixed_scoring_ignores_time() {
+        let settings = settings(GameSettings::default());
+        assert_eq!(win_scores(&settings, TimeDelta::zero(), 0), (POINT, 0));
+        assert_eq!(win_scores(&settings, TimeDelta::days(3), 0), (POINT, 0));
+    }
+
+    #[test]
+    fn hints_lower_the_finder_points_down_to_zero() {
+        let settings = settings(GameSettings::default());
+        assert_eq!(win_scores(&settings, TimeDelta::zero(), 1), (50, 0));
+        assert_eq!(win_scores(&settings, TimeDelta::zero(), 2), (0, 0));
+        assert_eq!(win_scores(&settings, TimeDelta::zero(), 5), (0, 0));
+        assert_eq!(win_scores(&settings, TimeDelta::zero(), usize::MAX), (0, 0));
+        assert_eq!(win_scores(&speed(0), TimeDelta::zero(), 1), (250, 0));
+    }
+
+    #[test]
+    fn speed_scoring_rewards_fast_finds_and_hard_pictures() {
+        let settings = speed(0);
+        assert_eq!(win_scores(&settings, TimeDelta::minutes(1), 0), (300, 0));
+        assert_eq!(win_scores(&settings, TimeDelta::minutes(20), 0), (200, 0));
+        // Slower than all the brackets
+        assert_eq!(win_scores(&settings, TimeDelta::minutes(45), 0), (POINT, 0));
+        assert_eq!(win_scores(&settings, TimeDelta::hours(2), 0), (POINT, 100));
+        assert_eq!(win_scores(&settings, TimeDelta::days(2), 0), (POINT, 200));
+    }
+
+    #[test]
+    fn poster_points_add_to_the_bonus() {
+        assert_eq!(win_scores(&speed(50), TimeDelta::minutes(1), 0), (300, 50));
+        assert_eq!(win_scores(&speed(50), TimeDelta::days(2), 0), (POINT, 250));
+        let fixed = settings(GameSettings { poster_points: Some(50), ..Default::default() });
+        assert_eq!(win_scores(&fixed, TimeDelta::days(2), 0), (POINT, 50));
+    }
+}
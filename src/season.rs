@@ -0,0 +1,137 @@
+//! Seasons, periodically freezing the scoreboard of a game
+
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::utils::Colour;
+use tracing::info;
+
+use crate::{
+    audit::{self, Action, Event},
+    cmd::player::scoreboard_page,
+    engine::Env,
+    error::{Error, Result},
+    i18n::tr,
+    models::*,
+    store::GameStore,
+    transport::{Embed, Reply},
+};
+
+/// How often seasons roll over.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rollover {
+    Monthly,
+    Quarterly,
+}
+
+impl Rollover {
+    fn months(self) -> u32 {
+        match self {
+            Self::Monthly => 1,
+            Self::Quarterly => 3,
+        }
+    }
+
+    /// Start of the season `at` is in.
+    pub fn period_start(self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let month = at.month0() / self.months() * self.months() + 1;
+        Utc.with_ymd_and_hms(at.year(), month, 1, 0, 0, 0).unwrap()
+    }
+
+    /// Name of the season `at` is in, like `2026-10` or `2026-T4`.
+    pub fn period_name(self, at: DateTime<Utc>) -> String {
+        match self {
+            Self::Monthly => format!("{}-{:02}", at.year(), at.month()),
+            Self::Quarterly => format!("{}-T{}", at.year(), at.month0() / 3 + 1),
+        }
+    }
+}
+
+impl FromStr for Rollover {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "monthly" => Ok(Self::Monthly),
+            "quarterly" => Ok(Self::Quarterly),
+            _ => Err(Error::InvalidSettingValue),
+        }
+    }
+}
+
+impl Display for Rollover {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Monthly => write!(f, "monthly"),
+            Self::Quarterly => write!(f, "quarterly"),
+        }
+    }
+}
+
+/// End the current season of a game if it is over, and start the next one.
+///
+/// Returns the announcements to send to the game channel.
+pub async fn rollover(env: &Env, store: &mut dyn GameStore, game: &Game) -> Result<Vec<Reply>> {
+    let settings = env.settings(store, game).await?;
+    let Some(rollover) = settings.season_rollover else { return Ok(vec![]) };
+    let now = Utc::now();
+
+    let seasons = store.seasons(game).await?;
+    let mut replies = vec![];
+    let ended = match seasons.iter().find(|s| s.ended_at.is_none()) {
+        Some(season) if season.started_at >= rollover.period_start(now) => return Ok(replies),
+        Some(season) => {
+            store.reset(game, season.id).await?;
+            let season = store.end_season(season).await?;
+            info!("Ended season {season:?}");
+            replies.push(podium(env, store, game, &season).await?);
+            Some(season)
+        }
+        None => None,
+    };
+
+    // Seasons restarted within the same period get a suffix, as names are unique
+    let base = rollover.period_name(now);
+    let name = (1..)
+        .map(|i| if i == 1 { base.clone() } else { format!("{base} ({i})") })
+        .find(|name| seasons.iter().all(|s| s.name != *name))
+        .unwrap();
+    let season = store.create_season(NewSeason { game_id: &game.id, name: &name }).await?;
+    info!("Started season {season:?}");
+    if let Some(ended) = ended {
+        let event = Event::new(Action::SeasonEnd)
+            .before(ended.id)
+            .after(season.id)
+            .details(format!("{} → {}", ended.name, season.name));
+        audit::record(env, store, game, event).await?;
+    }
+    replies.push(Reply::Text(tr!(env.locale, "season-start", name = season.name.as_str())));
+
+    Ok(replies)
+}
+
+/// Podium of an ended season.
+async fn podium(
+    env: &Env,
+    store: &mut dyn GameStore,
+    game: &Game,
+    season: &Season,
+) -> Result<Reply> {
+    let wins = store.scoreboard(game, Some(season.id)).await?;
+    if wins.is_empty() {
+        return Ok(Reply::Text(tr!(env.locale, "season-end-empty", name = season.name.as_str())));
+    }
+
+    let (board, _) = scoreboard_page(env, game.guild(), wins, 1, 3).await?;
+    Ok(Reply::Embed(Embed {
+        title: Some(tr!(env.locale, "season-end", name = season.name.as_str())),
+        colour: Some(Colour::GOLD),
+        fields: board,
+        ..Default::default()
+    }))
+}
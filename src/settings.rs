@@ -0,0 +1,470 @@
+//! Per-game settings, falling back to the config file when unset
+
+use std::{fmt::Write, ops::RangeInclusive};
+
+use chrono::TimeDelta;
+use serenity::model::{id::ChannelId, mention::Mentionable};
+
+use crate::{
+    config::BotConfig,
+    error::{Error, Result},
+    i18n::Locale,
+    models::GameSettings,
+    pictures::DuplicatePolicy,
+    score::{self, Brackets, POINT, Scoring},
+    season::Rollover,
+};
+
+/// Names of the settings, as used by `!config`.
+pub const KEYS: &[&str] = &[
+    "autoskip-delay",
+    "warn-delay",
+    "skip-penalty",
+    "win-sentences",
+    "page-size",
+    "season-rollover",
+    "undo-window",
+    "audit-channel",
+    "hint-penalty",
+    "scoring",
+    "speed-brackets",
+    "poster-brackets",
+    "poster-points",
+    "duplicate-pictures",
+    "reveal-location",
+    "scoreboard-image",
+];
+
+/// Value of `season-rollover` disabling seasons for a game, and of `duplicate-pictures` disabling
+/// the detection.
+const NONE: &str = "none";
+
+/// Points of the finder in speed scoring: 3 within 5 minutes, 2 within 30 minutes.
+const SPEED_BRACKETS: &str = "300:3|1800:2";
+
+/// Points of the poster in speed scoring: 1 after an hour, 2 after a day.
+const POSTER_BRACKETS: &str = "3600:1|86400:2";
+
+/// Values of `reveal-location` and `scoreboard-image`.
+const ON: &str = "on";
+const OFF: &str = "off";
+
+/// Separator of the win sentences when set from a command.
+const SENTENCE_SEPARATOR: char = '|';
+
+/// Discord embeds can't have more than 25 fields
+const PAGE_SIZES: RangeInclusive<i32> = 1..=25;
+
+/// Effective settings of a game.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// Delay for auto-skipping pictures, disabled if `None`.
+    pub autoskip_delay: Option<TimeDelta>,
+    /// Delay before warning the picture will be auto-skipped.
+    pub warn_delay: TimeDelta,
+    /// Points lost when skipping one's turn, in hundredths of a point.
+    pub skip_penalty: i32,
+    /// Sentences to use on win
+    pub win_sentences: Vec<String>,
+    /// Scoreboard entries per page
+    pub page_size: usize,
+    /// Automatic season rollover, disabled if `None`.
+    pub season_rollover: Option<Rollover>,
+    /// Delay during which a player can undo their own win or skip
+    pub undo_window: TimeDelta,
+    /// Moderation channel mirroring the audit log
+    pub audit_channel: Option<ChannelId>,
+    /// Points taken from the finder for each hint, in hundredths of a point
+    pub hint_penalty: i32,
+    /// How the finder of a picture is rewarded
+    pub scoring: Scoring,
+    /// Points of the finder depending on how fast the picture was found, in speed scoring
+    pub speed_brackets: Brackets,
+    /// Points of the poster depending on how long the picture resisted, in speed scoring
+    pub poster_brackets: Brackets,
+    /// Points of the poster of a found picture, in hundredths of a point
+    pub poster_points: i32,
+    /// What to do with pictures played before, not looking for them if `None`
+    pub duplicate_pictures: Option<DuplicatePolicy>,
+    /// Whether to tell where a found picture was taken, when its EXIF tags say so
+    pub reveal_location: bool,
+    /// Whether to draw the scoreboard as a picture rather than embed fields
+    pub scoreboard_image: bool,
+}
+
+impl Settings {
+    pub fn new(config: &BotConfig, game: Option<&GameSettings>) -> Self {
+        let game = game.cloned().unwrap_or_default();
+        let autoskip = config.auto_skip.as_ref();
+
+        let autoskip_delay = game
+            .autoskip_delay
+            .map(i64::from)
+            .or(autoskip.map(|a| a.autoskip_delay.into()))
+            .filter(|delay| *delay > 0)
+            .map(TimeDelta::seconds);
+        let warn_delay = game
+            .warn_delay
+            .map(i64::from)
+            .or(autoskip.map(|a| a.warn_delay.into()))
+            .map(TimeDelta::seconds)
+            .unwrap_or_default();
+
+        Self {
+            autoskip_delay,
+            warn_delay,
+            skip_penalty: game.skip_penalty.unwrap_or(POINT),
+            win_sentences: game.win_sentences.unwrap_or_else(|| config.win_sentences.clone()),
+            page_size: game
+                .page_size
+                .map(|size| size.clamp(*PAGE_SIZES.start(), *PAGE_SIZES.end()) as usize)
+                .unwrap_or(10),
+            season_rollover: match game.season_rollover {
+                // Anything else than a rollover is `NONE`
+                Some(rollover) => rollover.parse().ok(),
+                None => config.season_rollover,
+            },
+            undo_window: TimeDelta::seconds(game.undo_window.unwrap_or(300).into()),
+            audit_channel: game.audit_channel.and_then(|id| id.parse().ok()).map(ChannelId),
+            hint_penalty: game.hint_penalty.unwrap_or(POINT / 2),
+            scoring: game.scoring.and_then(|s| s.parse().ok()).unwrap_or(Scoring::Fixed),
+            speed_brackets: brackets(game.speed_brackets, SPEED_BRACKETS),
+            poster_brackets: brackets(game.poster_brackets, POSTER_BRACKETS),
+            poster_points: game.poster_points.unwrap_or(0),
+            duplicate_pictures: match game.duplicate_pictures {
+                // Anything else than a policy is `NONE`
+                Some(policy) => policy.parse().ok(),
+                None => Some(DuplicatePolicy::Warn),
+            },
+            reveal_location: game.reveal_location.unwrap_or(false),
+            scoreboard_image: game.scoreboard_image.unwrap_or(false),
+        }
+    }
+
+    /// Human readable value of a setting.
+    pub fn get(&self, key: &str, locale: Locale) -> Result<String> {
+        let disabled = || locale.tr("settings-disabled", &[]);
+        Ok(match key {
+            "autoskip-delay" => match self.autoskip_delay {
+                Some(delay) => delay.num_seconds().to_string(),
+                None => disabled(),
+            },
+            "warn-delay" => self.warn_delay.num_seconds().to_string(),
+            "skip-penalty" => score::format(self.skip_penalty.into(), locale),
+            "win-sentences" => self.win_sentences.join(&SENTENCE_SEPARATOR.to_string()),
+            "page-size" => self.page_size.to_string(),
+            "season-rollover" => match self.season_rollover {
+                Some(rollover) => rollover.to_string(),
+                None => disabled(),
+            },
+            "undo-window" => self.undo_window.num_seconds().to_string(),
+            "audit-channel" => match self.audit_channel {
+                Some(channel) => channel.mention().to_string(),
+                None => disabled(),
+            },
+            "hint-penalty" => score::format(self.hint_penalty.into(), locale),
+            "scoring" => self.scoring.to_string(),
+            "speed-brackets" => self.speed_brackets.to_string(),
+            "poster-brackets" => self.poster_brackets.to_string(),
+            "poster-points" => score::format(self.poster_points.into(), locale),
+            "duplicate-pictures" => match self.duplicate_pictures {
+                Some(policy) => policy.to_string(),
+                None => disabled(),
+            },
+            "reveal-location" => if self.reveal_location { ON } else { OFF }.to_owned(),
+            "scoreboard-image" => if self.scoreboard_image { ON } else { OFF }.to_owned(),
+            _ => return Err(Error::UnknownSetting),
+        })
+    }
+
+    /// Human readable values of all settings, `*` marking the ones set for this game.
+    pub fn describe(&self, game: Option<&GameSettings>, locale: Locale) -> String {
+        let mut out = String::new();
+        for key in KEYS {
+            let set = game.is_some_and(|game| is_set(game, key));
+            let value = self.get(key, locale).unwrap_or_default();
+            let _ = writeln!(out, "`{key}`{}: {value}", if set { "*" } else { "" });
+        }
+        out
+    }
+}
+
+fn brackets(game: Option<String>, default: &str) -> Brackets {
+    game.and_then(|b| b.parse().ok()).unwrap_or_else(|| default.parse().unwrap())
+}
+
+fn is_set(game: &GameSettings, key: &str) -> bool {
+    stored(game, key).is_some()
+}
+
+/// Value of a setting set for a game, the way [`set`] takes it.
+fn stored(game: &GameSettings, key: &str) -> Option<String> {
+    let points = |points: Option<i32>| points.map(|p| score::format(p.into(), Locale::default()));
+    let switch = |switch: Option<bool>| switch.map(|on| if on { ON } else { OFF }.to_owned());
+    match key {
+        "autoskip-delay" => game.autoskip_delay.map(|d| d.to_string()),
+        "warn-delay" => game.warn_delay.map(|d| d.to_string()),
+        "skip-penalty" => points(game.skip_penalty),
+        "win-sentences" => {
+            game.win_sentences.as_ref().map(|s| s.join(&SENTENCE_SEPARATOR.to_string()))
+        }
+        "page-size" => game.page_size.map(|size| size.to_string()),
+        "season-rollover" => game.season_rollover.clone(),
+        "undo-window" => game.undo_window.map(|w| w.to_string()),
+        "audit-channel" => game.audit_channel.clone(),
+        "hint-penalty" => points(game.hint_penalty),
+        "scoring" => game.scoring.clone(),
+        "speed-brackets" => game.speed_brackets.clone(),
+        "poster-brackets" => game.poster_brackets.clone(),
+        "poster-points" => points(game.poster_points),
+        "duplicate-pictures" => game.duplicate_pictures.clone(),
+        "reveal-location" => switch(game.reveal_location),
+        "scoreboard-image" => switch(game.scoreboard_image),
+        _ => None,
+    }
+}
+
+/// Settings of a game that [`set`] would refuse, like the ones of a hand-edited export.
+pub fn invalid(game: &GameSettings) -> Vec<&'static str> {
+    KEYS.iter()
+        .copied()
+        .filter(|key| {
+            let value = stored(game, key);
+            set(&mut GameSettings::default(), key, value.as_deref()).is_err()
+        })
+        .collect()
+}
+
+/// Set a setting of a game from its human readable value, or unset it if `value` is `None`.
+pub fn set(game: &mut GameSettings, key: &str, value: Option<&str>) -> Result<()> {
+    let int = |range: RangeInclusive<i32>| -> Result<Option<i32>> {
+        value
+            .map(|v| v.parse().ok().filter(|v| range.contains(v)).ok_or(Error::InvalidSettingValue))
+            .transpose()
+    };
+    let points = || -> Result<Option<i32>> {
+        value
+            .map(|v| score::parse(v).filter(|v| *v >= 0).ok_or(Error::InvalidSettingValue))
+            .transpose()
+    };
+    let switch = || -> Result<Option<bool>> {
+        value
+            .map(|v| match v {
+                ON => Ok(true),
+                OFF => Ok(false),
+                _ => Err(Error::InvalidSettingValue),
+            })
+            .transpose()
+    };
+    match key {
+        "autoskip-delay" => game.autoskip_delay = int(0..=i32::MAX)?,
+        "warn-delay" => game.warn_delay = int(0..=i32::MAX)?,
+        "skip-penalty" => game.skip_penalty = points()?,
+        "win-sentences" => {
+            let sentences = value.map(|v| {
+                v.split(SENTENCE_SEPARATOR).map(|s| s.trim().to_owned()).collect::<Vec<_>>()
+            });
+            if sentences.as_ref().is_some_and(|s| s.iter().any(|s| !s.contains("{}"))) {
+                return Err(Error::InvalidSettingValue);
+            }
+            game.win_sentences = sentences;
+        }
+        "page-size" => game.page_size = int(PAGE_SIZES)?,
+        "season-rollover" => {
+            if let Some(value) = value
+                && value != NONE
+            {
+                value.parse::<Rollover>()?;
+            }
+            game.season_rollover = value.map(str::to_owned);
+        }
+        "undo-window" => game.undo_window = int(0..=i32::MAX)?,
+        // Either a channel mention or its ID
+        "audit-channel" => {
+            game.audit_channel = value
+                .map(|v| {
+                    let id = v.trim_start_matches("<#").trim_end_matches('>');
+                    id.parse::<u64>()
+                        .map(|id| id.to_string())
+                        .map_err(|_| Error::InvalidSettingValue)
+                })
+                .transpose()?
+        }
+        "hint-penalty" => game.hint_penalty = points()?,
+        "scoring" => {
+            game.scoring = value.map(|v| v.parse::<Scoring>()).transpose()?.map(|s| s.to_string())
+        }
+        // Normalized, so that they show up sorted
+        "speed-brackets" => {
+            game.speed_brackets =
+                value.map(|v| v.parse::<Brackets>()).transpose()?.map(|b| b.to_string())
+        }
+        "poster-brackets" => {
+            game.poster_brackets =
+                value.map(|v| v.parse::<Brackets>()).transpose()?.map(|b| b.to_string())
+        }
+        "poster-points" => game.poster_points = points()?,
+        "duplicate-pictures" => {
+            if let Some(value) = value
+                && value != NONE
+            {
+                value.parse::<DuplicatePolicy>()?;
+            }
+            game.duplicate_pictures = value.map(str::to_owned);
+        }
+        "reveal-location" => game.reveal_location = switch()?,
+        "scoreboard-image" => game.scoreboard_image = switch()?,
+        _ => return Err(Error::UnknownSetting),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AutoskipConfig;
+
+    fn config() -> BotConfig {
+        BotConfig {
+            command_prefix: "!".to_owned(),
+            win_sentences: vec!["Bravo {} !".to_owned()],
+            ratelimit: None,
+            auto_skip: Some(AutoskipConfig { autoskip_delay: 3600, warn_delay: 600 }),
+            season_rollover: Some(Rollover::Monthly),
+            locale: Locale::default(),
+        }
+    }
+
+    /// Value of a setting once set for a game, as shown by `!config`.
+    fn shown(key: &str, value: Option<&str>) -> Result<String> {
+        let mut game = GameSettings::default();
+        set(&mut game, key, value)?;
+        Settings::new(&config(), Some(&game)).get(key, Locale::En)
+    }
+
+    #[test]
+    fn unset_falls_back_to_config() {
+        let settings = Settings::new(&config(), None);
+        assert_eq!(settings.autoskip_delay, Some(TimeDelta::hours(1)));
+        assert_eq!(settings.warn_delay, TimeDelta::minutes(10));
+        assert_eq!(settings.win_sentences, ["Bravo {} !"]);
+        assert_eq!(settings.season_rollover, Some(Rollover::Monthly));
+
+        // The others have no config, only defaults
+        assert_eq!(settings.skip_penalty, POINT);
+        assert_eq!(settings.page_size, 10);
+        assert_eq!(settings.undo_window, TimeDelta::minutes(5));
+        assert_eq!(settings.audit_channel, None);
+        assert_eq!(settings.hint_penalty, POINT / 2);
+        assert_eq!(settings.scoring, Scoring::Fixed);
+        assert_eq!(settings.speed_brackets.to_string(), SPEED_BRACKETS);
+        assert_eq!(settings.poster_brackets.to_string(), POSTER_BRACKETS);
+        assert_eq!(settings.poster_points, 0);
+        assert_eq!(settings.duplicate_pictures, Some(DuplicatePolicy::Warn));
+        assert!(!settings.reveal_location && !settings.scoreboard_image);
+
+        let mut config = config();
+        config.auto_skip = None;
+        config.season_rollover = None;
+        let settings = Settings::new(&config, Some(&GameSettings::default()));
+        assert_eq!(settings.autoskip_delay, None);
+        assert_eq!(settings.warn_delay, TimeDelta::zero());
+        assert_eq!(settings.season_rollover, None);
+    }
+
+    #[test]
+    fn set_overrides_config() {
+        assert_eq!(shown("autoskip-delay", Some("60")).unwrap(), "60");
+        assert_eq!(shown("autoskip-delay", Some("0")).unwrap(), "disabled");
+        assert_eq!(shown("skip-penalty", Some("1.5")).unwrap(), "1.5");
+        assert_eq!(shown("win-sentences", Some("GG {} | {} wins")).unwrap(), "GG {}|{} wins");
+        assert_eq!(shown("page-size", Some("25")).unwrap(), "25");
+        assert_eq!(shown("season-rollover", Some("none")).unwrap(), "disabled");
+        assert_eq!(shown("audit-channel", Some("<#42>")).unwrap(), "<#42>");
+        assert_eq!(shown("speed-brackets", Some("60:5|10:9")).unwrap(), "10:9|60:5");
+        assert_eq!(shown("duplicate-pictures", Some("reject")).unwrap(), "reject");
+        assert_eq!(shown("scoreboard-image", Some("on")).unwrap(), "on");
+    }
+
+    #[test]
+    fn set_rejects_invalid_values() {
+        for (key, value) in [
+            ("page-size", "0"),
+            ("page-size", "26"),
+            ("page-size", "ten"),
+            ("autoskip-delay", "-1"),
+            ("skip-penalty", "-1"),
+            ("hint-penalty", "0.001"),
+            ("win-sentences", "GG|{} wins"),
+            ("season-rollover", "weekly"),
+            ("audit-channel", "#general"),
+            ("scoring", "fastest"),
+            ("poster-brackets", "soon:1"),
+            ("duplicate-pictures", "ignore"),
+            ("reveal-location", "yes"),
+        ] {
+            let mut game = GameSettings::default();
+            let res = set(&mut game, key, Some(value));
+            assert!(matches!(res, Err(Error::InvalidSettingValue)), "{key} {value}: {res:?}");
+            assert!(!is_set(&game, key), "{key} {value}");
+        }
+        assert!(matches!(shown("color", Some("red")), Err(Error::UnknownSetting)));
+    }
+
+    #[test]
+    fn stored_values_are_checked_again() {
+        let mut game = GameSettings::default();
+        for (key, value) in [
+            ("skip-penalty", "0.25"),
+            ("win-sentences", "GG {}|{} wins"),
+            ("audit-channel", "42"),
+            ("speed-brackets", "60:5"),
+            ("season-rollover", "none"),
+            ("reveal-location", "on"),
+        ] {
+            set(&mut game, key, Some(value)).unwrap();
+        }
+        assert!(invalid(&game).is_empty());
+
+        // Out of reach of `set`
+        let game = GameSettings {
+            page_size: Some(0),
+            warn_delay: Some(-60),
+            poster_points: Some(-100),
+            scoring: Some("fastest".to_owned()),
+            win_sentences: Some(vec!["GG".to_owned()]),
+            ..game
+        };
+        assert_eq!(
+            invalid(&game),
+            ["warn-delay", "win-sentences", "page-size", "scoring", "poster-points"]
+        );
+    }
+
+    #[test]
+    fn page_size_is_clamped() {
+        for (size, clamped) in [(0, 1), (-5, 1), (26, 25), (12, 12)] {
+            let game = GameSettings { page_size: Some(size), ..Default::default() };
+            assert_eq!(Settings::new(&config(), Some(&game)).page_size, clamped, "{size}");
+        }
+    }
+
+    #[test]
+    fn unset_restores_fallback() {
+        let mut game = GameSettings::default();
+        for (key, value) in [("autoskip-delay", "60"), ("page-size", "5"), ("scoring", "speed")] {
+            set(&mut game, key, Some(value)).unwrap();
+            assert!(is_set(&game, key));
+        }
+        for key in KEYS {
+            set(&mut game, key, None).unwrap();
+            assert!(!is_set(&game, key), "{key}");
+        }
+
+        let settings = Settings::new(&config(), Some(&game));
+        assert_eq!(settings.autoskip_delay, Some(TimeDelta::hours(1)));
+        assert_eq!(settings.page_size, 10);
+        assert_eq!(settings.scoring, Scoring::Fixed);
+        assert!(!settings.describe(Some(&game), Locale::En).contains('*'));
+    }
+}
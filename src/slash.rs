@@ -0,0 +1,490 @@
+//! Slash commands, running the same engine commands as the prefixed ones
+
+use serenity::{
+    builder::{
+        CreateApplicationCommand, CreateApplicationCommandOption, CreateApplicationCommands,
+    },
+    client::Context,
+    model::{
+        Permissions,
+        application::{
+            command::{Command, CommandOptionType},
+            interaction::{
+                Interaction, InteractionResponseType,
+                application_command::{
+                    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+                },
+            },
+        },
+        id::MessageId,
+    },
+};
+use tracing::instrument;
+
+use crate::{
+    engine,
+    error::{Error, Result},
+    extensions::ContextExt,
+    i18n::{self, Locale},
+    paging::Paged,
+    report, settings,
+    transport::{
+        self, Reply, User,
+        discord::{attachment, build_embed},
+    },
+};
+
+fn commands(c: &mut CreateApplicationCommands) -> &mut CreateApplicationCommands {
+    c.create_application_command(|c| {
+        c.name("win").describe("help-win").dm_permission(false).create_option(|o| {
+            o.named("player")
+                .describe("slash-win-player")
+                .kind(CommandOptionType::User)
+                .required(true)
+        })
+    })
+    .create_application_command(|c| c.name("skip").describe("help-skip").dm_permission(false))
+    .create_application_command(|c| {
+        c.name("show").describe("help-show").dm_permission(false).create_option(|o| {
+            o.name("page")
+                .describe("slash-show-page")
+                .kind(CommandOptionType::Integer)
+                .min_int_value(1)
+        })
+    })
+    .create_application_command(|c| {
+        c.name("reset")
+            .describe("help-reset")
+            .dm_permission(false)
+            .default_member_permissions(Permissions::ADMINISTRATOR)
+            .create_option(|o| {
+                o.name("do").describe("slash-reset-do").kind(CommandOptionType::SubCommand)
+            })
+            .create_option(|o| {
+                o.name("list")
+                    .describe("slash-reset-list")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|o| {
+                        o.name("page")
+                            .describe("slash-reset-list-page")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(1)
+                    })
+            })
+            .create_option(|o| {
+                o.name("cancel")
+                    .describe("slash-reset-cancel")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|o| {
+                        o.name("id")
+                            .describe("slash-reset-cancel-id")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                    })
+            })
+    })
+    .create_application_command(|c| c.name("pic").describe("help-pic").dm_permission(false))
+    .create_application_command(|c| c.name("change").describe("help-change").dm_permission(false))
+    .create_application_command(|c| {
+        c.name("force_skip")
+            .describe("help-force-skip")
+            .dm_permission(false)
+            .default_member_permissions(Permissions::KICK_MEMBERS)
+    })
+    .create_application_command(|c| {
+        c.name("start")
+            .describe("help-start")
+            .dm_permission(false)
+            .default_member_permissions(Permissions::ADMINISTRATOR)
+    })
+    .create_application_command(|c| {
+        c.name("force_win")
+            .describe("help-force-win")
+            .dm_permission(false)
+            .default_member_permissions(Permissions::KICK_MEMBERS)
+            .create_option(|o| {
+                o.named("player")
+                    .describe("slash-force-win-player")
+                    .kind(CommandOptionType::User)
+                    .required(true)
+            })
+    })
+    .create_application_command(|c| {
+        c.name("stop")
+            .describe("help-stop")
+            .dm_permission(false)
+            .default_member_permissions(Permissions::ADMINISTRATOR)
+    })
+    .create_application_command(|c| {
+        c.name("pause")
+            .describe("help-pause")
+            .dm_permission(false)
+            .default_member_permissions(Permissions::KICK_MEMBERS)
+    })
+    .create_application_command(|c| {
+        c.name("resume")
+            .describe("help-resume")
+            .dm_permission(false)
+            .default_member_permissions(Permissions::KICK_MEMBERS)
+    })
+    .create_application_command(|c| {
+        c.name("config")
+            .describe("help-config")
+            .dm_permission(false)
+            .default_member_permissions(Permissions::ADMINISTRATOR)
+            .create_option(|o| {
+                o.name("get")
+                    .describe("slash-config-get")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|o| setting_option(o).required(false))
+            })
+            .create_option(|o| {
+                o.name("set")
+                    .describe("slash-config-set")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(setting_option)
+                    .create_sub_option(|o| {
+                        o.named("value")
+                            .describe("slash-config-set-value")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                    })
+            })
+            .create_option(|o| {
+                o.name("unset")
+                    .describe("slash-config-unset")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(setting_option)
+            })
+    })
+    .create_application_command(|c| {
+        c.name("season").describe("help-season").dm_permission(false).create_option(|o| {
+            o.named("name").describe("slash-season-name").kind(CommandOptionType::String)
+        })
+    })
+    .create_application_command(|c| {
+        c.name("stats").describe("help-stats").dm_permission(false).create_option(|o| {
+            o.named("player").describe("slash-stats-player").kind(CommandOptionType::User)
+        })
+    })
+    .create_application_command(|c| c.name("undo").describe("help-undo").dm_permission(false))
+    .create_application_command(|c| {
+        c.name("audit")
+            .describe("help-audit")
+            .dm_permission(false)
+            .default_member_permissions(Permissions::ADMINISTRATOR)
+            .create_option(|o| {
+                o.name("page")
+                    .describe("slash-audit-page")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+            })
+    })
+    .create_application_command(|c| {
+        c.name("hint").describe("help-hint").dm_permission(false).create_option(|o| {
+            o.named("hint")
+                .describe("slash-hint-hint")
+                .kind(CommandOptionType::String)
+                .required(true)
+        })
+    })
+    .create_application_command(|c| {
+        c.name("history")
+            .describe("help-history")
+            .dm_permission(false)
+            .create_option(|o| {
+                o.named("player").describe("slash-history-player").kind(CommandOptionType::User)
+            })
+            .create_option(|o| {
+                o.name("page")
+                    .describe("slash-history-page")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+            })
+    })
+    .create_application_command(|c| {
+        c.name("locale")
+            .describe("help-locale")
+            .dm_permission(false)
+            .default_member_permissions(Permissions::ADMINISTRATOR)
+            .create_option(|o| {
+                o.named("language")
+                    .describe("slash-locale-language")
+                    .kind(CommandOptionType::String);
+                for locale in Locale::ALL {
+                    o.add_string_choice(locale, locale);
+                }
+                o
+            })
+    })
+    .create_application_command(|c| {
+        c.name("chart").describe("help-chart").dm_permission(false);
+        // Slash commands can't take a variable number of players
+        for name in ["player1", "player2", "player3"] {
+            c.create_option(|o| {
+                o.named(name).describe("slash-chart-player").kind(CommandOptionType::User)
+            });
+        }
+        c.create_option(|o| {
+            o.name("reset").describe("slash-chart-reset").kind(CommandOptionType::String)
+        })
+    })
+}
+
+/// Builders of commands and options, described in every locale from the catalogs.
+trait Describe {
+    fn describe(&mut self, id: &str) -> &mut Self;
+}
+
+impl Describe for CreateApplicationCommand {
+    fn describe(&mut self, id: &str) -> &mut Self {
+        self.description(i18n::FALLBACK.tr(id, &[]));
+        for (discord, description) in descriptions(id) {
+            self.description_localized(discord, description);
+        }
+        self
+    }
+}
+
+impl Describe for CreateApplicationCommandOption {
+    fn describe(&mut self, id: &str) -> &mut Self {
+        self.description(i18n::FALLBACK.tr(id, &[]));
+        for (discord, description) in descriptions(id) {
+            self.description_localized(discord, description);
+        }
+        self
+    }
+}
+
+/// Description for each Discord locale.
+fn descriptions(id: &str) -> impl Iterator<Item = (&'static str, String)> + '_ {
+    Locale::ALL.iter().flat_map(move |locale| {
+        locale.discord_locales().iter().map(move |discord| (*discord, locale.tr(id, &[])))
+    })
+}
+
+/// Option builders, named in English and in the language of the catalogs having a name for them.
+trait Named {
+    fn named(&mut self, name: &str) -> &mut Self;
+}
+
+impl Named for CreateApplicationCommandOption {
+    fn named(&mut self, name: &str) -> &mut Self {
+        self.name(name);
+        let id = format!("slash-option-{name}");
+        for locale in Locale::ALL {
+            // No fallback, the English name is the one to fall back to
+            let Some(localized) = locale.message(&id, &[]) else { continue };
+            for discord in locale.discord_locales() {
+                self.name_localized(discord, &localized);
+            }
+        }
+        self
+    }
+}
+
+fn setting_option(o: &mut CreateApplicationCommandOption) -> &mut CreateApplicationCommandOption {
+    o.named("setting")
+        .describe("slash-config-setting")
+        .kind(CommandOptionType::String)
+        .required(true);
+    for key in settings::KEYS {
+        o.add_string_choice(key, key);
+    }
+    o
+}
+
+/// Register all the slash commands to Discord.
+pub async fn register(ctx: &Context) -> Result<()> {
+    let commands = Command::set_global_application_commands(&ctx.http, commands).await?;
+    tracing::info!("Registered {} slash commands", commands.len());
+    Ok(())
+}
+
+pub async fn on_interaction(ctx: &Context, interaction: Interaction) {
+    let Interaction::ApplicationCommand(cmd) = interaction else { return };
+    if let Err(e) = run_command(ctx, &cmd).await {
+        tracing::error!("{e:?}");
+        let context = report::Context {
+            command: Some(cmd.data.name.clone()),
+            guild_id: cmd.guild_id,
+            channel_id: Some(cmd.channel_id),
+            user_id: Some(cmd.user.id),
+        };
+        report::capture(&e, context);
+    }
+}
+
+/// Flatten the options of a command to the equivalent prefixed command arguments.
+fn flatten_options(options: &[CommandDataOption], msg: &mut transport::Message) {
+    for option in options {
+        match &option.resolved {
+            Some(CommandDataOptionValue::User(user, _)) => {
+                msg.mentions.push(User { id: user.id, bot: user.bot })
+            }
+            Some(CommandDataOptionValue::Integer(i)) => msg.args.push(i.to_string()),
+            Some(CommandDataOptionValue::String(s)) => msg.args.push(s.clone()),
+            _ if option.kind == CommandOptionType::SubCommand => {
+                msg.args.push(option.name.clone());
+                flatten_options(&option.options, msg);
+            }
+            _ => (),
+        }
+    }
+}
+
+impl From<&ApplicationCommandInteraction> for transport::Message {
+    fn from(cmd: &ApplicationCommandInteraction) -> Self {
+        let mut msg = Self {
+            id: MessageId(cmd.id.0),
+            guild_id: cmd.guild_id,
+            channel_id: cmd.channel_id,
+            author: User { id: cmd.user.id, bot: cmd.user.bot },
+            args: vec![],
+            mentions: vec![],
+            pictures: vec![],
+        };
+        flatten_options(&cmd.data.options, &mut msg);
+        msg
+    }
+}
+
+/// Answer right away with a private message.
+async fn respond(ctx: &Context, cmd: &ApplicationCommandInteraction, text: String) -> Result<()> {
+    cmd.create_interaction_response(&ctx.http, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|d| d.content(text).ephemeral(true))
+    })
+    .await?;
+    Ok(())
+}
+
+/// Send the outcome of a deferred command, returning the ID of the message.
+///
+/// The first follow-up replaces the deferred response, which is public. Private replies need it
+/// deleted first so that they come as a message of their own.
+async fn follow_up(
+    ctx: &Context,
+    cmd: &ApplicationCommandInteraction,
+    reply: Reply,
+    ephemeral: bool,
+) -> Result<MessageId> {
+    if ephemeral {
+        cmd.delete_original_interaction_response(&ctx.http).await?;
+    }
+    let msg = cmd
+        .create_followup_message(&ctx.http, |d| {
+            match reply {
+                Reply::Text(text) => d.content(text),
+                Reply::Embed(embed) | Reply::Paged(Paged { embed, .. }) => {
+                    if let Some(file) = &embed.attachment {
+                        d.add_file(attachment(file));
+                    }
+                    d.embed(|e| build_embed(e, embed))
+                }
+            }
+            .ephemeral(ephemeral)
+        })
+        .await?;
+    Ok(msg.id)
+}
+
+fn error_text(e: &Error, locale: Locale) -> String {
+    e.as_message(locale).unwrap_or_else(|| locale.tr("error-internal", &[]))
+}
+
+#[instrument(skip(ctx, cmd), fields(name = %cmd.data.name))]
+async fn run_command(ctx: &Context, cmd: &ApplicationCommandInteraction) -> Result<()> {
+    let env = ctx.env(cmd.guild_id).await;
+    let msg = transport::Message::from(cmd);
+    let name = cmd.data.name.as_str();
+
+    // Guilds may let anyone see the command whatever its default permissions
+    if let Err(e) = engine::check_permissions(&env, name, &msg).await {
+        respond(ctx, cmd, error_text(&e, env.locale)).await?;
+        return if e.is_internal() { Err(e) } else { Ok(()) };
+    }
+    // Commands may take longer than the few seconds Discord waits for a response
+    cmd.defer(&ctx.http).await?;
+
+    let res = match ctx.conn().await {
+        Ok(mut conn) => {
+            conn.build_transaction()
+                .serializable()
+                .run(|conn| Box::pin(engine::command(&env, conn, name, &msg)))
+                .await
+        }
+        Err(e) => Err(Error::from(e)),
+    };
+    env.audit.flush(&*env.transport, res.is_ok()).await;
+
+    match res {
+        Ok(Some(reply)) => {
+            let state = match &reply {
+                Reply::Paged(paged) => Some(paged.state.clone()),
+                _ => None,
+            };
+            let id = follow_up(ctx, cmd, reply, false).await?;
+            if let Some(state) = state {
+                engine::add_controls(&env, msg.channel_id, id, state).await?;
+            }
+        }
+        Ok(None) => {
+            let reply = Reply::Text(env.locale.tr("slash-no-game", &[]));
+            follow_up(ctx, cmd, reply, true).await?;
+        }
+        Err(e) => {
+            follow_up(ctx, cmd, Reply::Text(error_text(&e, env.locale)), true).await?;
+            if e.is_internal() {
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Names of the options of a command, recursively, along with their localized ones.
+    fn option_names(command: &Value) -> Vec<String> {
+        let options = command["options"].as_array().into_iter().flatten();
+        options
+            .flat_map(|option| {
+                let localized = option["name_localizations"].as_object().into_iter().flatten();
+                let localized = localized.map(|(_, name)| name.as_str().unwrap().to_owned());
+                [option["name"].as_str().unwrap().to_owned()]
+                    .into_iter()
+                    .chain(localized)
+                    .chain(option_names(option))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn option_names_follow_discord_rules() {
+        let mut builder = CreateApplicationCommands::default();
+        commands(&mut builder);
+        for command in &builder.0 {
+            for name in option_names(command) {
+                let valid =
+                    name.chars().all(|c| c.is_lowercase() || c.is_ascii_digit() || c == '_');
+                assert!(valid && (1..=32).contains(&name.chars().count()), "{name:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn option_names_are_localized() {
+        let mut builder = CreateApplicationCommands::default();
+        commands(&mut builder);
+        let win = builder.0.iter().find(|c| c["name"] == "win").unwrap();
+        assert_eq!(win["options"][0]["name"], "player");
+        assert_eq!(win["options"][0]["name_localizations"]["fr"], "joueur");
+        assert!(win["options"][0]["name_localizations"].get("en-US").is_none());
+    }
+}
@@ -0,0 +1,288 @@
+//! Statistics of a player, computed from the whole history of a game
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{
+    i18n::{Locale, tr},
+    models::*,
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct PlayerStats {
+    /// Points since the last reset
+    pub points: i64,
+    /// Points since the last reset as the finder of pictures, or lost by skipping
+    pub finder_points: i64,
+    /// Points since the last reset as the poster of found pictures
+    pub poster_points: i64,
+    /// Points since the beginning of the game
+    pub total_points: i64,
+    /// Pictures posted
+    pub pictures: usize,
+    /// Pictures found
+    pub finds: usize,
+    /// Turns skipped by the player or by a moderator
+    pub skips: usize,
+    /// Turns skipped by the auto-skip
+    pub auto_skips: usize,
+    /// Average time between a picture being posted and the player finding it
+    pub average_find_time: Option<TimeDelta>,
+    /// Most pictures found in a row, broken by the finds of others and by skipping one's turn
+    pub longest_streak: usize,
+    /// Player finding the pictures of this one the most, with the number of finds
+    pub favourite_finder: Option<(String, usize)>,
+}
+
+impl PlayerStats {
+    /// Compute the stats of a player from the participations of a game, oldest first.
+    pub fn new(player_id: &str, parts: &[(Participation, Option<Win>)]) -> Self {
+        let mut stats = Self::default();
+        let mut find_times = vec![];
+        let mut streak = 0;
+        let mut finders = HashMap::<&str, usize>::new();
+
+        for (part, win) in parts {
+            if let Some(win) = win.as_ref().filter(|w| w.winner_id == player_id) {
+                stats.total_points += i64::from(win.score);
+                if !win.reset {
+                    stats.finder_points += i64::from(win.score);
+                }
+            }
+            // The poster is the player of the participation, whoever gave the win
+            if let Some(win) = win.as_ref().filter(|_| part.is_win && part.player_id == player_id) {
+                stats.total_points += i64::from(win.poster_score);
+                if !win.reset {
+                    stats.poster_points += i64::from(win.poster_score);
+                }
+            }
+
+            let own = part.player_id == player_id;
+            if own && part.picture_url.is_some() {
+                stats.pictures += 1;
+            }
+            if own && part.is_skip {
+                // Giving up one's turn breaks the streak too
+                streak = 0;
+                if part.is_auto_skip {
+                    stats.auto_skips += 1;
+                } else {
+                    stats.skips += 1;
+                }
+            }
+
+            let (true, Some(win)) = (part.is_win, win) else { continue };
+            if win.winner_id == player_id {
+                stats.finds += 1;
+                streak += 1;
+                stats.longest_streak = stats.longest_streak.max(streak);
+                if let Some(won_at) = part.won_at {
+                    find_times.push(won_at - part.updated_at);
+                }
+            } else {
+                streak = 0;
+            }
+            if own {
+                *finders.entry(&win.winner_id).or_default() += 1;
+            }
+        }
+
+        stats.points = stats.finder_points + stats.poster_points;
+        if !find_times.is_empty() {
+            let total = find_times.iter().copied().sum::<TimeDelta>();
+            stats.average_find_time = Some(total / find_times.len() as i32);
+        }
+        stats.favourite_finder = finders
+            .into_iter()
+            .max_by(|(a_id, a), (b_id, b)| a.cmp(b).then(b_id.cmp(a_id)))
+            .map(|(id, count)| (id.to_owned(), count));
+        stats
+    }
+}
+
+/// Score of a player after each win changing it, oldest first, from wins sorted the same way.
+pub fn evolution(player_id: &str, wins: &[(Participation, Win)]) -> Vec<(DateTime<Utc>, i64)> {
+    let mut score = 0;
+    let mut points = vec![];
+    for (part, win) in wins {
+        let mut earned = 0;
+        if win.winner_id == player_id {
+            earned += i64::from(win.score);
+        }
+        // The poster is the player of the participation, whoever gave the win
+        if part.player_id == player_id {
+            earned += i64::from(win.poster_score);
+        }
+        if earned != 0 {
+            score += earned;
+            points.push((win.created_at, score));
+        }
+    }
+    points
+}
+
+/// Human readable duration, like `1h 05min` or `3min 12s`.
+pub fn format_duration(delta: TimeDelta, locale: Locale) -> String {
+    let secs = delta.num_seconds().max(0);
+    match (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60) {
+        (0, 0, 0, s) => tr!(locale, "duration-seconds", seconds = s),
+        (0, 0, m, s) => tr!(locale, "duration-minutes", minutes = m, seconds = format!("{s:02}")),
+        (0, h, m, _) => tr!(locale, "duration-hours", hours = h, minutes = format!("{m:02}")),
+        (d, h, _, _) => tr!(locale, "duration-days", days = d, hours = h),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// Participations of a game, an hour apart.
+    struct History(Vec<(Participation, Option<Win>)>);
+
+    impl History {
+        fn part(&mut self, poster: &str) -> Participation {
+            let at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+                + TimeDelta::hours(self.0.len() as i64);
+            Participation {
+                id: Uuid::new_v4(),
+                created_at: at,
+                player_id: poster.to_owned(),
+                is_win: false,
+                won_at: None,
+                win_id: None,
+                is_skip: false,
+                skipped_at: None,
+                picture_url: Some("https://example.com/pic.jpg".to_owned()),
+                game_id: Uuid::nil(),
+                warned_at: None,
+                updated_at: at,
+                is_auto_skip: false,
+                hints: vec![],
+                picture_id: None,
+                latitude: None,
+                longitude: None,
+                resumed_at: None,
+            }
+        }
+
+        fn win(part: &Participation, winner: &str, at: DateTime<Utc>, scores: (i32, i32)) -> Win {
+            Win {
+                id: Uuid::new_v4(),
+                created_at: at,
+                player_id: part.player_id.clone(),
+                winner_id: winner.to_owned(),
+                reset: false,
+                reset_at: None,
+                reset_id: None,
+                score: scores.0,
+                poster_score: scores.1,
+            }
+        }
+
+        /// Picture of `poster` found by `finder` after `secs` seconds.
+        fn found(&mut self, poster: &str, finder: &str, secs: i64, scores: (i32, i32)) -> &mut Win {
+            let mut part = self.part(poster);
+            let won_at = part.updated_at + TimeDelta::seconds(secs);
+            let win = Self::win(&part, finder, won_at, scores);
+            (part.is_win, part.won_at, part.win_id) = (true, Some(won_at), Some(win.id));
+            self.0.push((part, Some(win)));
+            self.0.last_mut().unwrap().1.as_mut().unwrap()
+        }
+
+        fn skipped(&mut self, poster: &str, penalty: i32, auto: bool) {
+            let mut part = self.part(poster);
+            let win =
+                (penalty != 0).then(|| Self::win(&part, poster, part.updated_at, (-penalty, 0)));
+            part.is_skip = true;
+            part.is_auto_skip = auto;
+            part.skipped_at = Some(part.updated_at);
+            part.win_id = win.as_ref().map(|win| win.id);
+            self.0.push((part, win));
+        }
+
+        fn wins(&self) -> Vec<(Participation, Win)> {
+            let wins = self.0.iter().filter_map(|(part, win)| Some((part.clone(), win.clone()?)));
+            wins.collect()
+        }
+    }
+
+    fn history() -> History {
+        let mut history = History(vec![]);
+        history.found("2", "1", 60, (100, 0)).reset = true;
+        history.found("2", "1", 180, (100, 0));
+        history.skipped("1", 100, false);
+        history.found("2", "1", 60, (100, 0));
+        history.found("3", "1", 120, (100, 0));
+        history.skipped("1", 0, true);
+        history.found("1", "2", 600, (100, 50));
+        history.found("1", "3", 600, (100, 50));
+        history.found("1", "2", 600, (100, 50));
+        history
+    }
+
+    #[test]
+    fn points_split_by_role_and_reset() {
+        let stats = PlayerStats::new("1", &history().0);
+        // The first find was reset, and a skip cost a point
+        assert_eq!(stats.finder_points, 200);
+        assert_eq!(stats.poster_points, 150);
+        assert_eq!(stats.points, 350);
+        assert_eq!(stats.total_points, 450);
+    }
+
+    #[test]
+    fn counts() {
+        let stats = PlayerStats::new("1", &history().0);
+        assert_eq!(stats.pictures, 5);
+        assert_eq!(stats.finds, 4);
+        assert_eq!((stats.skips, stats.auto_skips), (1, 1));
+        assert_eq!(stats.average_find_time, Some(TimeDelta::seconds(105)));
+
+        let stats = PlayerStats::new("4", &history().0);
+        assert_eq!((stats.pictures, stats.finds, stats.points), (0, 0, 0));
+        assert_eq!(stats.average_find_time, None);
+        assert_eq!(stats.favourite_finder, None);
+    }
+
+    #[test]
+    fn streaks_break_on_skips_and_other_finds() {
+        // Four finds in a row but for the skip
+        assert_eq!(PlayerStats::new("1", &history().0).longest_streak, 2);
+
+        let mut history = History(vec![]);
+        history.found("2", "1", 60, (100, 0));
+        history.found("3", "1", 60, (100, 0));
+        history.found("2", "1", 60, (100, 0));
+        history.found("1", "3", 60, (100, 0));
+        history.found("3", "1", 60, (100, 0));
+        assert_eq!(PlayerStats::new("1", &history.0).longest_streak, 3);
+    }
+
+    #[test]
+    fn favourite_finder() {
+        let stats = PlayerStats::new("1", &history().0);
+        assert_eq!(stats.favourite_finder, Some(("2".to_owned(), 2)));
+
+        // Ties go to the lowest ID
+        let mut history = History(vec![]);
+        history.found("1", "3", 60, (100, 0));
+        history.found("1", "2", 60, (100, 0));
+        let stats = PlayerStats::new("1", &history.0);
+        assert_eq!(stats.favourite_finder, Some(("2".to_owned(), 1)));
+    }
+
+    #[test]
+    fn evolution_follows_every_change() {
+        let wins = history().wins();
+        let scores = evolution("1", &wins).into_iter().map(|(_, score)| score).collect::<Vec<_>>();
+        assert_eq!(scores, [100, 200, 100, 200, 300, 350, 400, 450]);
+
+        let points = evolution("3", &wins);
+        assert_eq!(points, [(wins[6].1.created_at, 100)]);
+        assert_eq!(evolution("4", &wins), []);
+    }
+}
@@ -0,0 +1,460 @@
+//! In-memory storage, behaving like the postgres one without needing a database
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use uuid::Uuid;
+
+use super::{GameStore, PlayerScore};
+use crate::{error::Result, models::*, pictures::Location};
+
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStore {
+    pub games: Vec<Game>,
+    pub settings: Vec<GameSettings>,
+    pub guild_settings: Vec<GuildSettings>,
+    pub participations: Vec<Participation>,
+    pub wins: Vec<Win>,
+    pub seasons: Vec<Season>,
+    pub events: Vec<AuditEvent>,
+    pub pictures: Vec<Picture>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn game_mut(&mut self, id: Uuid) -> &mut Game {
+        self.games.iter_mut().find(|g| g.id == id).expect("unknown game")
+    }
+
+    fn part_mut(&mut self, id: Uuid) -> &mut Participation {
+        self.participations.iter_mut().find(|p| p.id == id).expect("unknown participation")
+    }
+
+    fn insert_win(&mut self, win: NewWin<'_>) -> Win {
+        let win = Win {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            player_id: win.player_id.to_owned(),
+            winner_id: win.winner_id.to_owned(),
+            reset: false,
+            reset_at: None,
+            reset_id: None,
+            score: win.score,
+            poster_score: win.poster_score,
+        };
+        self.wins.push(win.clone());
+        win
+    }
+
+    /// IDs of all the wins of a game.
+    fn win_ids(&self, game: &Game) -> Vec<Uuid> {
+        self.participations
+            .iter()
+            .filter(|p| p.game_id == game.id)
+            .filter_map(|p| p.win_id)
+            .collect()
+    }
+
+    /// Current hands of running games along with their game.
+    fn pending_parts(&self) -> impl Iterator<Item = (&Participation, &Game)> {
+        self.participations.iter().filter(|p| !p.is_win && !p.is_skip).filter_map(|p| {
+            let game = self.games.iter().find(|g| g.id == p.game_id)?;
+            (game.ended_at.is_none() && !game.is_paused()).then_some((p, game))
+        })
+    }
+}
+
+#[async_trait]
+impl GameStore for MemoryStore {
+    async fn game(&mut self, guild_id: u64, chan_id: u64) -> Result<Option<Game>> {
+        Ok(self
+            .games
+            .iter()
+            .filter(|g| g.ended_at.is_none())
+            .find(|g| g.guild_id == guild_id.to_string() && g.channel_id == chan_id.to_string())
+            .cloned())
+    }
+
+    async fn game_with_part(
+        &mut self,
+        guild_id: u64,
+        chan_id: u64,
+    ) -> Result<Option<(Game, Option<Participation>)>> {
+        let Some(game) = self.game(guild_id, chan_id).await? else { return Ok(None) };
+        let part = self
+            .participations
+            .iter()
+            .find(|p| p.game_id == game.id && !p.is_win && !p.is_skip)
+            .cloned();
+        Ok(Some((game, part)))
+    }
+
+    async fn game_by_id(&mut self, id: Uuid) -> Result<Option<Game>> {
+        Ok(self.games.iter().find(|g| g.id == id).cloned())
+    }
+
+    async fn create_game(&mut self, game: NewGame<'_>) -> Result<Game> {
+        let game = Game {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            guild_id: game.guild_id.to_owned(),
+            channel_id: game.channel_id.to_owned(),
+            creator_id: game.creator_id.to_owned(),
+            ended_at: None,
+            paused_at: None,
+        };
+        self.games.push(game.clone());
+        Ok(game)
+    }
+
+    async fn end_game(&mut self, game: &Game) -> Result<Game> {
+        let game = self.game_mut(game.id);
+        game.ended_at = Some(Utc::now());
+        Ok(game.clone())
+    }
+
+    async fn set_paused(&mut self, game: &Game, paused: bool) -> Result<Game> {
+        if !paused {
+            let now = Utc::now();
+            for part in self
+                .participations
+                .iter_mut()
+                .filter(|p| p.game_id == game.id && !p.is_win && !p.is_skip)
+            {
+                part.resumed_at = Some(now);
+                part.warned_at = None;
+            }
+        }
+        let game = self.game_mut(game.id);
+        game.paused_at = paused.then(Utc::now);
+        Ok(game.clone())
+    }
+
+    async fn settings(&mut self, game: &Game) -> Result<Option<GameSettings>> {
+        Ok(self.settings.iter().find(|s| s.game_id == game.id).cloned())
+    }
+
+    async fn save_settings(&mut self, settings: &GameSettings) -> Result<GameSettings> {
+        self.settings.retain(|s| s.game_id != settings.game_id);
+        self.settings.push(settings.clone());
+        Ok(settings.clone())
+    }
+
+    async fn guild_settings(&mut self, guild_id: u64) -> Result<Option<GuildSettings>> {
+        let guild_id = guild_id.to_string();
+        Ok(self.guild_settings.iter().find(|s| s.guild_id == guild_id).cloned())
+    }
+
+    async fn save_guild_settings(&mut self, settings: &GuildSettings) -> Result<GuildSettings> {
+        self.guild_settings.retain(|s| s.guild_id != settings.guild_id);
+        self.guild_settings.push(settings.clone());
+        Ok(settings.clone())
+    }
+
+    async fn create_participation(&mut self, part: NewParticipation<'_>) -> Result<Participation> {
+        let now = Utc::now();
+        let part = Participation {
+            id: Uuid::new_v4(),
+            created_at: now,
+            player_id: part.player_id.to_owned(),
+            is_win: false,
+            won_at: None,
+            win_id: None,
+            is_skip: false,
+            skipped_at: None,
+            picture_url: part.picture_url.map(str::to_owned),
+            game_id: *part.game_id,
+            warned_at: None,
+            updated_at: now,
+            is_auto_skip: false,
+            hints: vec![],
+            picture_id: part.picture_id,
+            latitude: part.latitude,
+            longitude: part.longitude,
+            resumed_at: None,
+        };
+        self.participations.push(part.clone());
+        Ok(part)
+    }
+
+    async fn set_picture(
+        &mut self,
+        part: &Participation,
+        url: Option<&str>,
+        picture_id: Option<Uuid>,
+        location: Option<Location>,
+    ) -> Result<Participation> {
+        let part = self.part_mut(part.id);
+        part.picture_url = url.map(str::to_owned);
+        part.picture_id = picture_id;
+        part.latitude = location.map(|l| l.latitude);
+        part.longitude = location.map(|l| l.longitude);
+        part.hints.clear();
+        part.updated_at = Utc::now();
+        Ok(part.clone())
+    }
+
+    async fn create_picture(&mut self, picture: NewPicture) -> Result<Picture> {
+        let picture = Picture {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            url: picture.url,
+            storage_key: picture.storage_key,
+            hash: picture.hash,
+            size: picture.size,
+            width: picture.width,
+            height: picture.height,
+            mime_type: picture.mime_type,
+            phash: picture.phash,
+        };
+        self.pictures.push(picture.clone());
+        Ok(picture)
+    }
+
+    async fn picture(&mut self, id: Uuid) -> Result<Option<Picture>> {
+        Ok(self.pictures.iter().find(|p| p.id == id).cloned())
+    }
+
+    async fn played_pictures(
+        &mut self,
+        game: &Game,
+    ) -> Result<Vec<(Participation, Picture, Option<Win>)>> {
+        Ok(self
+            .participations
+            .iter()
+            .filter(|p| p.game_id == game.id)
+            .sorted_by_key(|p| p.created_at)
+            .filter_map(|p| {
+                let picture = self.pictures.iter().find(|pic| Some(pic.id) == p.picture_id)?;
+                let win = p.win_id.and_then(|id| self.wins.iter().find(|w| w.id == id));
+                Some((p.clone(), picture.clone(), win.cloned()))
+            })
+            .collect())
+    }
+
+    async fn add_hint(&mut self, part: &Participation, hint: &str) -> Result<Participation> {
+        let part = self.part_mut(part.id);
+        part.hints.push(hint.to_owned());
+        Ok(part.clone())
+    }
+
+    async fn record_win(&mut self, part: &Participation, win: NewWin<'_>) -> Result<Win> {
+        let win = self.insert_win(win);
+        let part = self.part_mut(part.id);
+        part.is_win = true;
+        part.won_at = Some(Utc::now());
+        part.win_id = Some(win.id);
+        Ok(win)
+    }
+
+    async fn skip(
+        &mut self,
+        part: &Participation,
+        penalty: i32,
+        auto: bool,
+    ) -> Result<Participation> {
+        let win_id = (penalty != 0).then(|| {
+            self.insert_win(NewWin {
+                player_id: &part.player_id,
+                winner_id: &part.player_id,
+                score: -penalty,
+                poster_score: 0,
+            })
+            .id
+        });
+        let part = self.part_mut(part.id);
+        part.is_skip = true;
+        part.skipped_at = Some(Utc::now());
+        part.win_id = win_id;
+        part.is_auto_skip = auto;
+        Ok(part.clone())
+    }
+
+    async fn undo(&mut self, part: &Participation) -> Result<Participation> {
+        self.participations.retain(|p| p.game_id != part.game_id || p.is_win || p.is_skip);
+        if let Some(win_id) = part.win_id {
+            self.wins.retain(|w| w.id != win_id);
+        }
+        let part = self.part_mut(part.id);
+        part.is_win = false;
+        part.won_at = None;
+        part.win_id = None;
+        part.is_skip = false;
+        part.skipped_at = None;
+        part.is_auto_skip = false;
+        part.warned_at = None;
+        part.updated_at = Utc::now();
+        Ok(part.clone())
+    }
+
+    async fn reset(&mut self, game: &Game, reset_id: Uuid) -> Result<()> {
+        let win_ids = self.win_ids(game);
+        for win in self.wins.iter_mut().filter(|w| !w.reset && win_ids.contains(&w.id)) {
+            win.reset = true;
+            win.reset_at = Some(Utc::now());
+            win.reset_id = Some(reset_id);
+        }
+        Ok(())
+    }
+
+    async fn resets(&mut self, game: &Game) -> Result<Vec<(Uuid, DateTime<Utc>)>> {
+        let win_ids = self.win_ids(game);
+        let mut resets = HashMap::<Uuid, DateTime<Utc>>::new();
+        for win in self.wins.iter().filter(|w| w.reset && win_ids.contains(&w.id)) {
+            let (Some(id), Some(at)) = (win.reset_id, win.reset_at) else { continue };
+            let last = resets.entry(id).or_insert(at);
+            *last = (*last).max(at);
+        }
+        Ok(resets.into_iter().sorted_by_key(|(_, at)| *at).collect())
+    }
+
+    async fn cancel_reset(&mut self, game: &Game, reset_id: Uuid) -> Result<usize> {
+        let win_ids = self.win_ids(game);
+        let mut count = 0;
+        for win in self
+            .wins
+            .iter_mut()
+            .filter(|w| w.reset && w.reset_id == Some(reset_id) && win_ids.contains(&w.id))
+        {
+            win.reset = false;
+            win.reset_at = None;
+            win.reset_id = None;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    async fn scoreboard(
+        &mut self,
+        game: &Game,
+        reset_id: Option<Uuid>,
+    ) -> Result<Vec<PlayerScore>> {
+        Ok(super::tally(&self.wins(game, reset_id).await?))
+    }
+
+    async fn wins(
+        &mut self,
+        game: &Game,
+        reset_id: Option<Uuid>,
+    ) -> Result<Vec<(Participation, Win)>> {
+        Ok(self
+            .participations
+            .iter()
+            .filter(|p| p.game_id == game.id)
+            .filter_map(|p| {
+                let win = self.wins.iter().find(|w| Some(w.id) == p.win_id)?;
+                (win.reset == reset_id.is_some() && win.reset_id == reset_id)
+                    .then(|| (p.clone(), win.clone()))
+            })
+            .sorted_by_key(|(_, win)| win.created_at)
+            .collect())
+    }
+
+    async fn seasons(&mut self, game: &Game) -> Result<Vec<Season>> {
+        Ok(self
+            .seasons
+            .iter()
+            .filter(|s| s.game_id == game.id)
+            .sorted_by_key(|s| s.started_at)
+            .cloned()
+            .collect())
+    }
+
+    async fn create_season(&mut self, season: NewSeason<'_>) -> Result<Season> {
+        let season = Season {
+            id: Uuid::new_v4(),
+            game_id: *season.game_id,
+            name: season.name.to_owned(),
+            started_at: Utc::now(),
+            ended_at: None,
+        };
+        self.seasons.push(season.clone());
+        Ok(season)
+    }
+
+    async fn end_season(&mut self, season: &Season) -> Result<Season> {
+        let season = self.seasons.iter_mut().find(|s| s.id == season.id).expect("unknown season");
+        season.ended_at = Some(Utc::now());
+        Ok(season.clone())
+    }
+
+    async fn games(&mut self) -> Result<Vec<Game>> {
+        Ok(self.games.iter().filter(|g| g.ended_at.is_none()).cloned().collect())
+    }
+
+    async fn all_games(&mut self) -> Result<Vec<Game>> {
+        Ok(self.games.iter().sorted_by_key(|g| g.created_at).cloned().collect())
+    }
+
+    async fn participations(&mut self, game: &Game) -> Result<Vec<(Participation, Option<Win>)>> {
+        Ok(self
+            .participations
+            .iter()
+            .filter(|p| p.game_id == game.id)
+            .sorted_by_key(|p| p.created_at)
+            .map(|p| {
+                (p.clone(), p.win_id.and_then(|id| self.wins.iter().find(|w| w.id == id).cloned()))
+            })
+            .collect())
+    }
+
+    async fn restore_game(
+        &mut self,
+        game: &Game,
+        settings: Option<&GameSettings>,
+        seasons: &[Season],
+        participations: &[Participation],
+        wins: &[Win],
+    ) -> Result<()> {
+        self.games.push(game.clone());
+        self.settings.extend(settings.cloned());
+        self.seasons.extend_from_slice(seasons);
+        self.wins.extend_from_slice(wins);
+        self.participations.extend_from_slice(participations);
+        Ok(())
+    }
+
+    async fn record_event(&mut self, event: &NewAuditEvent) -> Result<AuditEvent> {
+        let event = AuditEvent {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            game_id: event.game_id,
+            actor_id: event.actor_id.clone(),
+            action: event.action.clone(),
+            target_id: event.target_id.clone(),
+            before_id: event.before_id,
+            after_id: event.after_id,
+            details: event.details.clone(),
+        };
+        self.events.push(event.clone());
+        Ok(event)
+    }
+
+    async fn events(
+        &mut self,
+        game: &Game,
+        offset: usize,
+        count: usize,
+    ) -> Result<Vec<AuditEvent>> {
+        let events = self.events.iter().filter(|e| e.game_id == game.id).rev();
+        Ok(events.skip(offset).take(count).cloned().collect())
+    }
+
+    async fn event_count(&mut self, game: &Game) -> Result<usize> {
+        Ok(self.events.iter().filter(|e| e.game_id == game.id).count())
+    }
+
+    async fn hands(&mut self) -> Result<Vec<(Participation, Game)>> {
+        Ok(self.pending_parts().map(|(p, g)| (p.clone(), g.clone())).collect())
+    }
+
+    async fn mark_warned(&mut self, part: &Participation) -> Result<()> {
+        self.part_mut(part.id).warned_at = Some(Utc::now());
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+//! Storage of games, participations and wins
+//!
+//! Game rules only ever talk to a [`GameStore`], so they can run against postgres in production
+//! and against the [`MemoryStore`] anywhere else.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use serenity::model::id::UserId;
+use uuid::Uuid;
+
+use crate::{error::Result, models::*, pictures::Location};
+
+mod memory;
+mod pg;
+#[cfg(test)]
+mod tests;
+
+pub use memory::MemoryStore;
+
+/// Score of a player, in hundredths of a point.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlayerScore {
+    pub player_id: String,
+    /// Points as the finder of pictures, or lost by skipping
+    pub finder: i64,
+    /// Points as the poster of found pictures
+    pub poster: i64,
+}
+
+impl PlayerScore {
+    pub fn total(&self) -> i64 {
+        self.finder + self.poster
+    }
+
+    /// Discord user of the player, failing on a stored ID that is not one.
+    pub fn player(&self) -> Result<UserId> {
+        parse_user_id(&self.player_id)
+    }
+}
+
+/// Scores per player as `(finder, poster)`, best total first.
+fn sort_scores(scores: HashMap<String, (i64, i64)>) -> Vec<PlayerScore> {
+    scores
+        .into_iter()
+        .map(|(player_id, (finder, poster))| PlayerScore { player_id, finder, poster })
+        .sorted_by(|a, b| b.total().cmp(&a.total()).then(a.player_id.cmp(&b.player_id)))
+        .collect()
+}
+
+/// Scores per player earned by the given wins, best total first.
+pub fn tally<'a>(wins: impl IntoIterator<Item = &'a (Participation, Win)>) -> Vec<PlayerScore> {
+    let mut scores = HashMap::<String, (i64, i64)>::new();
+    for (part, win) in wins {
+        scores.entry(win.winner_id.clone()).or_default().0 += i64::from(win.score);
+        if win.poster_score != 0 {
+            scores.entry(part.player_id.clone()).or_default().1 += i64::from(win.poster_score);
+        }
+    }
+    sort_scores(scores)
+}
+
+#[async_trait]
+pub trait GameStore: Send {
+    /// Game running in the given channel, if any.
+    async fn game(&mut self, guild_id: u64, chan_id: u64) -> Result<Option<Game>>;
+
+    /// Game running in the given channel along with the current hand, if anybody has it.
+    async fn game_with_part(
+        &mut self,
+        guild_id: u64,
+        chan_id: u64,
+    ) -> Result<Option<(Game, Option<Participation>)>>;
+
+    /// Game by ID, even if it ended.
+    async fn game_by_id(&mut self, id: Uuid) -> Result<Option<Game>>;
+
+    async fn create_game(&mut self, game: NewGame<'_>) -> Result<Game>;
+
+    /// Archive a game, so that the channel can host a new one.
+    async fn end_game(&mut self, game: &Game) -> Result<Game>;
+
+    /// Pause or resume a game. Resuming restarts the autoskip timer of the current hand, but
+    /// not the time it was posted at.
+    async fn set_paused(&mut self, game: &Game, paused: bool) -> Result<Game>;
+
+    async fn settings(&mut self, game: &Game) -> Result<Option<GameSettings>>;
+
+    async fn save_settings(&mut self, settings: &GameSettings) -> Result<GameSettings>;
+
+    async fn guild_settings(&mut self, guild_id: u64) -> Result<Option<GuildSettings>>;
+
+    async fn save_guild_settings(&mut self, settings: &GuildSettings) -> Result<GuildSettings>;
+
+    async fn create_participation(&mut self, part: NewParticipation<'_>) -> Result<Participation>;
+
+    /// Set (or clear) the picture of a participation along with its stored copy and location,
+    /// bumping its `updated_at`. The hints about the previous picture are dropped.
+    async fn set_picture(
+        &mut self,
+        part: &Participation,
+        url: Option<&str>,
+        picture_id: Option<Uuid>,
+        location: Option<Location>,
+    ) -> Result<Participation>;
+
+    /// Save the metadata of a stored picture.
+    async fn create_picture(&mut self, picture: NewPicture) -> Result<Picture>;
+
+    async fn picture(&mut self, id: Uuid) -> Result<Option<Picture>>;
+
+    /// Participations of the game with a stored picture, along with it and their win, oldest
+    /// first.
+    async fn played_pictures(
+        &mut self,
+        game: &Game,
+    ) -> Result<Vec<(Participation, Picture, Option<Win>)>>;
+
+    /// Record a hint about the picture of a participation.
+    async fn add_hint(&mut self, part: &Participation, hint: &str) -> Result<Participation>;
+
+    /// Save a win and mark the participation as won by it.
+    async fn record_win(&mut self, part: &Participation, win: NewWin<'_>) -> Result<Win>;
+
+    /// Skip a participation, with the player losing `penalty` hundredths of a point. `auto` is set
+    /// when skipped by the auto-skip rather than by someone.
+    async fn skip(
+        &mut self,
+        part: &Participation,
+        penalty: i32,
+        auto: bool,
+    ) -> Result<Participation>;
+
+    /// Revert the win or skip of a participation, giving the hand back to its player and dropping
+    /// the hand that followed, which is expected to have no picture yet.
+    async fn undo(&mut self, part: &Participation) -> Result<Participation>;
+
+    /// Reset all the scores of the game under the given reset ID.
+    async fn reset(&mut self, game: &Game, reset_id: Uuid) -> Result<()>;
+
+    /// All resets of the game, along with their date, oldest first.
+    async fn resets(&mut self, game: &Game) -> Result<Vec<(Uuid, DateTime<Utc>)>>;
+
+    /// Cancel a reset, returning the number of restored wins.
+    async fn cancel_reset(&mut self, game: &Game, reset_id: Uuid) -> Result<usize>;
+
+    /// Total score per player since the last reset, or frozen by the given reset, best first.
+    ///
+    /// Players earn the `score` of the wins they are the winner of, and the `poster_score` of the
+    /// wins of their participations.
+    async fn scoreboard(&mut self, game: &Game, reset_id: Option<Uuid>)
+    -> Result<Vec<PlayerScore>>;
+
+    /// Wins counted by [`GameStore::scoreboard`] along with their participation, oldest first.
+    async fn wins(
+        &mut self,
+        game: &Game,
+        reset_id: Option<Uuid>,
+    ) -> Result<Vec<(Participation, Win)>>;
+
+    /// All seasons of the game, oldest first. The current one, if any, has no `ended_at`.
+    async fn seasons(&mut self, game: &Game) -> Result<Vec<Season>>;
+
+    async fn create_season(&mut self, season: NewSeason<'_>) -> Result<Season>;
+
+    /// Mark a season as ended. Its scores are expected to have been reset with its ID.
+    async fn end_season(&mut self, season: &Season) -> Result<Season>;
+
+    /// All running games.
+    async fn games(&mut self) -> Result<Vec<Game>>;
+
+    /// All the games, ended ones included, oldest first.
+    async fn all_games(&mut self) -> Result<Vec<Game>>;
+
+    /// All participations of the game along with their win, oldest first.
+    async fn participations(&mut self, game: &Game) -> Result<Vec<(Participation, Option<Win>)>>;
+
+    /// Insert a game along with its settings and history as is, IDs and timestamps included.
+    async fn restore_game(
+        &mut self,
+        game: &Game,
+        settings: Option<&GameSettings>,
+        seasons: &[Season],
+        participations: &[Participation],
+        wins: &[Win],
+    ) -> Result<()>;
+
+    async fn record_event(&mut self, event: &NewAuditEvent) -> Result<AuditEvent>;
+
+    /// Last `count` audit events of the game, newest first.
+    async fn events(&mut self, game: &Game, offset: usize, count: usize)
+    -> Result<Vec<AuditEvent>>;
+
+    async fn event_count(&mut self, game: &Game) -> Result<usize>;
+
+    /// Current hands of all running games.
+    async fn hands(&mut self) -> Result<Vec<(Participation, Game)>>;
+
+    async fn mark_warned(&mut self, part: &Participation) -> Result<()>;
+}
@@ -0,0 +1,389 @@
+//! Postgres storage, the one used in production
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::{
+    PgExpressionMethods,
+    dsl::{not, now, sql, sum},
+    prelude::{ExpressionMethods, NullableExpressionMethods, OptionalExtension, QueryDsl},
+    sql_types::Timestamptz,
+};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use super::{GameStore, PlayerScore};
+use crate::{error::Result, models::*, pictures::Location};
+
+#[async_trait]
+impl GameStore for AsyncPgConnection {
+    async fn game(&mut self, guild_id: u64, chan_id: u64) -> Result<Option<Game>> {
+        Ok(Game::get(self, guild_id, chan_id).await?)
+    }
+
+    async fn game_with_part(
+        &mut self,
+        guild_id: u64,
+        chan_id: u64,
+    ) -> Result<Option<(Game, Option<Participation>)>> {
+        Ok(Game::get_with_part(self, guild_id, chan_id).await?)
+    }
+
+    async fn game_by_id(&mut self, id: Uuid) -> Result<Option<Game>> {
+        Ok(game::table.find(id).first(self).await.optional()?)
+    }
+
+    async fn create_game(&mut self, game: NewGame<'_>) -> Result<Game> {
+        Ok(diesel::insert_into(game::table).values(game).get_result(self).await?)
+    }
+
+    async fn end_game(&mut self, game: &Game) -> Result<Game> {
+        Ok(diesel::update(game).set(game::ended_at.eq(now)).get_result(self).await?)
+    }
+
+    async fn set_paused(&mut self, game: &Game, paused: bool) -> Result<Game> {
+        if paused {
+            return Ok(diesel::update(game).set(game::paused_at.eq(now)).get_result(self).await?);
+        }
+
+        diesel::update(participation::table)
+            .filter(participation::game_id.eq(&game.id))
+            .filter(not(participation::is_win))
+            .filter(not(participation::is_skip))
+            .set((
+                participation::resumed_at.eq(now),
+                participation::warned_at.eq::<Option<DateTime<Utc>>>(None),
+            ))
+            .execute(self)
+            .await?;
+        Ok(diesel::update(game)
+            .set(game::paused_at.eq::<Option<DateTime<Utc>>>(None))
+            .get_result(self)
+            .await?)
+    }
+
+    async fn settings(&mut self, game: &Game) -> Result<Option<GameSettings>> {
+        Ok(game_settings::table.find(game.id).first(self).await.optional()?)
+    }
+
+    async fn save_settings(&mut self, settings: &GameSettings) -> Result<GameSettings> {
+        Ok(diesel::insert_into(game_settings::table)
+            .values(settings)
+            .on_conflict(game_settings::game_id)
+            .do_update()
+            .set(settings)
+            .get_result(self)
+            .await?)
+    }
+
+    async fn guild_settings(&mut self, guild_id: u64) -> Result<Option<GuildSettings>> {
+        Ok(guild_settings::table.find(guild_id.to_string()).first(self).await.optional()?)
+    }
+
+    async fn save_guild_settings(&mut self, settings: &GuildSettings) -> Result<GuildSettings> {
+        Ok(diesel::insert_into(guild_settings::table)
+            .values(settings)
+            .on_conflict(guild_settings::guild_id)
+            .do_update()
+            .set(settings)
+            .get_result(self)
+            .await?)
+    }
+
+    async fn create_participation(&mut self, part: NewParticipation<'_>) -> Result<Participation> {
+        Ok(diesel::insert_into(participation::table).values(part).get_result(self).await?)
+    }
+
+    async fn set_picture(
+        &mut self,
+        part: &Participation,
+        url: Option<&str>,
+        picture_id: Option<Uuid>,
+        location: Option<Location>,
+    ) -> Result<Participation> {
+        Ok(diesel::update(part)
+            .set((
+                participation::picture_url.eq(url),
+                participation::picture_id.eq(picture_id),
+                participation::latitude.eq(location.map(|l| l.latitude)),
+                participation::longitude.eq(location.map(|l| l.longitude)),
+                participation::hints.eq(Vec::<String>::new()),
+                participation::updated_at.eq(now),
+            ))
+            .get_result(self)
+            .await?)
+    }
+
+    async fn create_picture(&mut self, picture: NewPicture) -> Result<Picture> {
+        Ok(diesel::insert_into(picture::table).values(picture).get_result(self).await?)
+    }
+
+    async fn picture(&mut self, id: Uuid) -> Result<Option<Picture>> {
+        Ok(picture::table.find(id).first(self).await.optional()?)
+    }
+
+    async fn played_pictures(
+        &mut self,
+        game: &Game,
+    ) -> Result<Vec<(Participation, Picture, Option<Win>)>> {
+        Ok(participation::table
+            .filter(participation::game_id.eq(&game.id))
+            .inner_join(picture::table)
+            .left_join(win::table)
+            .order_by(participation::created_at)
+            .load(self)
+            .await?)
+    }
+
+    async fn add_hint(&mut self, part: &Participation, hint: &str) -> Result<Participation> {
+        let mut hints = part.hints.clone();
+        hints.push(hint.to_owned());
+        Ok(diesel::update(part).set(participation::hints.eq(hints)).get_result(self).await?)
+    }
+
+    async fn record_win(&mut self, part: &Participation, win: NewWin<'_>) -> Result<Win> {
+        let win: Win = diesel::insert_into(win::table).values(win).get_result(self).await?;
+        diesel::update(part)
+            .set((
+                participation::is_win.eq(true),
+                participation::won_at.eq(now),
+                participation::win_id.eq(&win.id),
+            ))
+            .execute(self)
+            .await?;
+        Ok(win)
+    }
+
+    async fn skip(
+        &mut self,
+        part: &Participation,
+        penalty: i32,
+        auto: bool,
+    ) -> Result<Participation> {
+        Ok(part.skip(self, penalty, auto).await?)
+    }
+
+    async fn undo(&mut self, part: &Participation) -> Result<Participation> {
+        diesel::delete(
+            participation::table
+                .filter(participation::game_id.eq(&part.game_id))
+                .filter(not(participation::is_win))
+                .filter(not(participation::is_skip)),
+        )
+        .execute(self)
+        .await?;
+        let restored = diesel::update(part)
+            .set((
+                participation::is_win.eq(false),
+                participation::won_at.eq::<Option<DateTime<Utc>>>(None),
+                participation::win_id.eq::<Option<Uuid>>(None),
+                participation::is_skip.eq(false),
+                participation::skipped_at.eq::<Option<DateTime<Utc>>>(None),
+                participation::is_auto_skip.eq(false),
+                participation::warned_at.eq::<Option<DateTime<Utc>>>(None),
+                participation::updated_at.eq(now),
+            ))
+            .get_result(self)
+            .await?;
+        if let Some(win_id) = part.win_id {
+            diesel::delete(win::table.find(win_id)).execute(self).await?;
+        }
+        Ok(restored)
+    }
+
+    async fn reset(&mut self, game: &Game, reset_id: Uuid) -> Result<()> {
+        let win_ids = participation::table
+            .filter(participation::win_id.is_not_null())
+            .filter(participation::game_id.eq(&game.id))
+            .select(participation::win_id)
+            .load::<Option<Uuid>>(self)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        diesel::update(win::table.filter(not(win::reset)).filter(win::id.eq_any(win_ids)))
+            .set((win::reset.eq(true), win::reset_at.eq(now), win::reset_id.eq(reset_id)))
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn resets(&mut self, game: &Game) -> Result<Vec<(Uuid, DateTime<Utc>)>> {
+        Ok(win::table
+            .select((win::reset_id, sql::<Timestamptz>("max(reset_at) as rst")))
+            .inner_join(participation::table)
+            .filter(participation::game_id.eq(&game.id))
+            .filter(win::reset)
+            .group_by(win::reset_id)
+            .order_by(sql::<Timestamptz>("rst"))
+            .load::<(Option<Uuid>, DateTime<Utc>)>(self)
+            .await?
+            .into_iter()
+            .filter_map(|(id, at)| Some((id?, at)))
+            .collect())
+    }
+
+    async fn cancel_reset(&mut self, game: &Game, reset_id: Uuid) -> Result<usize> {
+        let win_ids = participation::table
+            .filter(participation::game_id.eq(&game.id))
+            .filter(participation::win_id.is_not_null())
+            .select(participation::win_id);
+        Ok(diesel::update(
+            win::table
+                .filter(win::reset)
+                .filter(win::reset_id.eq(reset_id))
+                .filter(win::id.nullable().eq_any(win_ids)),
+        )
+        .set((
+            win::reset.eq(false),
+            win::reset_at.eq::<Option<DateTime<Utc>>>(None),
+            win::reset_id.eq::<Option<Uuid>>(None),
+        ))
+        .execute(self)
+        .await?)
+    }
+
+    async fn scoreboard(
+        &mut self,
+        game: &Game,
+        reset_id: Option<Uuid>,
+    ) -> Result<Vec<PlayerScore>> {
+        let wins = win::table
+            .inner_join(participation::table)
+            .filter(win::reset.eq(reset_id.is_some()))
+            .filter(win::reset_id.is_not_distinct_from(reset_id))
+            .filter(participation::game_id.eq(&game.id));
+        let finders = wins
+            .group_by(win::winner_id)
+            .select((sum(win::score), win::winner_id))
+            .load::<(Option<i64>, String)>(self)
+            .await?;
+        let posters = wins
+            .filter(win::poster_score.ne(0))
+            .group_by(participation::player_id)
+            .select((sum(win::poster_score), participation::player_id))
+            .load::<(Option<i64>, String)>(self)
+            .await?;
+
+        let mut scores = HashMap::<String, (i64, i64)>::new();
+        for (score, id) in finders {
+            scores.entry(id).or_default().0 += score.unwrap_or_default();
+        }
+        for (score, id) in posters {
+            scores.entry(id).or_default().1 += score.unwrap_or_default();
+        }
+        Ok(super::sort_scores(scores))
+    }
+
+    async fn wins(
+        &mut self,
+        game: &Game,
+        reset_id: Option<Uuid>,
+    ) -> Result<Vec<(Participation, Win)>> {
+        Ok(participation::table
+            .inner_join(win::table)
+            .filter(win::reset.eq(reset_id.is_some()))
+            .filter(win::reset_id.is_not_distinct_from(reset_id))
+            .filter(participation::game_id.eq(&game.id))
+            .order_by(win::created_at)
+            .load(self)
+            .await?)
+    }
+
+    async fn seasons(&mut self, game: &Game) -> Result<Vec<Season>> {
+        Ok(season::table
+            .filter(season::game_id.eq(&game.id))
+            .order_by(season::started_at)
+            .load(self)
+            .await?)
+    }
+
+    async fn create_season(&mut self, season: NewSeason<'_>) -> Result<Season> {
+        Ok(diesel::insert_into(season::table).values(season).get_result(self).await?)
+    }
+
+    async fn end_season(&mut self, season: &Season) -> Result<Season> {
+        Ok(diesel::update(season).set(season::ended_at.eq(now)).get_result(self).await?)
+    }
+
+    async fn games(&mut self) -> Result<Vec<Game>> {
+        Ok(game::table.filter(game::ended_at.is_null()).load(self).await?)
+    }
+
+    async fn all_games(&mut self) -> Result<Vec<Game>> {
+        Ok(game::table.order_by(game::created_at).load(self).await?)
+    }
+
+    async fn participations(&mut self, game: &Game) -> Result<Vec<(Participation, Option<Win>)>> {
+        Ok(participation::table
+            .filter(participation::game_id.eq(&game.id))
+            .left_join(win::table)
+            .order_by(participation::created_at)
+            .load(self)
+            .await?)
+    }
+
+    async fn restore_game(
+        &mut self,
+        game: &Game,
+        settings: Option<&GameSettings>,
+        seasons: &[Season],
+        participations: &[Participation],
+        wins: &[Win],
+    ) -> Result<()> {
+        diesel::insert_into(game::table).values(game).execute(self).await?;
+        if let Some(settings) = settings {
+            diesel::insert_into(game_settings::table).values(settings).execute(self).await?;
+        }
+        diesel::insert_into(season::table).values(seasons).execute(self).await?;
+        // Participations point to their win
+        diesel::insert_into(win::table).values(wins).execute(self).await?;
+        diesel::insert_into(participation::table).values(participations).execute(self).await?;
+        Ok(())
+    }
+
+    async fn record_event(&mut self, event: &NewAuditEvent) -> Result<AuditEvent> {
+        Ok(diesel::insert_into(audit_event::table).values(event).get_result(self).await?)
+    }
+
+    async fn events(
+        &mut self,
+        game: &Game,
+        offset: usize,
+        count: usize,
+    ) -> Result<Vec<AuditEvent>> {
+        Ok(audit_event::table
+            .filter(audit_event::game_id.eq(&game.id))
+            .order_by(audit_event::created_at.desc())
+            .offset(offset as i64)
+            .limit(count as i64)
+            .load(self)
+            .await?)
+    }
+
+    async fn event_count(&mut self, game: &Game) -> Result<usize> {
+        let count: i64 = audit_event::table
+            .filter(audit_event::game_id.eq(&game.id))
+            .count()
+            .get_result(self)
+            .await?;
+        Ok(count as usize)
+    }
+
+    async fn hands(&mut self) -> Result<Vec<(Participation, Game)>> {
+        Ok(participation::table
+            .filter(not(participation::is_win))
+            .filter(not(participation::is_skip))
+            .inner_join(game::table)
+            .filter(game::ended_at.is_null())
+            .filter(game::paused_at.is_null())
+            .load(self)
+            .await?)
+    }
+
+    async fn mark_warned(&mut self, part: &Participation) -> Result<()> {
+        diesel::update(part).set(participation::warned_at.eq(now)).execute(self).await?;
+        Ok(())
+    }
+}
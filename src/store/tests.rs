@@ -0,0 +1,281 @@
+//! Behaviour expected from every [`GameStore`], checked against each backend
+//!
+//! The postgres checks run when `TEST_DATABASE_URL` points to an up to date database, each one in
+//! a transaction that is never committed.
+
+use diesel_async::{AsyncConnection, AsyncPgConnection};
+
+use super::*;
+use crate::error::Error;
+
+/// Defines each check as a test against the memory store, and another one against postgres.
+macro_rules! contract {
+    ($($check:ident),* $(,)?) => {
+        mod memory {
+            $(
+                #[tokio::test]
+                async fn $check() {
+                    super::$check(&mut super::MemoryStore::new()).await;
+                }
+            )*
+        }
+
+        mod pg {
+            $(
+                #[tokio::test]
+                async fn $check() {
+                    if let Some(mut conn) = super::pg().await {
+                        super::$check(&mut conn).await;
+                    }
+                }
+            )*
+        }
+    };
+}
+
+contract!(
+    win_passes_hand,
+    skip_costs_penalty,
+    reset_freezes_scores,
+    undo_win,
+    undo_skip,
+    scoreboard_order,
+    resume_keeps_post_time,
+);
+
+async fn pg() -> Option<AsyncPgConnection> {
+    let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("TEST_DATABASE_URL not set, skipping");
+        return None;
+    };
+    let mut conn = AsyncPgConnection::establish(&url).await.expect("Failed to connect");
+    conn.begin_test_transaction().await.expect("Failed to begin test transaction");
+    Some(conn)
+}
+
+const GUILD: u64 = 42;
+const CHANNEL: u64 = 1337;
+
+/// Fresh game where `player` has a hand, along with the picture they posted.
+async fn game_with_hand(store: &mut dyn GameStore, player: &str) -> (Game, Participation) {
+    let game = NewGame {
+        guild_id: &GUILD.to_string(),
+        channel_id: &CHANNEL.to_string(),
+        creator_id: player,
+    };
+    let game = store.create_game(game).await.unwrap();
+    let part = hand(store, &game, player, Some("https://example.com/pic.jpg")).await;
+    (game, part)
+}
+
+async fn hand(
+    store: &mut dyn GameStore,
+    game: &Game,
+    player: &str,
+    picture_url: Option<&str>,
+) -> Participation {
+    let part = NewParticipation {
+        player_id: player,
+        picture_url,
+        game_id: &game.id,
+        picture_id: None,
+        latitude: None,
+        longitude: None,
+    };
+    store.create_participation(part).await.unwrap()
+}
+
+/// Record the win of a hand by `winner` and give them the next one, like `!win` does.
+async fn win(
+    store: &mut dyn GameStore,
+    game: &Game,
+    part: &Participation,
+    winner: &str,
+    (score, poster_score): (i32, i32),
+) -> (Win, Participation) {
+    let win = NewWin { player_id: &part.player_id, winner_id: winner, score, poster_score };
+    let win = store.record_win(part, win).await.unwrap();
+    (win, hand(store, game, winner, None).await)
+}
+
+async fn current_hand(store: &mut dyn GameStore) -> Option<Participation> {
+    store.game_with_part(GUILD, CHANNEL).await.unwrap().and_then(|(_, part)| part)
+}
+
+fn score(player_id: &str, finder: i64, poster: i64) -> PlayerScore {
+    PlayerScore { player_id: player_id.to_owned(), finder, poster }
+}
+
+async fn win_passes_hand(store: &mut dyn GameStore) {
+    let (game, part) = game_with_hand(store, "1").await;
+    let (won, next) = win(store, &game, &part, "2", (100, 50)).await;
+
+    assert_eq!(current_hand(store).await.map(|p| p.id), Some(next.id));
+    let parts = store.participations(&game).await.unwrap();
+    let (part, part_win) = parts.iter().find(|(p, _)| p.id == part.id).unwrap();
+    assert!(part.is_win);
+    assert_eq!(part.win_id, Some(won.id));
+    assert_eq!(part_win.as_ref().map(|w| w.id), Some(won.id));
+    assert_eq!(
+        store.scoreboard(&game, None).await.unwrap(),
+        [score("2", 100, 0), score("1", 0, 50)]
+    );
+}
+
+async fn skip_costs_penalty(store: &mut dyn GameStore) {
+    let (game, part) = game_with_hand(store, "1").await;
+    let skipped = store.skip(&part, 50, false).await.unwrap();
+    assert!(skipped.is_skip && !skipped.is_auto_skip);
+    assert!(skipped.win_id.is_some());
+    assert_eq!(current_hand(store).await.map(|p| p.id), None);
+    assert_eq!(store.scoreboard(&game, None).await.unwrap(), [score("1", -50, 0)]);
+
+    // Nothing to lose without a penalty
+    let part = hand(store, &game, "2", None).await;
+    let skipped = store.skip(&part, 0, true).await.unwrap();
+    assert!(skipped.is_skip && skipped.is_auto_skip);
+    assert_eq!(skipped.win_id, None);
+    assert_eq!(store.scoreboard(&game, None).await.unwrap(), [score("1", -50, 0)]);
+}
+
+async fn reset_freezes_scores(store: &mut dyn GameStore) {
+    let (game, part) = game_with_hand(store, "1").await;
+    let (won, _) = win(store, &game, &part, "2", (100, 0)).await;
+
+    let reset_id = Uuid::new_v4();
+    store.reset(&game, reset_id).await.unwrap();
+    assert_eq!(store.scoreboard(&game, None).await.unwrap(), []);
+    assert_eq!(store.scoreboard(&game, Some(reset_id)).await.unwrap(), [score("2", 100, 0)]);
+    let wins = store.wins(&game, Some(reset_id)).await.unwrap();
+    assert_eq!(wins.iter().map(|(_, w)| w.id).collect_vec(), [won.id]);
+    let resets = store.resets(&game).await.unwrap();
+    assert_eq!(resets.iter().map(|(id, _)| *id).collect_vec(), [reset_id]);
+
+    assert_eq!(store.cancel_reset(&game, Uuid::new_v4()).await.unwrap(), 0);
+    assert_eq!(store.cancel_reset(&game, reset_id).await.unwrap(), 1);
+    assert_eq!(store.scoreboard(&game, None).await.unwrap(), [score("2", 100, 0)]);
+    assert_eq!(store.resets(&game).await.unwrap(), []);
+}
+
+async fn undo_win(store: &mut dyn GameStore) {
+    let (game, part) = game_with_hand(store, "1").await;
+    let (won, next) = win(store, &game, &part, "2", (100, 50)).await;
+
+    let restored = store.undo(&part).await.unwrap();
+    assert!(!restored.is_win && restored.won_at.is_none() && restored.win_id.is_none());
+    assert_eq!(restored.picture_url, part.picture_url);
+    assert_eq!(current_hand(store).await.map(|p| p.id), Some(part.id));
+    let parts = store.participations(&game).await.unwrap();
+    assert!(parts.iter().all(|(p, _)| p.id != next.id));
+    assert!(store.wins(&game, None).await.unwrap().iter().all(|(_, w)| w.id != won.id));
+    assert_eq!(store.scoreboard(&game, None).await.unwrap(), []);
+}
+
+async fn undo_skip(store: &mut dyn GameStore) {
+    let (game, part) = game_with_hand(store, "1").await;
+    let skipped = store.skip(&part, 50, true).await.unwrap();
+
+    let restored = store.undo(&skipped).await.unwrap();
+    assert!(!restored.is_skip && !restored.is_auto_skip && restored.skipped_at.is_none());
+    assert_eq!(current_hand(store).await.map(|p| p.id), Some(part.id));
+    assert_eq!(store.scoreboard(&game, None).await.unwrap(), []);
+}
+
+async fn scoreboard_order(store: &mut dyn GameStore) {
+    let (game, part) = game_with_hand(store, "1").await;
+    let (_, part) = win(store, &game, &part, "3", (100, 0)).await;
+    let part = store.set_picture(&part, Some("https://pic"), None, None).await.unwrap();
+    let (_, part) = win(store, &game, &part, "2", (100, 0)).await;
+    let part = store.set_picture(&part, Some("https://pic"), None, None).await.unwrap();
+    let (_, part) = win(store, &game, &part, "1", (200, 50)).await;
+    store.skip(&part, 100, false).await.unwrap();
+
+    // Best total first, ties by player ID
+    assert_eq!(
+        store.scoreboard(&game, None).await.unwrap(),
+        [score("2", 100, 50), score("1", 100, 0), score("3", 100, 0)]
+    );
+    let wins = store.wins(&game, None).await.unwrap();
+    assert_eq!(tally(&wins), store.scoreboard(&game, None).await.unwrap());
+}
+
+async fn resume_keeps_post_time(store: &mut dyn GameStore) {
+    let (game, part) = game_with_hand(store, "1").await;
+    store.mark_warned(&part).await.unwrap();
+
+    let game = store.set_paused(&game, true).await.unwrap();
+    assert!(game.is_paused());
+    assert!(store.hands().await.unwrap().iter().all(|(_, g)| g.id != game.id));
+
+    let game = store.set_paused(&game, false).await.unwrap();
+    assert!(!game.is_paused());
+    let hand = current_hand(store).await.unwrap();
+    assert_eq!(hand.updated_at, part.updated_at);
+    assert_eq!(hand.warned_at, None);
+    let resumed_at = hand.resumed_at.expect("Resuming restarts the timer");
+    assert_eq!(hand.timer_start(), resumed_at.max(part.updated_at));
+    assert!(store.hands().await.unwrap().iter().any(|(p, _)| p.id == hand.id));
+}
+
+fn played(player_id: &str, winner_id: &str, score: i32, poster_score: i32) -> (Participation, Win) {
+    let now = Utc::now();
+    let win = Win {
+        id: Uuid::new_v4(),
+        created_at: now,
+        player_id: player_id.to_owned(),
+        winner_id: winner_id.to_owned(),
+        reset: false,
+        reset_at: None,
+        reset_id: None,
+        score,
+        poster_score,
+    };
+    let part = Participation {
+        id: Uuid::new_v4(),
+        created_at: now,
+        player_id: player_id.to_owned(),
+        is_win: true,
+        won_at: Some(now),
+        win_id: Some(win.id),
+        is_skip: false,
+        skipped_at: None,
+        picture_url: None,
+        game_id: Uuid::new_v4(),
+        warned_at: None,
+        updated_at: now,
+        is_auto_skip: false,
+        hints: vec![],
+        picture_id: None,
+        latitude: None,
+        longitude: None,
+        resumed_at: None,
+    };
+    (part, win)
+}
+
+#[test]
+fn tally_splits_finder_and_poster() {
+    let wins = [played("1", "2", 100, 50), played("2", "1", 75, 0), played("1", "1", -50, 0)];
+    assert_eq!(tally(&wins), [score("2", 100, 0), score("1", 25, 50)]);
+    assert_eq!(tally(&[]), []);
+}
+
+#[test]
+fn sort_scores_breaks_ties_by_player() {
+    let scores = HashMap::from([
+        ("b".to_owned(), (100, 0)),
+        ("a".to_owned(), (50, 50)),
+        ("c".to_owned(), (-100, 0)),
+        ("d".to_owned(), (200, 0)),
+    ]);
+    assert_eq!(
+        sort_scores(scores),
+        [score("d", 200, 0), score("a", 50, 50), score("b", 100, 0), score("c", -100, 0)]
+    );
+}
+
+#[test]
+fn player_of_score() {
+    assert_eq!(score("42", 0, 0).player().unwrap(), UserId(42));
+    assert!(matches!(score("not an ID", 0, 0).player(), Err(Error::Db(_))));
+}
@@ -0,0 +1,158 @@
+//! Discord transport, through serenity
+
+use std::{borrow::Cow, sync::Arc};
+
+use async_trait::async_trait;
+use serenity::{
+    builder::CreateEmbed,
+    cache::Cache as SerenityCache,
+    client::Context,
+    http::Http,
+    model::prelude::{
+        AttachmentType, ChannelId, GuildId, Message as DMessage, MessageId, ReactionType, UserId,
+    },
+};
+use tracing::{Instrument, info_span};
+
+use super::*;
+use crate::{BotUserId, cache::Cache, extensions::ContextExt};
+
+#[derive(Clone)]
+pub struct DiscordTransport {
+    http: Arc<Http>,
+    cache: Arc<SerenityCache>,
+    members: Cache,
+    bot_id: Option<UserId>,
+}
+
+impl DiscordTransport {
+    pub fn new(
+        http: Arc<Http>,
+        cache: Arc<SerenityCache>,
+        members: Cache,
+        bot_id: Option<UserId>,
+    ) -> Self {
+        Self { http, cache, members, bot_id }
+    }
+
+    pub async fn from_context(ctx: &Context) -> Self {
+        let bot_id = ctx.data.read().await.get::<BotUserId>().copied();
+        Self::new(ctx.http.clone(), ctx.cache.clone(), ctx.cache().await, bot_id)
+    }
+}
+
+pub(crate) fn build_embed(e: &mut CreateEmbed, embed: Embed) -> &mut CreateEmbed {
+    if let Some(title) = embed.title {
+        e.title(title);
+    }
+    if let Some(colour) = embed.colour {
+        e.colour(colour);
+    }
+    if let Some((name, icon)) = embed.author {
+        e.author(|a| a.name(name).icon_url(icon));
+    }
+    if let Some(description) = embed.description {
+        e.description(description);
+    }
+    if let Some(file) = embed.attachment {
+        e.attachment(file.name);
+    } else if let Some(image) = embed.image {
+        e.image(image);
+    }
+    e.fields(embed.fields)
+}
+
+pub(crate) fn attachment(file: &File) -> AttachmentType<'static> {
+    AttachmentType::Bytes { data: Cow::Owned(file.data.clone()), filename: file.name.clone() }
+}
+
+#[async_trait]
+impl Transport for DiscordTransport {
+    fn bot_id(&self) -> Option<UserId> {
+        self.bot_id
+    }
+
+    async fn send(&self, channel: ChannelId, reply: Reply) -> Result<MessageId> {
+        let msg = match reply {
+            Reply::Text(text) => channel.say(&self.http, text).await?,
+            Reply::Embed(embed) | Reply::Paged(Paged { embed, .. }) => {
+                let file = embed.attachment.as_ref().map(attachment);
+                channel
+                    .send_message(&self.http, |m| {
+                        if let Some(file) = file {
+                            m.add_file(file);
+                        }
+                        m.embed(|e| build_embed(e, embed))
+                    })
+                    .await?
+            }
+        };
+        Ok(msg.id)
+    }
+
+    async fn edit(&self, channel: ChannelId, message: MessageId, reply: Reply) -> Result<()> {
+        channel
+            .edit_message(&self.http, message, |m| match reply {
+                Reply::Text(text) => m.content(text),
+                Reply::Embed(embed) | Reply::Paged(Paged { embed, .. }) => {
+                    // Pages of a view may come with another picture than the one shown
+                    m.remove_all_attachments();
+                    if let Some(file) = &embed.attachment {
+                        m.attachment(attachment(file));
+                    }
+                    m.embed(|e| build_embed(e, embed))
+                }
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn react(&self, channel: ChannelId, message: MessageId, emoji: &str) -> Result<()> {
+        let emoji = ReactionType::Unicode(emoji.to_owned());
+        Ok(channel.create_reaction(&self.http, message, emoji).await?)
+    }
+
+    async fn member(&self, guild: GuildId, user: UserId) -> Result<Member> {
+        match self.members.member(&self.http, guild, user).await {
+            Ok(member) => {
+                Ok(Member { name: member.display_name().to_string(), avatar: member.face() })
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to fetch member {user}: {e}, falling back to fetching the user. \
+                    Maybe the user left the guild?",
+                );
+                let user = self
+                    .members
+                    .user(&self.http, user)
+                    .instrument(info_span!("Cache::user"))
+                    .await?;
+                Ok(Member { avatar: user.face(), name: user.name })
+            }
+        }
+    }
+
+    async fn permissions(&self, guild: GuildId, user: UserId) -> Result<Permissions> {
+        let member = self.members.member(&self.http, guild, user).await?;
+        Ok(member.permissions(&self.cache)?)
+    }
+}
+
+impl From<&DMessage> for Message {
+    fn from(msg: &DMessage) -> Self {
+        Self {
+            id: msg.id,
+            guild_id: msg.guild_id,
+            channel_id: msg.channel_id,
+            author: User { id: msg.author.id, bot: msg.author.bot },
+            args: msg.content.split(' ').skip(1).map(str::to_owned).collect(),
+            mentions: msg.mentions.iter().map(|u| User { id: u.id, bot: u.bot }).collect(),
+            pictures: msg
+                .attachments
+                .iter()
+                .filter(|a| a.height.is_some())
+                .map(|a| a.proxy_url.clone())
+                .collect(),
+        }
+    }
+}
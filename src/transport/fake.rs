@@ -0,0 +1,265 @@
+//! Fake transport recording everything the bot says, and a scripted scenario runner on top of it
+//!
+//! ```
+//! # use bot_ticelli::transport::{Reply, fake::Scenario};
+//! # use serenity::model::Permissions;
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let mut s = Scenario::new();
+//! let alice = s.player(1, "Alice");
+//! let bob = s.player(2, "Bob");
+//! s.transport.set_permissions(alice, Permissions::ADMINISTRATOR);
+//! s.say(alice, "!start").await;
+//! s.post_picture(alice, "https://example.com/pic.jpg").await;
+//! assert_eq!(s.say(alice, "!win <@2>").await, [Reply::Text("Bravo <@2> !".to_owned())]);
+//! # });
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use async_trait::async_trait;
+use serenity::model::{
+    Permissions,
+    id::{ChannelId, GuildId, MessageId, UserId},
+};
+
+use super::*;
+use crate::{
+    config::BotConfig,
+    engine::{self, COMMANDS, Env},
+    error::ErrorResultExt,
+    i18n::Locale,
+    store::MemoryStore,
+};
+
+/// Message sent by the bot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sent {
+    pub id: MessageId,
+    pub channel: ChannelId,
+    pub reply: Reply,
+    pub reactions: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct FakeTransport {
+    members: Mutex<HashMap<UserId, (Member, bool)>>,
+    permissions: Mutex<HashMap<UserId, Permissions>>,
+    sent: Mutex<Vec<Sent>>,
+    next_id: AtomicU64,
+}
+
+pub const BOT_ID: UserId = UserId(1_000_000);
+
+impl FakeTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_member(&self, id: UserId, name: &str, bot: bool) {
+        let member = Member { name: name.to_owned(), avatar: format!("https://avatar/{id}") };
+        self.members.lock().unwrap().insert(id, (member, bot));
+    }
+
+    pub fn set_permissions(&self, id: UserId, permissions: Permissions) {
+        self.permissions.lock().unwrap().insert(id, permissions);
+    }
+
+    pub fn is_bot(&self, id: UserId) -> bool {
+        id == BOT_ID || self.members.lock().unwrap().get(&id).is_some_and(|(_, bot)| *bot)
+    }
+
+    /// Everything the bot sent so far, edits included.
+    pub fn sent(&self) -> Vec<Sent> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    pub fn next_message_id(&self) -> MessageId {
+        MessageId(self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+}
+
+#[async_trait]
+impl Transport for FakeTransport {
+    fn bot_id(&self) -> Option<UserId> {
+        Some(BOT_ID)
+    }
+
+    async fn send(&self, channel: ChannelId, reply: Reply) -> Result<MessageId> {
+        let id = self.next_message_id();
+        self.sent.lock().unwrap().push(Sent { id, channel, reply, reactions: vec![] });
+        Ok(id)
+    }
+
+    async fn edit(&self, _channel: ChannelId, message: MessageId, reply: Reply) -> Result<()> {
+        if let Some(sent) = self.sent.lock().unwrap().iter_mut().find(|s| s.id == message) {
+            sent.reply = reply;
+        }
+        Ok(())
+    }
+
+    async fn react(&self, _channel: ChannelId, message: MessageId, emoji: &str) -> Result<()> {
+        if let Some(sent) = self.sent.lock().unwrap().iter_mut().find(|s| s.id == message) {
+            sent.reactions.push(emoji.to_owned());
+        }
+        Ok(())
+    }
+
+    async fn member(&self, _guild: GuildId, user: UserId) -> Result<Member> {
+        let members = self.members.lock().unwrap();
+        let member = members.get(&user).map(|(m, _)| m.clone());
+        Ok(member.unwrap_or_else(|| Member {
+            name: user.to_string(),
+            avatar: format!("https://avatar/{user}"),
+        }))
+    }
+
+    async fn permissions(&self, _guild: GuildId, user: UserId) -> Result<Permissions> {
+        Ok(self.permissions.lock().unwrap().get(&user).copied().unwrap_or_default())
+    }
+}
+
+/// Single channel game, played by scripted players having no permissions until given some.
+pub struct Scenario {
+    pub env: Env,
+    pub transport: Arc<FakeTransport>,
+    pub store: MemoryStore,
+    pub guild: GuildId,
+    pub channel: ChannelId,
+    pub prefix: String,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        let transport = Arc::new(FakeTransport::new());
+        let env = Env {
+            transport: transport.clone(),
+            config: Arc::new(BotConfig {
+                command_prefix: "!".to_owned(),
+                win_sentences: vec!["Bravo {} !".to_owned()],
+                ratelimit: None,
+                auto_skip: None,
+                season_rollover: None,
+                locale: Locale::default(),
+            }),
+            pictures: None,
+            pages: Arc::default(),
+            locale: Locale::default(),
+            audit: Arc::default(),
+        };
+        Self {
+            env,
+            transport,
+            store: MemoryStore::new(),
+            guild: GuildId(1),
+            channel: ChannelId(1),
+            prefix: "!".to_owned(),
+        }
+    }
+
+    pub fn player(&mut self, id: u64, name: &str) -> UserId {
+        self.transport.add_member(UserId(id), name, false);
+        UserId(id)
+    }
+
+    pub fn bot(&mut self, id: u64, name: &str) -> UserId {
+        self.transport.add_member(UserId(id), name, true);
+        UserId(id)
+    }
+
+    fn message(&self, author: UserId, content: &str, pictures: Vec<String>) -> Message {
+        let mentions = content
+            .split(' ')
+            .filter_map(|word| word.strip_prefix("<@")?.strip_suffix('>'))
+            .filter_map(|id| id.trim_start_matches('!').parse().ok())
+            .map(|id| User { id: UserId(id), bot: self.transport.is_bot(UserId(id)) })
+            .collect();
+        Message {
+            id: self.transport.next_message_id(),
+            guild_id: Some(self.guild),
+            channel_id: self.channel,
+            author: User { id: author, bot: self.transport.is_bot(author) },
+            args: content.split(' ').skip(1).map(str::to_owned).collect(),
+            mentions,
+            pictures,
+        }
+    }
+
+    /// Replies sent to the game channel since the bot had sent `count` messages.
+    fn replies_since(&self, count: usize) -> Vec<Reply> {
+        let sent = self.transport.sent().into_iter().skip(count);
+        sent.filter(|s| s.channel == self.channel).map(|s| s.reply).collect()
+    }
+
+    async fn handle(&mut self, content: &str, msg: Message) -> Vec<Reply> {
+        let before = self.transport.sent().len();
+        let env = self.env.for_guild(&mut self.store, msg.guild_id).await.unwrap();
+        let name = content
+            .strip_prefix(&self.prefix)
+            .and_then(|c| c.split(' ').next())
+            .filter(|name| COMMANDS.contains(name));
+
+        let res = match name {
+            Some(name) if msg.pictures.is_empty() => {
+                match engine::check_permissions(&env, name, &msg).await {
+                    Ok(()) => {
+                        let res = engine::command(&env, &mut self.store, name, &msg).await;
+                        env.audit.flush(&*env.transport, res.is_ok()).await;
+                        res
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            _ => match engine::save_picture(&env, &mut self.store, &msg).await {
+                Ok(saved) => engine::on_message(&env, &mut self.store, &msg, saved.as_ref())
+                    .await
+                    .map(|reply| reply.map(Reply::Text)),
+                Err(e) => Err(e),
+            },
+        };
+        if let Ok(Some(reply)) = res.handle_err(&msg.channel_id, &env).await {
+            engine::send(&env, &msg, reply).await.unwrap();
+        }
+        self.replies_since(before)
+    }
+
+    /// Post a message, returning the replies of the bot.
+    pub async fn say(&mut self, author: UserId, content: &str) -> Vec<Reply> {
+        let msg = self.message(author, content, vec![]);
+        self.handle(content, msg).await
+    }
+
+    /// Post a picture, returning the replies of the bot.
+    pub async fn post_picture(&mut self, author: UserId, url: &str) -> Vec<Reply> {
+        let msg = self.message(author, "", vec![url.to_owned()]);
+        self.handle("", msg).await
+    }
+
+    /// React to a message of the bot, returning the error replies if any. Edits are visible
+    /// through [`FakeTransport::sent`].
+    pub async fn react(&mut self, user: UserId, message: MessageId, emoji: &str) -> Vec<Reply> {
+        let before = self.transport.sent().len();
+        let react = Reaction {
+            guild_id: Some(self.guild),
+            channel_id: self.channel,
+            message_id: message,
+            user_id: Some(user),
+            emoji: emoji.to_owned(),
+        };
+        let env = self.env.for_guild(&mut self.store, react.guild_id).await.unwrap();
+        let res = engine::on_reaction(&env, &mut self.store, &react).await;
+        let _ = res.handle_err(&react.channel_id, &env).await;
+        self.replies_since(before)
+    }
+}
@@ -0,0 +1,116 @@
+//! What the game engine needs from a chat platform: events coming in, replies going out, and a
+//! way to know who is who.
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+
+use async_trait::async_trait;
+use serenity::{
+    model::{
+        Permissions,
+        id::{ChannelId, GuildId, MessageId, UserId},
+        mention::Mention,
+    },
+    utils::Colour,
+};
+
+use crate::{error::Result, paging::Paged};
+
+pub mod discord;
+pub mod fake;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct User {
+    pub id: UserId,
+    pub bot: bool,
+}
+
+impl From<User> for Mention {
+    fn from(user: User) -> Self {
+        user.id.into()
+    }
+}
+
+/// Incoming message.
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub id: MessageId,
+    pub guild_id: Option<GuildId>,
+    pub channel_id: ChannelId,
+    pub author: User,
+    /// Words following the command name, if the message is a command
+    pub args: Vec<String>,
+    pub mentions: Vec<User>,
+    /// URLs of the pictures attached to the message
+    pub pictures: Vec<String>,
+}
+
+/// Incoming reaction on a message.
+#[derive(Clone, Debug)]
+pub struct Reaction {
+    pub guild_id: Option<GuildId>,
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    pub user_id: Option<UserId>,
+    pub emoji: String,
+}
+
+/// Outgoing message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reply {
+    Text(String),
+    Embed(Embed),
+    /// Embed browsable with reactions
+    Paged(Paged),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Embed {
+    pub title: Option<String>,
+    pub colour: Option<Colour>,
+    /// Name and icon URL of the author
+    pub author: Option<(String, String)>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    /// Picture uploaded along with the embed, shown instead of `image`
+    pub attachment: Option<File>,
+    /// Name, value and inline flag of each field
+    pub fields: Vec<(String, String, bool)>,
+}
+
+/// File uploaded along with a message.
+#[derive(Clone, PartialEq)]
+pub struct File {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+// Not dumping megabytes of pictures in the logs
+impl Debug for File {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "File({:?}, {} bytes)", self.name, self.data.len())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Member {
+    pub name: String,
+    pub avatar: String,
+}
+
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// ID of the bot itself, if known yet.
+    fn bot_id(&self) -> Option<UserId>;
+
+    async fn send(&self, channel: ChannelId, reply: Reply) -> Result<MessageId>;
+
+    async fn edit(&self, channel: ChannelId, message: MessageId, reply: Reply) -> Result<()>;
+
+    async fn react(&self, channel: ChannelId, message: MessageId, emoji: &str) -> Result<()>;
+
+    /// Display name and avatar of a guild member, even if they left the guild.
+    async fn member(&self, guild: GuildId, user: UserId) -> Result<Member>;
+
+    /// Guild-wide permissions of a member.
+    async fn permissions(&self, guild: GuildId, user: UserId) -> Result<Permissions>;
+}
@@ -0,0 +1,214 @@
+//! Games played by scripted players through the fake transport, checking every reply of the bot
+
+use bot_ticelli::transport::{Embed, Reply, fake::Scenario};
+use serenity::model::{
+    Permissions,
+    id::{ChannelId, UserId},
+};
+
+fn text(text: &str) -> Vec<Reply> {
+    vec![Reply::Text(text.to_owned())]
+}
+
+/// Embed of the only reply.
+fn embed(replies: Vec<Reply>) -> Embed {
+    let [reply] = &replies[..] else { panic!("Expected a single reply, got {replies:?}") };
+    match reply {
+        Reply::Embed(embed) => embed.clone(),
+        Reply::Paged(paged) => paged.embed.clone(),
+        Reply::Text(text) => panic!("Expected an embed, got {text:?}"),
+    }
+}
+
+/// Fields of the embed of the only reply, as `(name, value)`.
+fn fields(replies: Vec<Reply>) -> Vec<(String, String)> {
+    let embed = embed(replies);
+    embed.fields.iter().map(|(name, value, _)| (name.clone(), value.clone())).collect()
+}
+
+/// Game started by Alice, an admin who has the hand with a picture, Bob and Carol being around.
+async fn started() -> (Scenario, UserId, UserId, UserId) {
+    let mut s = Scenario::new();
+    let alice = s.player(1, "Alice");
+    let bob = s.player(2, "Bob");
+    let carol = s.player(3, "Carol");
+    s.transport.set_permissions(alice, Permissions::ADMINISTRATOR);
+    assert_eq!(s.say(alice, "!start").await, text("Partie démarrée !"));
+    assert_eq!(
+        s.post_picture(alice, "https://example.com/1.jpg").await,
+        text("🔎 À vos claviers, une nouvelle photo est à trouver")
+    );
+    (s, alice, bob, carol)
+}
+
+#[tokio::test]
+async fn win_then_show() {
+    let (mut s, alice, bob, carol) = started().await;
+
+    assert_eq!(
+        s.say(carol, "!win <@2>").await,
+        text("❌ Tut tut tut, c'est pas toi qui a la main...")
+    );
+    assert_eq!(s.say(alice, "!win <@2>").await, text("Bravo <@2> !"));
+    assert_eq!(
+        s.post_picture(alice, "https://example.com/2.jpg").await,
+        [],
+        "Only the one with the hand can post"
+    );
+    assert_eq!(
+        s.post_picture(bob, "https://example.com/2.jpg").await,
+        text("🔎 À vos claviers, une nouvelle photo est à trouver")
+    );
+    assert_eq!(s.say(bob, "!win <@3>").await, text("Bravo <@3> !"));
+
+    assert_eq!(
+        fields(s.say(alice, "!show").await),
+        [("🥇. Bob".to_owned(), "1".to_owned()), ("🥈. Carol".to_owned(), "1".to_owned())]
+    );
+}
+
+#[tokio::test]
+async fn skip() {
+    let (mut s, alice, bob, _) = started().await;
+
+    assert_eq!(s.say(bob, "!skip").await, text("❌ Tut tut tut, c'est pas toi qui a la main..."));
+    assert_eq!(s.say(alice, "!skip").await, text("A vos photos, <@1> passe la main !"));
+    assert_eq!(s.say(alice, "!skip").await, text("⁉️ Mais personne n'a la main ..."));
+    // Skipping costs a point
+    assert_eq!(fields(s.say(bob, "!show").await), [("🥇. Alice".to_owned(), "-1".to_owned())]);
+
+    // Anyone can take the free hand
+    assert_eq!(
+        s.post_picture(bob, "https://example.com/2.jpg").await,
+        text("🔎 À vos claviers, une nouvelle photo est à trouver")
+    );
+    assert_eq!(s.say(bob, "!win <@1>").await, text("Bravo <@1> !"));
+    assert_eq!(fields(s.say(bob, "!show").await), [("🥇. Alice".to_owned(), "0".to_owned())]);
+}
+
+#[tokio::test]
+async fn reset() {
+    let (mut s, alice, bob, _) = started().await;
+    assert_eq!(s.say(alice, "!win <@2>").await, text("Bravo <@2> !"));
+
+    assert_eq!(s.say(alice, "!reset").await, text("Pour confirmer le reset, envoie `!reset do`."));
+    assert_eq!(fields(s.say(alice, "!show").await), [("🥇. Bob".to_owned(), "1".to_owned())]);
+
+    let [Reply::Text(done)] = &s.say(alice, "!reset do").await[..] else { panic!() };
+    let id = done.strip_prefix("Scores reset avec ID ").expect(done);
+    assert_eq!(fields(s.say(alice, "!show").await), []);
+    // The current hand was dropped along with the scores
+    assert_eq!(s.say(bob, "!skip").await, text("⁉️ Mais personne n'a la main ..."));
+
+    assert_eq!(
+        s.say(alice, &format!("!reset cancel {id}")).await,
+        text(&format!("Reset {id} annulé"))
+    );
+    assert_eq!(fields(s.say(alice, "!show").await), [("🥇. Bob".to_owned(), "1".to_owned())]);
+}
+
+#[tokio::test]
+async fn undo_needs_permission() {
+    let (mut s, alice, bob, carol) = started().await;
+    assert_eq!(s.say(alice, "!win <@2>").await, text("Bravo <@2> !"));
+
+    // Only the one who gave the win can take it back on their own
+    assert_eq!(
+        s.say(carol, "!undo").await,
+        text("❌ Seul l'auteur peut annuler, et pas trop longtemps après. Demande à un modo !")
+    );
+    assert_eq!(fields(s.say(bob, "!show").await), [("🥇. Bob".to_owned(), "1".to_owned())]);
+
+    s.transport.set_permissions(carol, Permissions::KICK_MEMBERS);
+    assert_eq!(
+        s.say(carol, "!undo").await,
+        text("↩️ Victoire de <@2> annulée, <@1> reprend la main avec sa photo.")
+    );
+    assert_eq!(fields(s.say(bob, "!show").await), []);
+    assert_eq!(s.say(bob, "!skip").await, text("❌ Tut tut tut, c'est pas toi qui a la main..."));
+}
+
+#[tokio::test]
+async fn admin_commands_need_permission() {
+    let (mut s, alice, bob, carol) = started().await;
+    let refused = text("🔒 Tu n'as pas les droits pour cette commande.");
+
+    assert_eq!(s.say(bob, "!reset do").await, refused);
+    assert_eq!(s.say(bob, "!force_skip").await, refused);
+    assert_eq!(s.say(bob, "!stop").await, refused);
+    assert_eq!(s.say(alice, "!win <@2>").await, text("Bravo <@2> !"));
+    assert_eq!(fields(s.say(bob, "!show").await), [("🥇. Bob".to_owned(), "1".to_owned())]);
+
+    // Moderators can skip, but not reset
+    s.transport.set_permissions(carol, Permissions::KICK_MEMBERS);
+    assert_eq!(
+        s.say(carol, "!force_skip").await,
+        text("A vos photos, <@2> n'a plus la main, on y a coupé court !")
+    );
+    assert_eq!(s.say(carol, "!reset do").await, refused);
+    assert_eq!(fields(s.say(bob, "!show").await), [("🥇. Bob".to_owned(), "0".to_owned())]);
+}
+
+#[tokio::test]
+async fn undo_keeps_next_picture() {
+    let (mut s, alice, bob, _) = started().await;
+    let too_late = text("⏩ Trop tard, la photo suivante est déjà postée.");
+
+    assert_eq!(s.say(alice, "!win <@2>").await, text("Bravo <@2> !"));
+    assert_eq!(
+        s.post_picture(bob, "https://example.com/2.jpg").await,
+        text("🔎 À vos claviers, une nouvelle photo est à trouver")
+    );
+    assert_eq!(s.say(alice, "!undo").await, too_late);
+    assert_eq!(s.store.participations.len(), 2);
+    assert_eq!(s.say(bob, "!win <@1>").await, text("Bravo <@1> !"));
+
+    // Same once somebody took the hand left by a skip
+    assert_eq!(
+        s.post_picture(alice, "https://example.com/3.jpg").await,
+        text("🔎 À vos claviers, une nouvelle photo est à trouver")
+    );
+    assert_eq!(s.say(alice, "!skip").await, text("A vos photos, <@1> passe la main !"));
+    assert_eq!(
+        s.post_picture(bob, "https://example.com/4.jpg").await,
+        text("🔎 À vos claviers, une nouvelle photo est à trouver")
+    );
+    assert_eq!(s.say(alice, "!undo").await, too_late);
+    assert_eq!(s.store.participations.len(), 4);
+}
+
+#[tokio::test]
+async fn scoreboard_image() {
+    let (mut s, alice, _, _) = started().await;
+    assert_eq!(s.say(alice, "!win <@2>").await, text("Bravo <@2> !"));
+    s.say(alice, "!config set scoreboard-image on").await;
+
+    let file = embed(s.say(alice, "!show").await).attachment.expect("Expected a picture");
+    assert_eq!(file.name, "scoreboard-1.png");
+    assert!(file.data.starts_with(b"\x89PNG"));
+}
+
+#[tokio::test]
+async fn chart() {
+    let (mut s, alice, bob, _) = started().await;
+    assert_eq!(s.say(bob, "!chart").await, text("Aucun point marqué sur cette période"));
+    assert_eq!(s.say(alice, "!win <@2>").await, text("Bravo <@2> !"));
+
+    let file = embed(s.say(bob, "!chart").await).attachment.expect("Expected a picture");
+    assert_eq!(file.name, "chart.png");
+    assert!(file.data.starts_with(b"\x89PNG"));
+}
+
+#[tokio::test]
+async fn audit_mirror() {
+    let (mut s, alice, _, _) = started().await;
+    s.say(alice, "!config set audit-channel <#99>").await;
+    let before = s.transport.sent().len();
+
+    assert_eq!(s.say(alice, "!win <@2>").await, text("Bravo <@2> !"));
+    let mirrors =
+        s.transport.sent().into_iter().skip(before).filter(|m| m.channel == ChannelId(99));
+    let [mirror] = &mirrors.collect::<Vec<_>>()[..] else { panic!("Expected a single mirror") };
+    let fields = fields(vec![mirror.reply.clone()]);
+    assert!(fields[1].0.ends_with("Victoire"), "{fields:?}");
+}